@@ -2,6 +2,7 @@
 
 use hyperon::*;
 use hyperon::space::DynSpace;
+use hyperon::space::grounding::GroundingSpace;
 use hyperon::atom::serial;
 
 use crate::util::*;
@@ -41,6 +42,21 @@ pub enum atom_type_t {
 ///
 pub type c_atom_callback_t = extern "C" fn(atom: atom_ref_t, context: *mut c_void);
 
+/// @brief Function signature for a callback providing access to an atom and its nesting depth
+/// @ingroup atom_group
+/// @param[in]  atom  A reference to the atom.  This atom should not be modified or freed by the callback.
+/// @param[in]  depth  The nesting depth of the atom.  The top-level atom has depth 0, its children have depth 1, etc.
+/// @param[in]  context  The context state pointer initially passed to the upstream function initiating the callback.
+///
+pub type c_atom_depth_callback_t = extern "C" fn(atom: atom_ref_t, depth: usize, context: *mut c_void);
+
+/// @brief Function signature for a callback providing access to a name
+/// @ingroup atom_group
+/// @param[in]  name  A NULL-terminated string containing the name.  The string is valid only within the callback.
+/// @param[in]  context  The context state pointer initially passed to the upstream function initiating the callback.
+///
+pub type c_name_callback_t = extern "C" fn(name: *const c_char, context: *mut c_void);
+
 //Implementation Notes: both `atom_t` and `atom_ref_t` are transparent wrappers around a RustAtom,
 // which internally knows whether it owns or borrows the native `Atom` struct.  The reason for this
 // design choice is because at allows a pointer to `atom_ref` to be used interchangeably with a
@@ -236,10 +252,83 @@ pub unsafe extern "C" fn atom_expr_from_vec(children: atom_vec_t) -> atom_t {
     Atom::expr(children).into()
 }
 
+/// @brief Accumulates children of an Expression atom being constructed incrementally
+/// @ingroup atom_group
+/// @note `expr_builder_t` must be either finished with `expr_builder_finish()` or freed
+/// with `expr_builder_free()`
+///
+#[repr(C)]
+pub struct expr_builder_t {
+    /// Internal.  Should not be accessed directly
+    builder: *mut RustExprBuilder,
+}
+
+// Internal wrapper type so CBindgen doesn't try and export Vec<Atom>
+struct RustExprBuilder(Vec<Atom>);
+
+impl expr_builder_t {
+    fn borrow_mut(&mut self) -> &mut Vec<Atom> {
+        unsafe{ &mut (*self.builder).0 }
+    }
+    fn into_inner(self) -> Vec<Atom> {
+        unsafe{ *Box::from_raw(self.builder) }.0
+    }
+}
+
+/// @brief Creates a new `expr_builder_t` containing no children
+/// @ingroup atom_group
+/// @return The newly created `expr_builder_t`
+/// @note The caller must take ownership responsibility for the returned `expr_builder_t`, and
+/// either finish it with `expr_builder_finish()` or free it with `expr_builder_free()`
+///
+#[no_mangle]
+pub extern "C" fn expr_builder_new() -> expr_builder_t {
+    expr_builder_t{ builder: Box::into_raw(Box::new(RustExprBuilder(Vec::new()))) }
+}
+
+/// @brief Appends a child atom to the Expression being built
+/// @ingroup atom_group
+/// @param[in]  builder  A pointer to the `expr_builder_t` to append the child to
+/// @param[in]  atom  The child atom to append
+/// @warning This function takes ownership of the supplied `atom_t`, and it must not be subsequently accessed or freed
+///
+#[no_mangle]
+pub extern "C" fn expr_builder_push(builder: *mut expr_builder_t, atom: atom_t) {
+    let builder = unsafe{ &mut *builder };
+    builder.borrow_mut().push(atom.into_inner());
+}
+
+/// @brief Creates an Expression atom from the children appended to the builder
+/// @ingroup atom_group
+/// @param[in]  builder  The `expr_builder_t` to finish
+/// @return An `atom_t` for the Expression atom, containing all appended children in order
+/// @note The caller must take ownership responsibility for the returned `atom_t`
+/// @warning This function takes ownership of the `builder`, so it must not be subsequently accessed or freed
+///
+#[no_mangle]
+pub extern "C" fn expr_builder_finish(builder: expr_builder_t) -> atom_t {
+    Atom::expr(builder.into_inner()).into()
+}
+
+/// @brief Frees an `expr_builder_t` without creating an Expression, along with all children appended to it
+/// @ingroup atom_group
+/// @param[in]  builder  The `expr_builder_t` to free
+///
+#[no_mangle]
+pub extern "C" fn expr_builder_free(builder: expr_builder_t) {
+    let children = builder.into_inner();
+    drop(children);
+}
+
 /// @brief Create a new Variable atom with the specified name
 /// @ingroup atom_group
-/// @param[in]  name  The name for the newly created Variable atom
+/// @param[in]  name  The name for the newly created Variable atom, without the leading `$`
 /// @return An `atom_t` for the Variable atom
+/// @note The `$` sigil is not a part of the name: `atom_var("x")` creates the variable rendered as `$x`
+///    by `atom_to_str()`, and passing "$x" creates a variable rendered as `$$x`.  The name must not
+///    contain the `#` character, which is reserved for the internal variable ids, see `atom_var_parse_name()`
+/// @note Variables with equal names created by this function are equal, so they can be used to connect
+///    different parts of a query, for example `(, (A $x) ($x B))` passed to `space_query()`
 /// @note The caller must take ownership responsibility for the returned `atom_t`
 ///
 #[no_mangle]
@@ -297,12 +386,66 @@ pub unsafe extern "C" fn atom_free(atom: atom_t) {
 /// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to clone
 /// @return A newly created `atom_t` for the cloned atom
 /// @note The caller must take ownership responsibility for the returned `atom_t`
+/// @note Grounded atoms are copied using the clone implementation of the grounded value, which may
+///    share the state with the original atom.  For example a cloned Space atom refers to the same
+///    Space as the original atom.  Use `atom_deep_clone()` to get a copy which doesn't share the state
 ///
 #[no_mangle]
 pub extern "C" fn atom_clone(atom: *const atom_ref_t) -> atom_t {
     unsafe{ &*atom }.borrow().clone().into()
 }
 
+/// @brief Makes a copy of an atom which doesn't share any state with the original atom
+/// @ingroup atom_group
+/// @see atom_clone
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to clone
+/// @return A newly created `atom_t` for the cloned atom
+/// @note Unlike `atom_clone()`, Space atoms found inside the atom are replaced by atoms referring to
+///    independent copies of the Spaces, so modifying the copy doesn't modify the original Space.
+///    Spaces which cannot be copied (for example Spaces implemented in C) and other grounded atoms are
+///    copied using the clone implementation of the grounded value
+/// @note The caller must take ownership responsibility for the returned `atom_t`
+///
+#[no_mangle]
+pub extern "C" fn atom_deep_clone(atom: *const atom_ref_t) -> atom_t {
+    deep_clone(unsafe{ &*atom }.borrow()).into()
+}
+
+fn deep_clone(atom: &Atom) -> Atom {
+    match atom {
+        Atom::Expression(expr) => Atom::expr(expr.children().iter().map(deep_clone).collect::<Vec<_>>()),
+        Atom::Grounded(_) => {
+            let copy = atom.as_gnd::<DynSpace>().and_then(|space| {
+                space.borrow().as_any()
+                    .and_then(|space| space.downcast_ref::<GroundingSpace>())
+                    .map(|space| DynSpace::new(space.clone()))
+            });
+            match copy {
+                Some(space) => Atom::gnd(space),
+                None => atom.clone(),
+            }
+        },
+        _ => atom.clone(),
+    }
+}
+
+/// @brief Computes a hash value of an atom
+/// @ingroup atom_group
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to hash
+/// @return The hash value of the atom
+/// @note Equal atoms have equal hashes, but equal hashes don't mean the atoms are equal.  Grounded atoms
+///    are hashed by the type of the grounded value only, so all grounded atoms of the same type have equal
+///    hashes.  The hash value is stable within a single run of the program, but it is not guaranteed to be
+///    the same for different runs or versions of the library
+///
+#[no_mangle]
+pub extern "C" fn atom_hash(atom: *const atom_ref_t) -> u64 {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+    let mut hasher = DefaultHasher::new();
+    unsafe{ &*atom }.borrow().hash(&mut hasher);
+    hasher.finish()
+}
+
 /// @brief Checks if two atom objects represent the same conceptual atom
 /// @ingroup atom_group
 /// @param[in]  a  A pointer to an `atom_t` or an `atom_ref_t` representing the first atom
@@ -319,6 +462,7 @@ pub unsafe extern "C" fn atom_eq(a: *const atom_ref_t, b: *const atom_ref_t) ->
 /// @param[in]  a  A pointer to an `atom_t` or an `atom_ref_t` representing the first atom
 /// @param[in]  b  A pointer to an `atom_t` or an `atom_ref_t` representing the second atom
 /// @return `true` if the atoms can be converted to each other by renaming variables, otherwise `false`
+/// @see atom_alpha_eq
 ///
 #[no_mangle]
 pub extern "C" fn atoms_are_equivalent(a: *const atom_ref_t, b: *const atom_ref_t) -> bool {
@@ -327,6 +471,50 @@ pub extern "C" fn atoms_are_equivalent(a: *const atom_ref_t, b: *const atom_ref_
     crate::atom::matcher::atoms_are_equivalent(a, b)
 }
 
+/// @brief Renames variables of an atom to a canonical sequence `$_0`, `$_1`, ... in order of their first occurrence
+/// @ingroup atom_group
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to canonicalize
+/// @return The `atom_t` with the variables renamed
+/// @note Alpha equivalent atoms have equal canonical forms, so `atom_eq()` and `atom_hash()` of the canonical
+///    forms can be used to deduplicate atoms which differ only by the names of variables.  Use
+///    `atoms_are_equivalent()` to compare two atoms directly
+/// @note The caller must take ownership responsibility for the returned `atom_t`
+///
+#[no_mangle]
+pub extern "C" fn atom_alpha_canonical(atom: *const atom_ref_t) -> atom_t {
+    let atom = unsafe{ &*atom }.borrow();
+    alpha_canonical(atom).into()
+}
+
+/// @brief Checks if two atoms are alpha equivalent by comparing their canonical forms
+/// @ingroup atom_group
+/// @param[in]  a  A pointer to an `atom_t` or an `atom_ref_t` representing the first atom
+/// @param[in]  b  A pointer to an `atom_t` or an `atom_ref_t` representing the second atom
+/// @return `true` if the atoms differ only by the names of variables, otherwise `false`
+/// @note The result is the same as `atoms_are_equivalent()` returns.  This function is provided to pair
+///    with `atom_alpha_canonical()`: `atom_alpha_eq(a, b)` is `true` exactly when the canonical forms of
+///    `a` and `b` are equal according to `atom_eq()`
+///
+#[no_mangle]
+pub extern "C" fn atom_alpha_eq(a: *const atom_ref_t, b: *const atom_ref_t) -> bool {
+    let a = unsafe{ &*a }.borrow();
+    let b = unsafe{ &*b }.borrow();
+    alpha_canonical(a) == alpha_canonical(b)
+}
+
+/// @brief Returns the number of atoms in an atom tree
+/// @ingroup atom_group
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to measure
+/// @return The number of atoms, where a Symbol, Variable or Grounded atom counts as 1, and an Expression
+///    counts as 1 plus the counts of all its children
+/// @note The count can be used to reject or down-weight very large atoms before adding them into a space
+///
+#[no_mangle]
+pub extern "C" fn atom_node_count(atom: *const atom_ref_t) -> usize {
+    let atom = unsafe{ &*atom }.borrow();
+    node_count(atom)
+}
+
 /// @brief Returns the metatype of an atom
 /// @ingroup atom_group
 /// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to inspect
@@ -367,6 +555,169 @@ pub extern "C" fn atom_to_str(atom: *const atom_ref_t, buf: *mut c_char, buf_len
     write_into_buf(atom, buf, buf_len)
 }
 
+/// @brief Renders a human-readable text description of an atom on multiple lines with indentation
+/// @ingroup atom_group
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to render
+/// @param[in]  indent_width  The number of spaces each nesting level is indented by
+/// @param[out]  buf  A buffer into which the text will be rendered
+/// @param[in]  buf_len  The maximum allocated size of `buf`
+/// @return The length of the description string, minus the string terminator character.  If
+/// `return_value > buf_len + 1`, then the text was not fully rendered and this function should be
+/// called again with a larger buffer.
+/// @note An expression which contains other expressions is always rendered on multiple lines: each
+///    child except the first one is written on a separate line.  Expressions without nested expressions
+///    are rendered on a single line, the same way `atom_to_str()` renders them
+///
+#[no_mangle]
+pub extern "C" fn atom_to_str_pretty(atom: *const atom_ref_t, indent_width: usize, buf: *mut c_char, buf_len: usize) -> usize {
+    let atom = unsafe{ (&*atom).borrow() };
+    write_into_buf(atom.pretty(indent_width), buf, buf_len)
+}
+
+/// @brief Returns the length of the human-readable text description of an atom
+/// @ingroup atom_group
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to measure
+/// @return The length of the string `atom_to_str()` renders for the atom, minus the string terminator
+///    character.  A buffer of `return_value + 1` bytes is enough to render the atom
+/// @note The text is not rendered into a temporary buffer, only its length is counted
+///
+#[no_mangle]
+pub extern "C" fn atom_to_str_len(atom: *const atom_ref_t) -> usize {
+    let atom = unsafe{ (&*atom).borrow() };
+    display_len(atom)
+}
+
+/// @brief Encodes an atom into a compact binary form
+/// @ingroup atom_group
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to encode
+/// @param[out]  buf  A buffer into which the encoded bytes will be written
+/// @param[in]  buf_len  The maximum allocated size of `buf`
+/// @return The length of the encoded atom in bytes.  If `return_value > buf_len`, then the atom was not
+///    fully written and this function should be called again with a larger buffer.  0 is returned if
+///    the atom contains Grounded atoms, which cannot be encoded
+/// @note Each atom is encoded as a single byte tag followed by its content.  Symbols and Variables are
+///    encoded as a length prefixed UTF-8 name, Expressions as a number of children followed by the
+///    encoded children.  Lengths are encoded as unsigned LEB128 integers.
+/// @see atom_deserialize
+///
+#[no_mangle]
+pub extern "C" fn atom_serialize(atom: *const atom_ref_t, buf: *mut u8, buf_len: usize) -> usize {
+    let atom = unsafe{ (&*atom).borrow() };
+    let mut bytes = Vec::new();
+    if !encode_atom(atom, &mut bytes) {
+        return 0;
+    }
+    if buf_len > 0 {
+        let len = bytes.len().min(buf_len);
+        unsafe{ std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf, len) };
+    }
+    bytes.len()
+}
+
+/// @brief Decodes an atom from the binary form produced by `atom_serialize()`
+/// @ingroup atom_group
+/// @param[in]  buf  A buffer containing the encoded atom
+/// @param[in]  len  The length of the encoded atom in bytes
+/// @return The decoded `atom_t`, or a NULL `atom_t` if `buf` doesn't contain a correctly encoded atom
+/// @note Expressions nested deeper than 1024 levels are rejected, so a malformed buffer cannot exhaust
+///    the stack.  A NULL `atom_t` is returned for them
+/// @note The caller must take ownership responsibility for the returned `atom_t`, if it is not NULL
+///
+#[no_mangle]
+pub extern "C" fn atom_deserialize(buf: *const u8, len: usize) -> atom_t {
+    let mut bytes = unsafe{ std::slice::from_raw_parts(buf, len) };
+    match decode_atom(&mut bytes, SERIAL_MAX_DEPTH) {
+        Some(atom) if bytes.is_empty() => atom.into(),
+        _ => atom_t::null(),
+    }
+}
+
+const SERIAL_TAG_SYMBOL: u8 = 0;
+const SERIAL_TAG_VARIABLE: u8 = 1;
+const SERIAL_TAG_EXPRESSION: u8 = 2;
+const SERIAL_MAX_DEPTH: usize = 1024;
+
+fn encode_len(mut len: usize, bytes: &mut Vec<u8>) {
+    while len >= 0x80 {
+        bytes.push((len as u8) | 0x80);
+        len >>= 7;
+    }
+    bytes.push(len as u8);
+}
+
+fn encode_str(s: &str, bytes: &mut Vec<u8>) {
+    encode_len(s.len(), bytes);
+    bytes.extend_from_slice(s.as_bytes());
+}
+
+fn encode_atom(atom: &Atom, bytes: &mut Vec<u8>) -> bool {
+    match atom {
+        Atom::Symbol(sym) => {
+            bytes.push(SERIAL_TAG_SYMBOL);
+            encode_str(sym.name(), bytes);
+        },
+        Atom::Variable(var) => {
+            bytes.push(SERIAL_TAG_VARIABLE);
+            encode_str(&var.name(), bytes);
+        },
+        Atom::Expression(expr) => {
+            bytes.push(SERIAL_TAG_EXPRESSION);
+            encode_len(expr.children().len(), bytes);
+            return expr.children().iter().all(|child| encode_atom(child, bytes));
+        },
+        Atom::Grounded(_) => return false,
+    }
+    true
+}
+
+fn decode_len(bytes: &mut &[u8]) -> Option<usize> {
+    let mut len: usize = 0;
+    let mut shift = 0;
+    loop {
+        let (byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        let payload = (byte & 0x7f) as usize;
+        let bits = payload.checked_shl(shift)?;
+        if bits >> shift != payload {
+            return None;
+        }
+        len |= bits;
+        if byte & 0x80 == 0 {
+            return Some(len);
+        }
+        shift += 7;
+    }
+}
+
+fn decode_str<'a>(bytes: &mut &'a [u8]) -> Option<&'a str> {
+    let len = decode_len(bytes)?;
+    if len > bytes.len() {
+        return None;
+    }
+    let (s, rest) = bytes.split_at(len);
+    *bytes = rest;
+    std::str::from_utf8(s).ok()
+}
+
+fn decode_atom(bytes: &mut &[u8], max_depth: usize) -> Option<Atom> {
+    let (tag, rest) = bytes.split_first()?;
+    *bytes = rest;
+    match *tag {
+        SERIAL_TAG_SYMBOL => Some(Atom::sym(decode_str(bytes)?)),
+        SERIAL_TAG_VARIABLE => VariableAtom::parse_name(decode_str(bytes)?).ok().map(Atom::Variable),
+        SERIAL_TAG_EXPRESSION => {
+            let count = decode_len(bytes)?;
+            // Each child takes at least one byte, so the count cannot exceed the rest of the buffer
+            if max_depth == 0 || count > bytes.len() {
+                return None;
+            }
+            let children = (0..count).map(|_| decode_atom(bytes, max_depth - 1)).collect::<Option<Vec<Atom>>>()?;
+            Some(Atom::expr(children))
+        },
+        _ => None,
+    }
+}
+
 /// @brief Renders the name of an atom into a text buffer
 /// @ingroup atom_group
 /// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to get the name of
@@ -403,6 +754,36 @@ pub unsafe extern "C" fn atom_get_children(atom: *const atom_ref_t,
     }
 }
 
+/// @brief Returns the number of children atoms within an expression atom
+/// @ingroup atom_group
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to inspect
+/// @return The number of direct children of the expression atom, or 0 if `atom` is not an Expression
+///
+#[no_mangle]
+pub extern "C" fn atom_expr_len(atom: *const atom_ref_t) -> usize {
+    match unsafe{ &*atom }.borrow() {
+        Atom::Expression(e) => e.children().len(),
+        _ => 0,
+    }
+}
+
+/// @brief Provides access to a child atom of an expression atom by its index
+/// @ingroup atom_group
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to access
+/// @param[in]  index  The index of the child atom, starting from 0
+/// @return An `atom_ref_t` referencing the child atom, or a null `atom_ref_t` if `atom` is not an
+///    Expression or `index` is not less than `atom_expr_len()`
+/// @note The returned `atom_ref_t` is borrowed from `atom`.  It must not be accessed after `atom` has
+///    been freed or modified
+///
+#[no_mangle]
+pub extern "C" fn atom_expr_child(atom: *const atom_ref_t, index: usize) -> atom_ref_t {
+    match unsafe{ &*atom }.borrow() {
+        Atom::Expression(e) => e.children().get(index).map_or(atom_ref_t::null(), |child| child.into()),
+        _ => atom_ref_t::null(),
+    }
+}
+
 /// @brief Performs a depth-first exhaustive iteration of an atom and all its children recursively
 /// @ingroup atom_group
 /// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to iterate
@@ -419,6 +800,91 @@ pub unsafe extern "C" fn atom_iterate(atom: *const atom_ref_t,
     }
 }
 
+/// @brief Provides all distinct Variable atoms contained within an atom
+/// @ingroup atom_group
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to inspect
+/// @param[in]  callback  A function that will be called to provide a vector of the Variable atoms
+/// @param[in]  context  A pointer to a caller-defined structure to facilitate communication with the `callback` function
+/// @note Each variable appears in the vector only once.  The order of the variables is unspecified
+///
+/// # Safety
+///
+/// `atom` must point to a valid `atom_t` or `atom_ref_t`
+///
+#[no_mangle]
+pub unsafe extern "C" fn atom_free_variables(atom: *const atom_ref_t,
+        callback: c_atom_vec_callback_t, context: *mut c_void) {
+    let atom = (&*atom).borrow();
+    let vars = collect_variables(atom).into_iter().map(Atom::Variable).collect();
+    return_atoms(&vars, callback, context);
+}
+
+/// @brief Provides the names of all distinct Variable atoms contained within an atom
+/// @ingroup atom_group
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to inspect
+/// @param[in]  callback  A function that will be called once for each distinct variable with its name
+/// @param[in]  context  A pointer to a caller-defined structure to facilitate communication with the `callback` function
+/// @note Names are provided without the `$` sigil, in the same format as `atom_get_name()` returns,
+///    and in order of the first occurrence of each variable within the atom
+///
+/// # Safety
+///
+/// `atom` must point to a valid `atom_t` or `atom_ref_t`
+///
+#[no_mangle]
+pub unsafe extern "C" fn atom_variable_names(atom: *const atom_ref_t,
+        callback: c_name_callback_t, context: *mut c_void) {
+    let atom = (&*atom).borrow();
+    for var in collect_variables(atom) {
+        let name = string_as_cstr(var.name());
+        callback(name.as_ptr(), context);
+    }
+}
+
+/// @brief Replaces all variables within an atom by fresh unique variables
+/// @ingroup atom_group
+/// @param[in]  atom  The `atom_t` to rename the variables of.  This function takes ownership of the atom
+/// @return The `atom_t` with all variables renamed
+/// @note Each distinct variable is renamed using the next value of a global variable id counter, so all
+///    occurrences of the same variable are replaced by the same fresh variable.  It can be used to prevent
+///    variable capture when combining atoms from different scopes
+/// @note The caller must take ownership responsibility for the returned `atom_t`
+///
+#[no_mangle]
+pub extern "C" fn atom_make_variables_unique(atom: atom_t) -> atom_t {
+    make_variables_unique(atom.into_inner()).into()
+}
+
+/// @brief Performs a depth-first iteration of an atom and its sub-atoms, providing the nesting depth of each one
+/// @ingroup atom_group
+/// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to iterate
+/// @param[in]  max_depth  The maximum nesting depth of the atoms to visit.  Pass 0 to visit only the `atom`
+///    itself, or `SIZE_MAX` to visit all sub-atoms
+/// @param[in]  callback  A function that will be called to return access to each visited atom
+/// @param[in]  context  A pointer to a caller-defined structure to facilitate communication with the `callback` function
+/// @note The atoms are visited in the same order as by `atom_iterate()`, the parent atom is visited before
+///    its children.  The callback receives borrowed references, which must not be freed
+///
+/// # Safety
+///
+/// `atom` must point to a valid `atom_t` or `atom_ref_t`
+///
+#[no_mangle]
+pub unsafe extern "C" fn atom_iterate_depth(atom: *const atom_ref_t, max_depth: usize,
+        callback: c_atom_depth_callback_t, context: *mut c_void) {
+    fn visit(atom: &Atom, depth: usize, max_depth: usize, callback: c_atom_depth_callback_t, context: *mut c_void) {
+        callback(atom.into(), depth, context);
+        if let Atom::Expression(expr) = atom {
+            if depth < max_depth {
+                for child in expr.children() {
+                    visit(child, depth + 1, max_depth, callback, context);
+                }
+            }
+        }
+    }
+    visit((&*atom).borrow(), 0, max_depth, callback, context);
+}
+
 /// @brief Retrieve the grounded type of a Grounded Atom
 /// @ingroup atom_group
 /// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to access
@@ -437,6 +903,59 @@ pub extern "C" fn atom_get_grounded_type(atom: *const atom_ref_t) -> atom_t {
     }
 }
 
+/// @brief Executes a Grounded Atom operation with the supplied arguments, without running the interpreter
+/// @ingroup atom_group
+/// @param[in]  op  A pointer to an `atom_t` or an `atom_ref_t` referencing the Grounded Atom to execute
+/// @param[in]  args  A pointer to an array of `atom_t` containing the arguments.  The arguments are
+///    cloned, so the caller keeps ownership of the array and the atoms
+/// @param[in]  argc  The number of atoms in the `args` array
+/// @param[in]  callback  A function that will be called to provide a vector of the result atoms
+/// @param[in]  context  A pointer to a caller-defined structure to facilitate communication with the `callback` function
+/// @param[out]  err_out  A pointer to an `exec_error_t`, which will be set to the error if the execution
+///    failed, or to the "No Error" status otherwise.  May be NULL if the caller is not interested in the error
+/// @return `true` if the operation is executed successfully, or `false` if an error occurred, in which
+///    case `callback` is not called
+/// @note If `op` is not a Grounded Atom, or the Grounded Atom is not executable, the function returns
+///    `false` and a runtime error is written to `err_out`
+/// @note The caller must take ownership responsibility for the `exec_error_t` written to `err_out`, and
+///    ultimately free it with `exec_error_free()`
+///
+/// # Safety
+///
+/// `op` must point to a valid `atom_t` or `atom_ref_t`, `args` must point to `argc` valid
+/// `atom_t` values unless `argc` is 0, and `err_out` must be NULL or point to writable memory
+///
+#[no_mangle]
+pub unsafe extern "C" fn atom_execute(op: *const atom_ref_t, args: *const atom_t, argc: usize,
+        callback: c_atom_vec_callback_t, context: *mut c_void, err_out: *mut exec_error_t) -> bool {
+    let op = (&*op).borrow();
+    let args: Vec<Atom> = if argc == 0 {
+        vec![]
+    } else {
+        std::slice::from_raw_parts(args, argc).iter().map(|arg| arg.borrow().clone()).collect()
+    };
+    let result = match op {
+        Atom::Grounded(gnd) => match gnd.as_grounded().as_execute() {
+            Some(executable) => executable.execute(&args),
+            None => Err(ExecError::Runtime(format!("Grounded atom {} is not executable", op))),
+        },
+        _ => Err(ExecError::Runtime(format!("Atom {} is not a Grounded atom", op))),
+    };
+    let (success, err) = match result {
+        Ok(atoms) => {
+            return_atoms(&atoms, callback, context);
+            (true, exec_error_t::NoErr)
+        },
+        Err(err) => (false, err.into()),
+    };
+    if err_out.is_null() {
+        exec_error_free(err);
+    } else {
+        *err_out = err;
+    }
+    success
+}
+
 /// @brief Access a pointer to an object backing a Grounded Atom
 /// @ingroup atom_group
 /// @param[in]  atom  A pointer to an `atom_t` or an `atom_ref_t` to access
@@ -8,6 +8,8 @@ use hyperon::metta::interpreter::InterpreterState;
 use hyperon::metta::runner::{Metta, RunContext, RunnerState, Environment, EnvBuilder};
 use hyperon::metta::runner::modules::{ModuleLoader, ModId, ResourceKey};
 use hyperon::metta::runner::pkg_mgmt::{FsModuleFormat, ModuleDescriptor};
+use hyperon::metta::runner::arithmetics::Number;
+use hyperon::metta::runner::string::Str;
 use hyperon::atom::*;
 
 use crate::util::*;
@@ -16,6 +18,7 @@ use crate::space::*;
 
 use std::os::raw::*;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use regex::Regex;
 
@@ -39,13 +42,17 @@ use regex::Regex;
 pub struct tokenizer_t {
     /// Internal.  Should not be accessed directly
     tokenizer: *const RustTokenizer,
+    err_string: *mut c_char,
 }
 
 struct RustTokenizer(std::cell::RefCell<Tokenizer>);
 
 impl From<Shared<Tokenizer>> for tokenizer_t {
     fn from(tokenizer: Shared<Tokenizer>) -> Self {
-        Self{ tokenizer: std::rc::Rc::into_raw(tokenizer.0).cast() }
+        Self{
+            tokenizer: std::rc::Rc::into_raw(tokenizer.0).cast(),
+            err_string: core::ptr::null_mut(),
+        }
     }
 }
 
@@ -54,9 +61,21 @@ impl tokenizer_t {
         let cell = unsafe{ &mut (&mut *self.tokenizer.cast_mut()).0 };
         cell.get_mut()
     }
-    fn into_handle(self) -> Shared<Tokenizer> {
+    fn into_handle(mut self) -> Shared<Tokenizer> {
+        self.free_err_string();
         unsafe{ Shared(std::rc::Rc::from_raw(self.tokenizer.cast())) }
     }
+    fn free_err_string(&mut self) {
+        if !self.err_string.is_null() {
+            let string = unsafe{ std::ffi::CString::from_raw(self.err_string) };
+            drop(string);
+            self.err_string = core::ptr::null_mut();
+        }
+    }
+    fn clone_handle(&self) -> Shared<Tokenizer> {
+        let handle = core::mem::ManuallyDrop::new(unsafe{ Shared(std::rc::Rc::from_raw(self.tokenizer.cast())) });
+        (*handle).clone()
+    }
 }
 
 /// @brief Creates a new Tokenizer, without any registered Tokens
@@ -125,14 +144,39 @@ impl Drop for CToken {
 /// @param[in]  api  A table of functions to manage the token
 /// @param[in]  context  A caller-defined structure to communicate any state necessary to implement the Token parser
 /// @note Hyperon uses the Rust RegEx engine and syntax, [documented here](https://docs.rs/regex/latest/regex/).
+/// @note The Token is registered with a priority of 0.  See `tokenizer_register_token_with_priority()`
+/// @warning This function will panic if `regex` is not a valid regular expression.  Use
+///     `tokenizer_register_token_checked()` for regular expressions that are not known to be valid ahead
+///     of time, for example if they come from user-authored files
 ///
 #[no_mangle]
 pub extern "C" fn tokenizer_register_token(tokenizer: *mut tokenizer_t,
     regex: *const c_char, api: *const token_api_t, context: *mut c_void) {
+    tokenizer_register_token_with_priority(tokenizer, regex, api, context, DEFAULT_TOKEN_PRIORITY)
+}
+
+/// @brief Registers a new custom Token in a Tokenizer, with an explicit priority
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  tokenizer  A pointer to the Tokenizer in which to register the Token
+/// @param[in]  regex  A regular expression to match the incoming text, triggering this token to generate a new atom
+/// @param[in]  api  A table of functions to manage the token
+/// @param[in]  context  A caller-defined structure to communicate any state necessary to implement the Token parser
+/// @param[in]  priority  The priority of the Token.  When several Tokens match the same text, the one with
+///     the highest priority is used.  Among Tokens with equal priority, the most recently registered is used
+/// @note `tokenizer_register_token()` uses a priority of 0
+/// @warning This function will panic if `regex` is not a valid regular expression
+///
+#[no_mangle]
+pub extern "C" fn tokenizer_register_token_with_priority(tokenizer: *mut tokenizer_t,
+    regex: *const c_char, api: *const token_api_t, context: *mut c_void, priority: i32) {
     let tokenizer = unsafe{ &*tokenizer }.borrow_inner();
     let regex = Regex::new(cstr_as_str(regex)).unwrap();
-    let c_token = CToken{ context, api };
-    tokenizer.register_token(regex, move |token| {
+    register_c_token(tokenizer, regex, CToken{ context, api }, priority);
+}
+
+/// Internal function to register a token implemented by a `token_api_t`
+fn register_c_token(tokenizer: &mut Tokenizer, regex: Regex, c_token: CToken, priority: i32) {
+    tokenizer.register_token_with_priority(regex, priority, move |token| {
         let c_token = &c_token; //Be explicit we're capturing c_token, and not the pointers it contains
         let constr = unsafe{ (&*c_token.api).construct_atom };
         let atom = constr(str_as_cstr(token).as_ptr(), c_token.context);
@@ -140,6 +184,87 @@ pub extern "C" fn tokenizer_register_token(tokenizer: *mut tokenizer_t,
     });
 }
 
+/// @brief Registers a new custom Token in a Tokenizer, after checking that the regex is valid
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  tokenizer  A pointer to the Tokenizer in which to register the Token
+/// @param[in]  regex  A regular expression to match the incoming text, triggering this token to generate a new atom
+/// @param[in]  api  A table of functions to manage the token
+/// @param[in]  context  A caller-defined structure to communicate any state necessary to implement the Token parser
+/// @return `true` if the Token was registered, or `false` if `regex` is invalid.  In that case
+///     nothing is registered, and the error can be accessed with `tokenizer_err_str()`
+/// @note The Token is registered with a priority of 0, like `tokenizer_register_token()`
+/// @note If the Token is not registered, the `free_context` function from `api` will still be called
+///     to free `context`
+///
+#[no_mangle]
+pub extern "C" fn tokenizer_register_token_checked(tokenizer: *mut tokenizer_t,
+    regex: *const c_char, api: *const token_api_t, context: *mut c_void) -> bool {
+    let tokenizer = unsafe{ &mut *tokenizer };
+    tokenizer.free_err_string();
+    let c_token = CToken{ context, api };
+    match Regex::new(cstr_as_str(regex)) {
+        Ok(regex) => {
+            register_c_token(tokenizer.borrow_inner(), regex, c_token, DEFAULT_TOKEN_PRIORITY);
+            true
+        },
+        Err(err) => {
+            drop(c_token);
+            let err_cstring = std::ffi::CString::new(err.to_string()).unwrap();
+            tokenizer.err_string = err_cstring.into_raw();
+            false
+        }
+    }
+}
+
+/// @brief Returns the error string associated with the last `tokenizer_register_token_checked` call
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  tokenizer  A pointer to the Tokenizer
+/// @return A pointer to the C-string containing the error that occurred, or NULL if no error occurred
+/// @warning The returned pointer should NOT be freed.  It must never be accessed after the
+///     tokenizer_t has been freed, or any subsequent call to `tokenizer_register_token_checked` has been made.
+///
+#[no_mangle]
+pub extern "C" fn tokenizer_err_str(tokenizer: *const tokenizer_t) -> *const c_char {
+    let tokenizer = unsafe{ &*tokenizer };
+    tokenizer.err_string
+}
+
+/// @brief Checks whether a registered Token matches the text, without parsing it
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  tokenizer  A pointer to the Tokenizer
+/// @param[in]  text  A C-style string to check
+/// @return `true` if the regular expression of any registered Token matches the whole `text`, otherwise `false`
+///
+#[no_mangle]
+pub extern "C" fn tokenizer_matches(tokenizer: *const tokenizer_t, text: *const c_char) -> bool {
+    let tokenizer = unsafe{ &*tokenizer }.borrow_inner();
+    tokenizer.find_token_regex(cstr_as_str(text)).is_some()
+}
+
+/// @brief Renders the regular expression of the Token which would be used to parse the text into a buffer
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  tokenizer  A pointer to the Tokenizer
+/// @param[in]  text  A C-style string to check
+/// @param[out]  buf  A buffer into which the regular expression will be rendered
+/// @param[in]  buf_len  The maximum allocated size of `buf`
+/// @return The length of the regular expression string, minus the string terminator character.  If
+///    `return_value > buf_len + 1`, then the text was not fully rendered and this function should be
+///    called again with a larger buffer.  If no Token matches `text`, 0 is returned and an empty string
+///    is written into `buf`
+/// @note When several Tokens match the text, the regular expression of the Token with the highest priority
+///    is returned.  Among Tokens with equal priority, the most recently registered is returned.  This is the
+///    Token the parser would use
+///
+#[no_mangle]
+pub extern "C" fn tokenizer_matched_regex(tokenizer: *const tokenizer_t, text: *const c_char,
+        buf: *mut c_char, buf_len: usize) -> usize {
+    let tokenizer = unsafe{ &*tokenizer }.borrow_inner();
+    match tokenizer.find_token_regex(cstr_as_str(text)) {
+        Some(regex) => write_into_buf(regex.as_str(), buf, buf_len),
+        None => write_into_buf("", buf, buf_len),
+    }
+}
+
 /// @brief Performs a "deep copy" of a Tokenizer
 /// @ingroup tokenizer_and_parser_group
 /// @param[in]  tokenizer  A pointer to the Tokenizer to clone
@@ -152,6 +277,23 @@ pub extern "C" fn tokenizer_clone(tokenizer: *const tokenizer_t) -> tokenizer_t
     Shared::new(tokenizer.clone()).into()
 }
 
+/// @brief Copies all registered Tokens from one Tokenizer into another
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  dst  A pointer to the Tokenizer into which the Tokens are copied
+/// @param[in]  src  A pointer to the Tokenizer from which the Tokens are copied.  It is not modified
+/// @note Among Tokens with equal priority, the Tokens copied from `src` are used before the Tokens of `dst`,
+///    as if they were registered in `dst` after all its own Tokens
+/// @note The copied Tokens share their `context` with `src`.  Each `context` is freed only once, when the
+///    last Tokenizer which contains the Token is freed
+///
+#[no_mangle]
+pub extern "C" fn tokenizer_merge(dst: *mut tokenizer_t, src: *const tokenizer_t) {
+    //Clone first, so merging a Tokenizer into itself doesn't alias the borrows
+    let src = unsafe{ &*src }.borrow_inner().clone();
+    let dst = unsafe{ &*dst }.borrow_inner();
+    dst.merge(&src);
+}
+
 /// @brief Represents an S-Expression Parser state machine, to parse input text into an Atom
 /// @ingroup tokenizer_and_parser_group
 /// @note `sexpr_parser_t` objects must be freed with `sexpr_parser_free()`
@@ -221,6 +363,12 @@ impl sexpr_parser_t {
             RustSExprParser::Owned(parser) => parser,
         }
     }
+    fn text(&self) -> &str {
+        match self.borrow_inner_enum() {
+            RustSExprParser::Borrowed(parser) => parser.text(),
+            RustSExprParser::Owned(parser) => parser.text(),
+        }
+    }
     fn borrow_sexpr_parser_mut(&mut self) -> &mut SExprParser<'static> {
         match unsafe{ &mut *self.parser } {
             RustSExprParser::Borrowed(parser) => parser,
@@ -243,6 +391,23 @@ pub extern "C" fn sexpr_parser_new(text: *const c_char) -> sexpr_parser_t {
     SExprParser::new(cstr_as_str(text)).into()
 }
 
+/// @brief Creates a new S-Expression Parser from a text buffer with an explicit length
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  text  A pointer to a buffer containing the UTF-8 encoded input text to parse.  The buffer
+///    does not need to be null-terminated
+/// @param[in]  len  The length of the text in `text`, in bytes
+/// @return The new `sexpr_parser_t`, ready to parse the text
+/// @note The returned `sexpr_parser_t` must be freed with `sexpr_parser_free()` or passed to another
+///    function that takes ownership
+/// @warning The returned `sexpr_parser_t` borrows a reference to the `text`, so the returned
+///    `sexpr_parser_t` must be freed before the `text` is freed or allowed to go out of scope.
+/// @warning This function will panic if the `text` buffer doesn't contain a valid UTF-8 sequence
+///
+#[no_mangle]
+pub extern "C" fn sexpr_parser_new_with_len(text: *const c_char, len: usize) -> sexpr_parser_t {
+    SExprParser::new(buf_as_str(text, len)).into()
+}
+
 /// @brief Creates a new S-Expression Parser, for situations where you must deallocate the text buffer
 ///    before parsing is complete
 /// @ingroup tokenizer_and_parser_group
@@ -282,6 +447,121 @@ pub extern "C" fn sexpr_parser_free(parser: sexpr_parser_t) {
     drop(parser);
 }
 
+/// @brief Returns the current position of an S-Expression Parser within its source text
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  parser  A pointer to the Parser, which is associated with the text to parse
+/// @return The byte offset into the original source text, of the next character the parser will consume.
+///    When all of the text has been consumed, the returned value will equal the length of the text
+/// @note The returned offset is a byte index, not a character index, so it may differ from the number
+///    of characters consumed if the text contains multi-byte UTF-8 sequences
+///
+#[no_mangle]
+pub extern "C" fn sexpr_parser_offset(parser: *const sexpr_parser_t) -> usize {
+    let parser = unsafe{ &*parser }.borrow_inner_enum();
+    match parser {
+        RustSExprParser::Borrowed(parser) => parser.cur_idx(),
+        RustSExprParser::Owned(parser) => parser.cur_idx(),
+    }
+}
+
+/// @brief Checks whether an S-Expression Parser has reached the end of its source text
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  parser  A pointer to the Parser, which is associated with the text to parse
+/// @return `true` if the rest of the text contains only whitespace and comments which the parser skips,
+///    otherwise `false`
+/// @note When this function returns `true`, the next call to `sexpr_parser_parse()` returns a `null` atom
+///    without an error.  Checking it before each call distinguishes the end of the input from a parse error
+///
+#[no_mangle]
+pub extern "C" fn sexpr_parser_at_end(parser: *const sexpr_parser_t) -> bool {
+    let parser = unsafe{ &*parser }.borrow_inner_enum();
+    match parser {
+        RustSExprParser::Borrowed(parser) => parser.at_end(),
+        RustSExprParser::Owned(parser) => parser.at_end(),
+    }
+}
+
+/// @brief Sets whether an S-Expression Parser returns the comments it encounters
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  parser  A pointer to the Parser, which is associated with the text to parse
+/// @param[in]  keep  `true` to return comments, or `false` to discard them, which is the default
+/// @note In keep-comments mode, `sexpr_parser_parse` returns each top-level comment as a
+///    `(Comment <text>)` expression atom between the other atoms, where `<text>` is a symbol containing
+///    the full comment line including the leading ';'.  Comments inside expressions are always discarded
+///
+#[no_mangle]
+pub extern "C" fn sexpr_parser_set_keep_comments(parser: *mut sexpr_parser_t, keep: bool) {
+    let mode = if keep { CommentMode::Keep } else { CommentMode::Skip };
+    match unsafe{ &mut *(*parser).parser } {
+        RustSExprParser::Borrowed(parser) => parser.set_comment_mode(mode),
+        RustSExprParser::Owned(parser) => parser.set_comment_mode(mode),
+    }
+}
+
+/// @brief Limits the nesting depth of expressions which an S-Expression Parser accepts
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  parser  A pointer to the Parser, which is associated with the text to parse
+/// @param[in]  max_depth  The maximal number of nested expressions, or 0 to remove the limit, which is the default
+/// @note When the input contains expressions nested deeper than `max_depth`, parsing fails with an error
+///    rather than exhausting the stack, and the rest of the text is skipped.  Use this function when
+///    parsing untrusted input
+///
+#[no_mangle]
+pub extern "C" fn sexpr_parser_set_max_depth(parser: *mut sexpr_parser_t, max_depth: usize) {
+    let max_depth = if max_depth == 0 { None } else { Some(max_depth) };
+    match unsafe{ &mut *(*parser).parser } {
+        RustSExprParser::Borrowed(parser) => parser.set_max_depth(max_depth),
+        RustSExprParser::Owned(parser) => parser.set_max_depth(max_depth),
+    }
+}
+
+/// @brief Sets the character which starts variables in the text parsed by an S-Expression Parser
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  parser  A pointer to the Parser, which is associated with the text to parse
+/// @param[in]  sigil  The ASCII character to start variables with.  The default is '$'
+/// @return `true` if the sigil is set, or `false` if `sigil` is whitespace, a parenthesis, ';', '"' or
+///    a non-ASCII character.  The parser is not changed when `false` is returned
+/// @note The sigil is detected before tokenization, so words starting with the sigil are always parsed
+///    as variables, even when they match a token registered in the Tokenizer.  Words starting with '$'
+///    are passed to the Tokenizer as any other word when another sigil is set
+///
+#[no_mangle]
+pub extern "C" fn sexpr_parser_set_variable_sigil(parser: *mut sexpr_parser_t, sigil: c_char) -> bool {
+    let sigil = sigil as u8;
+    if !sigil.is_ascii() || !SExprParser::is_valid_variable_sigil(sigil.into()) {
+        return false;
+    }
+    match unsafe{ &mut *(*parser).parser } {
+        RustSExprParser::Borrowed(parser) => parser.set_variable_sigil(sigil.into()),
+        RustSExprParser::Owned(parser) => parser.set_variable_sigil(sigil.into()),
+    }
+    true
+}
+
+/// @brief Sets the additional characters which separate atoms in the text parsed by an S-Expression Parser
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  parser  A pointer to the Parser, which is associated with the text to parse
+/// @param[in]  separators  A C-style string containing the ASCII characters to treat as whitespace between
+///    atoms, in addition to the standard whitespace.  An empty string removes the additional separators
+/// @return `true` if the separators are set, or `false` if `separators` contains a non-ASCII character.
+///    The parser is not changed when `false` is returned
+/// @note Separators never become a part of tokens.  ';' always starts a comment, and parentheses and '"'
+///    keep their meaning, so these characters are ignored in `separators`
+///
+#[no_mangle]
+pub extern "C" fn sexpr_parser_set_extra_separators(parser: *mut sexpr_parser_t, separators: *const c_char) -> bool {
+    let separators = cstr_as_str(separators);
+    if !separators.is_ascii() {
+        return false;
+    }
+    let separators: Vec<char> = separators.chars().collect();
+    match unsafe{ &mut *(*parser).parser } {
+        RustSExprParser::Borrowed(parser) => parser.set_extra_separators(&separators),
+        RustSExprParser::Owned(parser) => parser.set_extra_separators(&separators),
+    }
+    true
+}
+
 /// @brief Parses the text associated with an `sexpr_parser_t`, and creates the corresponding Atom
 /// @ingroup tokenizer_and_parser_group
 /// @param[in]  parser  A pointer to the Parser, which is associated with the text to parse
@@ -290,7 +570,8 @@ pub extern "C" fn sexpr_parser_free(parser: sexpr_parser_t) {
 ///    atom if parsing is finished, or an error expression atom if a parse error occurred.
 /// @note The caller must take ownership responsibility for the returned `atom_t`, and ultimately free
 ///    it with `atom_free()` or pass it to another function that takes ownership responsibility
-/// @note If this function encounters an error, the error may be accessed with `sexpr_parser_err_str()`
+/// @note If this function encounters an error, the error may be accessed with `sexpr_parser_err_str()`.
+///    Use `sexpr_parser_parse_checked()` to also find the location of the error
 ///
 #[no_mangle]
 pub extern "C" fn sexpr_parser_parse(
@@ -311,14 +592,236 @@ pub extern "C" fn sexpr_parser_parse(
     }
 }
 
-/// @brief Returns the error string associated with the last `sexpr_parser_parse` call
+/// @brief Represents an error encountered while parsing MeTTa source text, including its location
+/// @ingroup tokenizer_and_parser_group
+/// @note `parse_error_t` objects must be freed with `parse_error_free()`
+///
+#[repr(C)]
+pub struct parse_error_t {
+    /// Internal.  Should not be accessed directly
+    error: *mut RustParseError,
+}
+
+struct RustParseError {
+    error: ParseError,
+    /// The source text of the error's node, see [RustSyntaxNode]
+    src: SyntaxNodeSrc,
+}
+
+impl parse_error_t {
+    /// Creates the error, `text` is the source text parsed by the parser which reported it
+    fn new(error: ParseError, text: &str) -> Self {
+        let src = match &error.node {
+            Some(node) => SyntaxNodeSrc::new(node, text),
+            None => SyntaxNodeSrc::new_empty(),
+        };
+        Self{ error: Box::into_raw(Box::new(RustParseError{ error, src })) }
+    }
+    fn null() -> Self {
+        Self{ error: core::ptr::null_mut() }
+    }
+    fn is_null(&self) -> bool {
+        self.error.is_null()
+    }
+    fn borrow(&self) -> &ParseError {
+        &unsafe{ &*self.error }.error
+    }
+}
+
+/// @brief Parses the text associated with an `sexpr_parser_t`, and reports the location of any error
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  parser  A pointer to the Parser, which is associated with the text to parse
+/// @param[in]  tokenizer  A pointer to the Tokenizer, to use to interpret atoms within the expression
+/// @param[out]  err_out  A pointer to a `parse_error_t`, which will be set to the error if one occurred,
+///    or to a null `parse_error_t` otherwise
+/// @return The new `atom_t`, which may be an Expression atom with many child atoms.  Returns a `none`
+///    atom if parsing is finished or if an error occurred
+/// @note The caller must take ownership responsibility for the returned `atom_t`, and ultimately free
+///    it with `atom_free()` or pass it to another function that takes ownership responsibility
+/// @note The caller must take ownership responsibility for the `parse_error_t` written to `err_out`, and
+///    free it with `parse_error_free()`.  Use `parse_error_is_null()` to check whether an error occurred
+///
+#[no_mangle]
+pub extern "C" fn sexpr_parser_parse_checked(
+    parser: *mut sexpr_parser_t,
+    tokenizer: *const tokenizer_t,
+    err_out: *mut parse_error_t) -> atom_t
+{
+    let parser = unsafe{ &mut *parser };
+    parser.free_err_string();
+    let tokenizer = unsafe{ &*tokenizer }.borrow_inner();
+    let result = match unsafe{ &mut *parser.parser } {
+        RustSExprParser::Borrowed(parser) => parser.parse_checked(tokenizer),
+        RustSExprParser::Owned(parser) => parser.parse_checked(tokenizer),
+    };
+    match result {
+        Ok(atom) => {
+            unsafe{ *err_out = parse_error_t::null() };
+            atom.into()
+        },
+        Err(err) => {
+            unsafe{ *err_out = parse_error_t::new(err, parser.text()) };
+            atom_t::null()
+        }
+    }
+}
+
+/// @brief Frees a `parse_error_t`
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  err  The `parse_error_t` to free.  Freeing a null `parse_error_t` has no effect
+///
+#[no_mangle]
+pub extern "C" fn parse_error_free(err: parse_error_t) {
+    if !err.is_null() {
+        let err = unsafe{ Box::from_raw(err.error) };
+        drop(err);
+    }
+}
+
+/// @brief Returns `true` if a `parse_error_t` is null, meaning no error occurred
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  err  A pointer to the `parse_error_t` to inspect
+/// @return `true` if the `parse_error_t` is null, otherwise `false`
+///
+#[no_mangle]
+pub extern "C" fn parse_error_is_null(err: *const parse_error_t) -> bool {
+    unsafe{ &*err }.is_null()
+}
+
+/// @brief Returns the location of a parse error
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  err  A pointer to the `parse_error_t` to inspect
+/// @return The byte offset into the source text, where the error was detected
+/// @note For a Parser created with `sexpr_parser_new`, the offset is relative to the start of the text.
+///    The offset is a byte index, not a character index
+///
+#[no_mangle]
+pub extern "C" fn parse_error_offset(err: *const parse_error_t) -> usize {
+    unsafe{ &*err }.borrow().offset
+}
+
+/// @brief Renders the message of a parse error into a buffer
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  err  A pointer to the `parse_error_t` to inspect
+/// @param[out]  buf  A buffer into which the text will be written
+/// @param[in]  buf_len  The maximum allocated size of `buf`
+/// @return The length of the message string, minus the string terminator character.  If
+/// `return_value > buf_len + 1`, then the text was not fully written and this function should be
+/// called again with a larger buffer.
+///
+#[no_mangle]
+pub extern "C" fn parse_error_message(err: *const parse_error_t, buf: *mut c_char, buf_len: usize) -> usize {
+    write_into_buf(&unsafe{ &*err }.borrow().message, buf, buf_len)
+}
+
+/// @brief Returns the syntax node in which a parse error was detected
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  err  A pointer to the `parse_error_t` to inspect
+/// @return A new `syntax_node_t` for the node, or a null `syntax_node_t` if the error isn't associated with a node
+/// @note The caller must take ownership responsibility for the returned `syntax_node_t`, and free it with `syntax_node_free()`
+///
+#[no_mangle]
+pub extern "C" fn parse_error_node(err: *const parse_error_t) -> syntax_node_t {
+    let err = unsafe{ &*(*err).error };
+    match &err.error.node {
+        Some(node) => syntax_node_t::new(SyntaxNodeRef::Owned((**node).clone()), err.src.clone()),
+        None => syntax_node_t::null(),
+    }
+}
+
+/// @brief Parses all of the remaining text associated with an `sexpr_parser_t`, and provides every
+///    top-level Atom to a callback
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  parser  A pointer to the Parser, which is associated with the text to parse
+/// @param[in]  tokenizer  A pointer to the Tokenizer, to use to interpret atoms within the expression
+/// @param[in]  callback  A function that will be called to provide a vector of all parsed atoms, in
+///    the order they appear in the text
+/// @param[in]  context  A pointer to a caller-defined structure to facilitate communication with the `callback` function
+/// @note If this function encounters an error, the callback will not be called and the error may be
+///    accessed with `sexpr_parser_err_str()`
+///
+#[no_mangle]
+pub extern "C" fn sexpr_parser_parse_all(
+    parser: *mut sexpr_parser_t,
+    tokenizer: *const tokenizer_t,
+    callback: c_atom_vec_callback_t, context: *mut c_void)
+{
+    let parser = unsafe{ &mut *parser };
+    parser.free_err_string();
+    let rust_parser = parser.borrow_dyn_mut();
+    let tokenizer = unsafe{ &*tokenizer }.borrow_inner();
+    let mut atoms = vec![];
+    loop {
+        match rust_parser.next_atom(tokenizer) {
+            Ok(Some(atom)) => atoms.push(atom),
+            Ok(None) => break,
+            Err(err) => {
+                let err_cstring = std::ffi::CString::new(err).unwrap();
+                parser.err_string = err_cstring.into_raw();
+                return;
+            }
+        }
+    }
+    return_atoms(&atoms, callback, context);
+}
+
+/// @brief Function signature for a callback providing access to a `parse_error_t`
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  err  The `parse_error_t` being provided.  This error should not be modified or freed by the callback.
+/// @param[in]  context  The context state pointer initially passed to the upstream function initiating the callback.
+///
+pub type c_parse_error_callback_t = extern "C" fn(err: *const parse_error_t, context: *mut c_void);
+
+/// @brief Parses all of the remaining text associated with an `sexpr_parser_t`, continuing after
+///    syntax errors, and reports every top-level Atom and every error to the callbacks
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  parser  A pointer to the Parser, which is associated with the text to parse
+/// @param[in]  tokenizer  A pointer to the Tokenizer, to use to interpret atoms within the expression
+/// @param[in]  atom_callback  A function that will be called for each parsed atom
+/// @param[in]  error_callback  A function that will be called for each error
+/// @param[in]  context  A pointer to a caller-defined structure to facilitate communication with both
+///    callback functions
+/// @note Atoms and errors are reported in the order they appear in the text.  After a syntax error
+///    the parser skips to the next plausible atom boundary, so a single mistake may hide parts of
+///    the following text
+///
+#[no_mangle]
+pub extern "C" fn sexpr_parser_parse_recovering(
+    parser: *mut sexpr_parser_t,
+    tokenizer: *const tokenizer_t,
+    atom_callback: c_atom_callback_t,
+    error_callback: c_parse_error_callback_t,
+    context: *mut c_void)
+{
+    let parser = unsafe{ &mut *parser };
+    parser.free_err_string();
+    let tokenizer = unsafe{ &*tokenizer }.borrow_inner();
+    loop {
+        let result = match unsafe{ &mut *parser.parser } {
+            RustSExprParser::Borrowed(parser) => parser.parse_recovering(tokenizer),
+            RustSExprParser::Owned(parser) => parser.parse_recovering(tokenizer),
+        };
+        match result {
+            Ok(Some(atom)) => atom_callback((&atom).into(), context),
+            Ok(None) => break,
+            Err(err) => {
+                let err = parse_error_t::new(err, parser.text());
+                error_callback(&err, context);
+                parse_error_free(err);
+            }
+        }
+    }
+}
+
+/// @brief Returns the error string associated with the last `sexpr_parser_parse` or
+///     `sexpr_parser_parse_all` call
 /// @ingroup tokenizer_and_parser_group
 /// @param[in]  parser  A pointer to the Parser, which is associated with the text to parse
 /// @return A pointer to the C-string containing the parse error that occurred, or NULL if no
 ///     parse error occurred
 /// @warning The returned pointer should NOT be freed.  It must never be accessed after the
-///     sexpr_parser_t has been freed, or any subsequent call to `sexpr_parser_parse` or
-///     `sexpr_parser_parse_to_syntax_tree` has been made.
+///     sexpr_parser_t has been freed, or any subsequent call to `sexpr_parser_parse`,
+///     `sexpr_parser_parse_all` or `sexpr_parser_parse_to_syntax_tree` has been made.
 ///
 #[no_mangle]
 pub extern "C" fn sexpr_parser_err_str(
@@ -337,29 +840,54 @@ pub struct syntax_node_t {
     node: *mut RustSyntaxNode,
 }
 
-struct RustSyntaxNode(SyntaxNode);
+/// The source text is kept once per syntax tree and shared by all of its nodes,
+/// the text of a node is a slice of it
+struct RustSyntaxNode {
+    node: SyntaxNodeRef,
+    src: SyntaxNodeSrc,
+}
 
-impl From<SyntaxNode> for syntax_node_t {
-    fn from(node: SyntaxNode) -> Self {
-        Self{ node: Box::into_raw(Box::new(RustSyntaxNode(node))) }
-    }
+enum SyntaxNodeRef {
+    Owned(SyntaxNode),
+    /// A sub-node of the tree owned by another `syntax_node_t`, passed to the
+    /// `syntax_node_iterate` callback
+    Borrowed(*const SyntaxNode),
 }
 
-impl From<Option<SyntaxNode>> for syntax_node_t {
-    fn from(node: Option<SyntaxNode>) -> Self {
-        match node {
-            Some(node) => Self{ node: Box::into_raw(Box::new(RustSyntaxNode(node))) },
-            None => syntax_node_t::null()
-        }
+/// The part of the source text covered by a syntax tree, starting at the `start` offset of the source
+#[derive(Clone)]
+struct SyntaxNodeSrc {
+    text: std::rc::Rc<str>,
+    start: usize,
+}
+
+impl SyntaxNodeSrc {
+    fn new(node: &SyntaxNode, text: &str) -> Self {
+        Self{ text: text[node.src_range.clone()].into(), start: node.src_range.start }
+    }
+    fn new_empty() -> Self {
+        Self{ text: "".into(), start: 0 }
+    }
+    fn node_text(&self, node: &SyntaxNode) -> &str {
+        &self.text[node.src_range.start - self.start..node.src_range.end - self.start]
     }
 }
 
 impl syntax_node_t {
-    fn into_inner(self) -> SyntaxNode {
-        unsafe{ (*Box::from_raw(self.node)).0 }
+    fn new(node: SyntaxNodeRef, src: SyntaxNodeSrc) -> Self {
+        Self{ node: Box::into_raw(Box::new(RustSyntaxNode{ node, src })) }
+    }
+    fn into_inner(self) -> RustSyntaxNode {
+        unsafe{ *Box::from_raw(self.node) }
     }
     fn borrow(&self) -> &SyntaxNode {
-        &unsafe{ &*self.node }.0
+        match &unsafe{ &*self.node }.node {
+            SyntaxNodeRef::Owned(node) => node,
+            SyntaxNodeRef::Borrowed(node) => unsafe{ &**node },
+        }
+    }
+    fn borrow_src(&self) -> &SyntaxNodeSrc {
+        &unsafe{ &*self.node }.src
     }
     fn is_null(&self) -> bool {
         self.node == core::ptr::null_mut()
@@ -434,7 +962,13 @@ pub extern "C" fn sexpr_parser_parse_to_syntax_tree(parser: *mut sexpr_parser_t)
     let parser = unsafe{ &mut *parser };
     parser.free_err_string();
     let rust_parser = parser.borrow_sexpr_parser_mut();
-    rust_parser.parse_to_syntax_tree().into()
+    match rust_parser.parse_to_syntax_tree() {
+        Some(node) => {
+            let src = SyntaxNodeSrc::new(&node, rust_parser.text());
+            syntax_node_t::new(SyntaxNodeRef::Owned(node), src)
+        },
+        None => syntax_node_t::null(),
+    }
 }
 
 /// @brief Frees a syntax_node_t
@@ -456,8 +990,8 @@ pub extern "C" fn syntax_node_free(node: syntax_node_t) {
 ///
 #[no_mangle]
 pub extern "C" fn syntax_node_clone(node: *const syntax_node_t) -> syntax_node_t {
-    let node = unsafe{ &*node }.borrow();
-    node.clone().into()
+    let node = unsafe{ &*node };
+    syntax_node_t::new(SyntaxNodeRef::Owned(node.borrow().clone()), node.borrow_src().clone())
 }
 
 /// @brief Performs a depth-first iteration of all child syntax nodes within a syntax tree
@@ -469,9 +1003,11 @@ pub extern "C" fn syntax_node_clone(node: *const syntax_node_t) -> syntax_node_t
 #[no_mangle]
 pub extern "C" fn syntax_node_iterate(node: *const syntax_node_t,
     callback: c_syntax_node_callback_t, context: *mut c_void) {
-    let node = unsafe{ &*node }.borrow();
-    node.visit_depth_first(|node| {
-        let node = syntax_node_t{node: (node as *const SyntaxNode).cast_mut().cast()};
+    let node = unsafe{ &*node };
+    let src = node.borrow_src();
+    node.borrow().visit_depth_first(|node| {
+        let mut node = RustSyntaxNode{ node: SyntaxNodeRef::Borrowed(node), src: src.clone() };
+        let node = syntax_node_t{ node: &mut node };
         callback(&node, context);
     });
 }
@@ -521,6 +1057,21 @@ pub extern "C" fn syntax_node_src_range(node: *const syntax_node_t, range_start:
     unsafe{ *range_end = node.src_range.end; }
 }
 
+/// @brief Renders the source text represented by the syntax node into a buffer
+/// @ingroup tokenizer_and_parser_group
+/// @param[in]  node  A pointer to the `syntax_node_t`
+/// @param[out]  buf  A buffer into which the text will be rendered
+/// @param[in]  buf_len  The maximum allocated size of `buf`
+/// @return The length of the text string, minus the string terminator character.  If
+///    `return_value > buf_len + 1`, then the text was not fully rendered and this function should be
+///    called again with a larger buffer.
+///
+#[no_mangle]
+pub extern "C" fn syntax_node_text(node: *const syntax_node_t, buf: *mut c_char, buf_len: usize) -> usize {
+    let node = unsafe{ &*node };
+    write_into_buf(node.borrow_src().node_text(node.borrow()), buf, buf_len)
+}
+
 // =-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
 // MeTTa Language and Types
 // =-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
@@ -605,6 +1156,16 @@ pub extern "C" fn atom_error_message(atom: *const atom_ref_t, buf: *mut c_char,
 ///
 #[no_mangle] pub extern "C" fn ATOM_TYPE_GROUNDED() -> atom_t { hyperon::metta::ATOM_TYPE_GROUNDED.into() }
 
+/// @brief Creates a Symbol atom for the special MeTTa symbol: "->", used to construct the types of functions
+/// @ingroup metta_language_group
+/// @return  The `atom_t` representing the Symbol atom
+/// @note The returned `atom_t` must be freed with `atom_free()`
+/// @note A function type is an expression which starts with this symbol, followed by the types of the
+///    arguments and the type of the result.  For example `(-> Number Number Bool)` is constructed by
+///    passing `ATOM_TYPE_ARROW()`, two `Number` symbols and a `Bool` symbol to `atom_expr()`
+///
+#[no_mangle] pub extern "C" fn ATOM_TYPE_ARROW() -> atom_t { hyperon::metta::ARROW_SYMBOL.into() }
+
 /// @brief Creates a Symbol atom for the special MeTTa symbol used to indicate that an atom's type is a wrapper around a Space
 /// @ingroup metta_language_group
 /// @return  The `atom_t` representing the Symbol atom
@@ -649,6 +1210,57 @@ pub extern "C" fn atom_error_message(atom: *const atom_ref_t, buf: *mut c_char,
     hyperon::metta::METTA_SYMBOL.into()
 }
 
+/// @brief Creates a Symbol atom for the special symbol: ",", used to construct conjunction queries
+/// @ingroup metta_language_group
+/// @return  The `atom_t` representing the Symbol atom
+/// @note The returned `atom_t` must be freed with `atom_free()`
+/// @note An expression starting from this symbol, for example `(, (A $x) ($x C))`, passed to `space_query()`
+///    matches each of the remaining atoms against the Space, and returns only the results which are
+///    consistent with each other
+///
+#[no_mangle] pub extern "C" fn COMMA_SYMBOL() -> atom_t {
+    hyperon::space::grounding::COMMA_SYMBOL.into()
+}
+
+/// @brief Creates a Grounded atom for an integer number
+/// @ingroup metta_language_group
+/// @param[in]  value  The value of the number
+/// @return  The `atom_t` representing the number
+/// @note The atom has the `Number` type and it is the same atom the default Tokenizer creates for the
+///    integer literals, for example `42`
+/// @note The returned `atom_t` must be freed with `atom_free()`
+///
+#[no_mangle]
+pub extern "C" fn atom_int(value: i64) -> atom_t {
+    Atom::gnd(Number::Integer(value)).into()
+}
+
+/// @brief Creates a Grounded atom for a floating point number
+/// @ingroup metta_language_group
+/// @param[in]  value  The value of the number
+/// @return  The `atom_t` representing the number
+/// @note The atom has the `Number` type and it is the same atom the default Tokenizer creates for the
+///    floating point literals, for example `4.2`
+/// @note The returned `atom_t` must be freed with `atom_free()`
+///
+#[no_mangle]
+pub extern "C" fn atom_float(value: f64) -> atom_t {
+    Atom::gnd(Number::Float(value)).into()
+}
+
+/// @brief Creates a Grounded atom for a string
+/// @ingroup metta_language_group
+/// @param[in]  value  A C-style string containing the value of the string, without quotes
+/// @return  The `atom_t` representing the string
+/// @note The atom has the `String` type and it is the same atom the default Tokenizer creates for the
+///    string literals, for example `"text"`.  The text of the atom is quoted
+/// @note The returned `atom_t` must be freed with `atom_free()`
+///
+#[no_mangle]
+pub extern "C" fn atom_string(value: *const c_char) -> atom_t {
+    Atom::gnd(Str::from_string(cstr_into_string(value))).into()
+}
+
 /// @brief Checks whether Atom `atom` has Type `typ` in context of `space`
 /// @ingroup metta_language_group
 /// @param[in]  space  A pointer to the `space_t` representing the space context in which to perform the check
@@ -665,6 +1277,34 @@ pub extern "C" fn check_type(space: *const space_t, atom: *const atom_ref_t, typ
     hyperon::metta::types::check_type(dyn_space.borrow().as_space(), atom, typ)
 }
 
+/// @brief Checks whether Atom `atom` has Type `typ` in context of `space`, and provides the Bindings which
+///    made each successful check
+/// @ingroup metta_language_group
+/// @param[in]  space  A pointer to the `space_t` representing the space context in which to perform the check
+/// @param[in]  atom  A pointer to the `atom_t` or `atom_ref_t` representing the atom whose Type the function will check
+/// @param[in]  typ  A pointer to the `atom_t` or `atom_ref_t` representing the type to check against
+/// @param[in]  callback  A function that will be called once for each type of `atom` which matches `typ`, with the
+///    Bindings of the variables in `typ`
+/// @param[in]  context  A pointer to a caller-defined structure to facilitate communication with the `callback` function
+/// @note Unlike `check_type()`, this function makes it possible to infer parameters of generic types. For example
+///    checking an atom of type `(List A)` against `(List $t)` provides the Bindings `{ $t = A }`. A successful check
+///    against a non-parametric type provides empty Bindings, and the callback is not called if the check fails.
+/// @note The `bindings_t` passed to the callback is owned by this function and is freed after the callback returns
+///
+#[no_mangle]
+pub extern "C" fn check_type_bindings(space: *const space_t, atom: *const atom_ref_t, typ: *const atom_ref_t,
+        callback: bindings_mut_callback_t, context: *mut c_void) {
+    let dyn_space = unsafe{ &*space }.borrow();
+    let atom = unsafe{ &*atom }.borrow();
+    let typ = unsafe{ &*typ }.borrow();
+    let types = hyperon::metta::types::get_type_bindings(dyn_space.borrow().as_space(), atom, typ);
+    for (_typ, bindings) in types {
+        let mut c_bindings = bindings_t::from(bindings);
+        callback(&mut c_bindings, context);
+        c_bindings.into_inner();
+    }
+}
+
 /// @brief Checks whether `atom` is correctly typed
 /// @ingroup metta_language_group
 /// @param[in]  space  A pointer to the `space_t` representing the space context in which to perform the check
@@ -679,6 +1319,30 @@ pub extern "C" fn validate_atom(space: *const space_t, atom: *const atom_ref_t)
     hyperon::metta::types::validate_atom(dyn_space.borrow().as_space(), atom)
 }
 
+/// @brief Checks whether `atom` is correctly typed, and describes the type error if it is not
+/// @ingroup metta_language_group
+/// @param[in]  space  A pointer to the `space_t` representing the space context in which to perform the check
+/// @param[in]  atom  A pointer to the `atom_t` or `atom_ref_t` representing the atom whose Type the function will check
+/// @param[out]  err_buf  A buffer into which the text describing the type error will be written
+/// @param[in]  buf_len  The size of the allocated `err_buf`, in bytes
+/// @return  `true` if the Atom is correctly typed, otherwise `false`
+/// @note When the function returns `false` the message written into `err_buf` names the incorrectly typed
+///    argument, its position, and its expected and actual types.  Nothing is written if the function returns `true`
+///
+#[no_mangle]
+pub extern "C" fn validate_atom_explain(space: *const space_t, atom: *const atom_ref_t,
+        err_buf: *mut c_char, buf_len: usize) -> bool {
+    let dyn_space = unsafe{ &*space }.borrow();
+    let atom = unsafe{ &*atom }.borrow();
+    match hyperon::metta::types::validate_atom_explain(dyn_space.borrow().as_space(), atom) {
+        Ok(()) => true,
+        Err(message) => {
+            write_into_buf(message, err_buf, buf_len);
+            false
+        },
+    }
+}
+
 /// @brief Provides all types for `atom` in the context of `space`
 /// @ingroup metta_language_group
 /// @param[in]  space  A pointer to the `space_t` representing the space context in which to access the Atom's types
@@ -697,6 +1361,24 @@ pub extern "C" fn get_atom_types(space: *const space_t, atom: *const atom_ref_t,
     return_atoms(&types, callback, context);
 }
 
+/// @brief Provides the primary type for `atom` in the context of `space`
+/// @ingroup metta_language_group
+/// @param[in]  space  A pointer to the `space_t` representing the space context in which to access the Atom's types
+/// @param[in]  atom  A pointer to the `atom_t` or `atom_ref_t` representing the atom whose Type the function will access
+/// @return  An `atom_t` for the first type `get_atom_types()` would provide for `atom`, or `%Undefined%` if
+///    there is no type which applies
+/// @note This is a convenience function for the common case when only a single type is needed. Use
+///    `get_atom_types()` to access all types of the atom.
+/// @warning The returned `atom_t` must be freed with `atom_free()`
+///
+#[no_mangle]
+pub extern "C" fn get_atom_type_single(space: *const space_t, atom: *const atom_ref_t) -> atom_t {
+    let dyn_space = unsafe{ &*space }.borrow();
+    let atom = unsafe{ (&*atom).borrow() };
+    let types = hyperon::metta::types::get_atom_types(dyn_space.borrow().as_space(), atom);
+    types.into_iter().next().unwrap_or(hyperon::metta::ATOM_TYPE_UNDEFINED).into()
+}
+
 // =-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
 // MeTTa Intperpreter Interface
 // =-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
@@ -720,6 +1402,10 @@ pub struct step_result_t {
     result: *mut RustStepResult,
 }
 
+// Transparent because `step_result_t::ref_wrapper` casts a borrowed `InterpreterState` to it.  The
+// repr is wrapped in `cfg_attr` so cbindgen keeps the type opaque instead of emitting a typedef of
+// the Rust type
+#[cfg_attr(all(), repr(transparent))]
 struct RustStepResult(InterpreterState<'static, DynSpace>);
 
 impl From<InterpreterState<'static, DynSpace>> for step_result_t {
@@ -729,6 +1415,10 @@ impl From<InterpreterState<'static, DynSpace>> for step_result_t {
 }
 
 impl step_result_t {
+    /// WARNING: The output of this function must NOT be passed to into_inner
+    fn ref_wrapper(state: &InterpreterState<'static, DynSpace>) -> Self {
+        Self{ result: (state as *const InterpreterState<'static, DynSpace>).cast_mut().cast() }
+    }
     fn into_inner(self) -> InterpreterState<'static, DynSpace> {
         unsafe{ Box::from_raw(self.result).0 }
     }
@@ -754,42 +1444,162 @@ pub extern "C" fn interpret_init(space: *mut space_t, expr: *const atom_ref_t) -
     step.into()
 }
 
+/// @brief Initializes an interpreter operation with some variables bound, and take the initial step
+/// @ingroup interpreter_group
+/// @param[in]  space  A pointer to the Space in which to perform the operation
+/// @param[in]  expr  A pointer to an `atom_t` or `atom_ref_t` Expression atom to interpret
+/// @param[in]  bindings  A pointer to the `bindings_t` containing the values of the variables to substitute
+///    into `expr` before the interpretation begins
+/// @return A `step_result_t` representing the outcome from the initial step
+/// @note Variables in `expr` which aren't bound by `bindings` stay free
+/// @note Ultimately `step_get_result()` must be called to release the returned `step_result_t`
+/// @see interpret_init
+///
+#[no_mangle]
+pub extern "C" fn interpret_init_with_bindings(space: *mut space_t, expr: *const atom_ref_t,
+        bindings: *const bindings_t) -> step_result_t {
+    let dyn_space = unsafe{ &*space }.borrow();
+    let expr = unsafe{ (&*expr).borrow() };
+    let bindings = unsafe{ &*bindings }.borrow();
+    let expr = matcher::apply_bindings_to_atom_move(expr.clone(), bindings);
+    let step = interpreter::interpret_init(dyn_space.clone(), &expr);
+    step.into()
+}
+
 /// @brief Takes a subsequent step in an in-flight interpreter operation
 /// @ingroup interpreter_group
 /// @param[in]  step  The existing state for the in-flight interpreter operation
 /// @return A new `step_result_t` representing the outcome from the step
 ///
 #[no_mangle]
-pub extern "C" fn interpret_step(step: step_result_t) -> step_result_t {
-    let step = step.into_inner();
-    let next = interpreter::interpret_step(step);
-    next.into()
+pub extern "C" fn interpret_step(step: step_result_t) -> step_result_t {
+    let step = step.into_inner();
+    let next = interpreter::interpret_step(step);
+    next.into()
+}
+
+/// @brief Takes up to `max_sub_steps` subsequent steps in an in-flight interpreter operation
+/// @ingroup interpreter_group
+/// @param[in]  step  The existing state for the in-flight interpreter operation
+/// @param[in]  max_sub_steps  The maximum number of steps to take before returning
+/// @return A new `step_result_t` representing the outcome from the last step taken
+/// @note This function returns early if the operation is fully resolved before the budget is exhausted.
+///    If the budget is exhausted first, the returned `step_result_t` is a valid partially-completed state
+///    and `step_has_next()` will return `true`.  The caller may pass it to another call to
+///    `interpret_step_with_limit()` or `interpret_step()` to continue the operation.  This makes it
+///    possible to interleave the interpretation with the host's own work, without blocking for an
+///    unbounded amount of time
+/// @note If `max_sub_steps` is 0, the provided `step` is returned unchanged
+///
+#[no_mangle]
+pub extern "C" fn interpret_step_with_limit(step: step_result_t, max_sub_steps: usize) -> step_result_t {
+    let mut step = step.into_inner();
+    for _ in 0..max_sub_steps {
+        if !step.has_next() {
+            break;
+        }
+        step = interpreter::interpret_step(step);
+    }
+    step.into()
+}
+
+/// @brief Renders a text description of a `step_result_t` into a buffer
+/// @ingroup interpreter_group
+/// @param[in]  step  A pointer to a `step_result_t` to render
+/// @param[out]  buf  A buffer into which the text will be rendered
+/// @param[in]  buf_len  The maximum allocated size of `buf`
+/// @return The length of the description string, minus the string terminator character.  If
+///    `return_value > buf_len + 1`, then the text was not fully rendered and this function should be
+///    called again with a larger buffer.
+///
+#[no_mangle]
+pub extern "C" fn step_to_str(step: *const step_result_t, buf: *mut c_char, buf_len: usize) -> usize {
+    let step = unsafe{ &*step }.borrow();
+    write_debug_into_buf(step, buf, buf_len)
+}
+
+/// @brief Examines a `step_result_t` to determine if more work is needed
+/// @ingroup interpreter_group
+/// @param[in]  step  A pointer to the `step_result_t` representing the in-flight interpreter operation
+/// @return `true` if the operation plan indicates that more work is needed to finalize results, otherwise `false`
+///
+#[no_mangle]
+pub extern "C" fn step_has_next(step: *const step_result_t) -> bool {
+    let step = unsafe{ &*step }.borrow();
+    step.has_next()
+}
+
+/// @brief Provides the atoms which are going to be evaluated by the next steps of an in-flight
+///    interpreter operation
+/// @ingroup interpreter_group
+/// @param[in]  step  A pointer to the `step_result_t` representing the in-flight interpreter operation
+/// @param[in]  callback  A function that will be called to provide a vector of the atoms currently being
+///    evaluated, one per alternative, starting with the alternative which will be evaluated first
+/// @param[in]  context  A pointer to a caller-defined structure to facilitate communication with the `callback` function
+/// @note This function doesn't consume the `step_result_t`.  It is intended for debugger tooling, which
+///    may call it between calls to `interpret_step()` to display the evaluation frontier step-by-step
+/// @note The callback is called with an empty vector if there are no intermediate atoms to provide,
+///    for example when the operation has been fully resolved
+///
+#[no_mangle]
+pub extern "C" fn step_get_current_atoms(step: *const step_result_t,
+        callback: c_atom_vec_callback_t, context: *mut c_void) {
+    let step = unsafe{ &*step }.borrow();
+    let atoms: Vec<Atom> = step.current_atoms().cloned().collect();
+    return_atoms(&atoms, callback, context);
+}
+
+/// @brief Returns the number of result atoms of an interpreter operation, without consuming the `step_result_t`
+/// @ingroup interpreter_group
+/// @param[in]  step  A pointer to the `step_result_t` representing the interpreter operation
+/// @return The number of atoms which `step_get_result()` will provide to its callback
+/// @note The count is final only when `step_has_next()` returns `false`.  Calling this function while the
+///    operation is still in progress returns the number of results found so far
+///
+#[no_mangle]
+pub extern "C" fn step_result_count(step: *const step_result_t) -> usize {
+    let step = unsafe{ &*step }.borrow();
+    step.result_count()
 }
 
-/// @brief Renders a text description of a `step_result_t` into a buffer
+/// @brief Examines a `step_result_t` to determine if the interpreter operation resulted in an error
 /// @ingroup interpreter_group
-/// @param[in]  step  A pointer to a `step_result_t` to render
-/// @param[out]  buf  A buffer into which the text will be rendered
-/// @param[in]  buf_len  The maximum allocated size of `buf`
-/// @return The length of the description string, minus the string terminator character.  If
-///    `return_value > buf_len + 1`, then the text was not fully rendered and this function should be
-///    called again with a larger buffer.
+/// @param[in]  step  A pointer to the `step_result_t` representing the interpreter operation
+/// @return `true` if the operation is finished and one of its results is an error expression,
+///    otherwise `false`
+/// @note This function doesn't consume the `step_result_t`, so it can be called before `step_get_result()`
+///    to decide how the results should be handled
+/// @see step_get_error_message
 ///
 #[no_mangle]
-pub extern "C" fn step_to_str(step: *const step_result_t, buf: *mut c_char, buf_len: usize) -> usize {
+pub extern "C" fn step_is_error(step: *const step_result_t) -> bool {
     let step = unsafe{ &*step }.borrow();
-    write_debug_into_buf(step, buf, buf_len)
+    step.error().is_some()
 }
 
-/// @brief Examines a `step_result_t` to determine if more work is needed
+/// @brief Renders the message of the error the interpreter operation resulted in into a buffer
 /// @ingroup interpreter_group
-/// @param[in]  step  A pointer to the `step_result_t` representing the in-flight interpreter operation
-/// @return `true` if the operation plan indicates that more work is needed to finalize results, otherwise `false`
+/// @param[in]  step  A pointer to the `step_result_t` representing the interpreter operation
+/// @param[out]  buf  A buffer into which the text will be rendered
+/// @param[in]  buf_len  The maximum allocated size of `buf`
+/// @return The length of the message string, minus the string terminator character.  If
+///    `return_value > buf_len + 1`, then the text was not fully rendered and this function should be
+///    called again with a larger buffer.  If `step_is_error()` returns `false` then 0 is returned
+///    and nothing is written into `buf`
+/// @note This function doesn't consume the `step_result_t`
 ///
 #[no_mangle]
-pub extern "C" fn step_has_next(step: *const step_result_t) -> bool {
+pub extern "C" fn step_get_error_message(step: *const step_result_t, buf: *mut c_char, buf_len: usize) -> usize {
     let step = unsafe{ &*step }.borrow();
-    step.has_next()
+    let message = match step.error() {
+        Some(Atom::Expression(expr)) => match expr.children().last() {
+            Some(Atom::Symbol(sym)) => sym.name().to_string(),
+            Some(atom) => atom.to_string(),
+            None => return 0,
+        },
+        _ => return 0,
+    };
+    write_into_buf(message, buf, buf_len)
 }
 
 /// @brief Consumes a `step_result_t` and provides the ultimate outcome of a MeTTa interpreter session
@@ -944,6 +1754,37 @@ pub extern "C" fn metta_new_core(space: *mut space_t, env_builder: env_builder_t
     metta.into()
 }
 
+/// @brief Creates a new top-level MeTTa Runner, that uses a specific environment
+/// @ingroup interpreter_group
+/// @param[in]  space  A pointer to a handle for the Space for use in the Runner's top-level module.
+///     Pass NULL to create a new Space
+/// @param[in]  tokenizer  A pointer to a handle for the Tokenizer for use in the Runner's top-level module.
+///     Pass NULL to create a new Tokenizer
+/// @param[in]  env  An `env_t` handle to the environment to use.  This function takes ownership of the
+///     `env_t`, so it must not be freed or used again by the caller
+/// @return A `metta_t` handle to the newly created Runner
+/// @note The caller must take ownership responsibility for the returned `metta_t`, and free it with `metta_free()`
+/// @note The `space` and `tokenizer` handles are borrowed, and must still be freed by the caller
+/// @note Unlike `metta_new`, the runner's module search paths and configuration come from `env`
+///     rather than from the common environment, so runners with different include paths can
+///     coexist in the same process
+///
+#[no_mangle]
+pub extern "C" fn metta_new_with_env(space: *mut space_t, tokenizer: *mut tokenizer_t, env: env_t) -> metta_t {
+    let dyn_space = if space.is_null() {
+        None
+    } else {
+        Some(unsafe{ &*space }.borrow().clone())
+    };
+    let tokenizer = if tokenizer.is_null() {
+        None
+    } else {
+        Some(unsafe{ &*tokenizer }.clone_handle())
+    };
+    let metta = Metta::new_with_environment(None, dyn_space, tokenizer, env.into_inner());
+    metta.into()
+}
+
 /// @brief Clones a `metta_t` handle
 /// @ingroup interpreter_group
 /// @param[in]  metta  The handle to clone
@@ -968,15 +1809,15 @@ pub extern "C" fn metta_free(metta: metta_t) {
     drop(metta);
 }
 
-/// @brief Returns the error string associated with the last `metta_run`, `metta_evaluate_atom`,
-///     or `metta_load_module` call
+/// @brief Returns the error string associated with the last `metta_run`, `metta_run_str`,
+///     `metta_evaluate_atom`, or `metta_load_module` call
 /// @ingroup interpreter_group
 /// @param[in]  metta  A pointer to the MeTTa handle
 /// @return A pointer to the C-string containing the error that occurred, or NULL if no
 ///     error occurred
 /// @warning The returned pointer should NOT be freed.  It must never be accessed after the
-///     metta_t has been freed, or any subsequent call to `metta_run`, `metta_evaluate_atom`, or
-///     `metta_load_module` has been made.
+///     metta_t has been freed, or any subsequent call to `metta_run`, `metta_run_str`,
+///     `metta_evaluate_atom`, or `metta_load_module` has been made.
 ///
 #[no_mangle]
 pub extern "C" fn metta_err_str(metta: *const metta_t) -> *const c_char {
@@ -1021,6 +1862,107 @@ pub extern "C" fn metta_tokenizer(metta: *mut metta_t) -> tokenizer_t {
     metta.tokenizer().clone().into()
 }
 
+/// @brief Function signature for a native function which implements a grounded operation
+/// @ingroup interpreter_group
+/// @param[in]  args  A pointer to an `atom_vec_t` containing the arguments of the operation call.  This vector
+///    should not be modified or freed by the function
+/// @param[out]  out  A pointer to an `atom_vec_t` into which the function should push the results
+/// @param[in]  context  The context pointer passed when the operation was registered
+/// @return `true` if the operation is executed successfully, or `false` if the operation failed
+/// @see metta_register_grounded_op
+///
+pub type grounded_op_exec_t = extern "C" fn(args: *const atom_vec_t, out: *mut atom_vec_t, context: *mut c_void) -> bool;
+
+/// Grounded atom which executes a native function registered by `metta_register_grounded_op()`
+#[derive(Clone, Debug)]
+struct CGroundedOp {
+    name: String,
+    typ: Atom,
+    exec: grounded_op_exec_t,
+    context: *mut c_void,
+}
+
+impl PartialEq for CGroundedOp {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.context == other.context
+    }
+}
+
+impl std::fmt::Display for CGroundedOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Grounded for CGroundedOp {
+    fn type_(&self) -> Atom {
+        self.typ.clone()
+    }
+
+    fn as_execute(&self) -> Option<&dyn CustomExecute> {
+        Some(self)
+    }
+}
+
+impl CustomExecute for CGroundedOp {
+    fn execute(&self, args: &[Atom]) -> Result<Vec<Atom>, ExecError> {
+        let c_args: atom_vec_t = args.into();
+        let mut out: atom_vec_t = Vec::new().into();
+        if (self.exec)(&c_args, &mut out, self.context) {
+            Ok(out.into())
+        } else {
+            Err(ExecError::Runtime(format!("Grounded operation {} failed", self.name)))
+        }
+    }
+}
+
+/// @brief Registers a native function as a grounded operation, which can be called from MeTTa code by name
+/// @ingroup interpreter_group
+/// @param[in]  metta  A pointer to the runner handle
+/// @param[in]  name  A C-style string containing the name of the operation.  The runner's Tokenizer will
+///    replace each occurrence of the name in the parsed text by the grounded atom which executes `exec`
+/// @param[in]  exec  The function which will be called to execute the operation
+/// @param[in]  context  A pointer to a caller-defined structure which will be passed to `exec`
+/// @note If `exec` returns `false` the evaluation of the operation call produces an error
+/// @note The registered operation has the `%Undefined%` type, so the arguments are not checked by the type
+///    checker.  The `context` must remain valid for as long as the runner or any atoms containing the
+///    operation exist
+///
+#[no_mangle]
+pub extern "C" fn metta_register_grounded_op(metta: *mut metta_t, name: *const c_char,
+        exec: grounded_op_exec_t, context: *mut c_void) {
+    let metta = unsafe{ &*metta }.borrow();
+    register_grounded_op(metta, cstr_as_str(name), hyperon::metta::ATOM_TYPE_UNDEFINED, exec, context);
+}
+
+/// @brief Registers a native function as a grounded operation with the specified type
+/// @ingroup interpreter_group
+/// @param[in]  metta  A pointer to the runner handle
+/// @param[in]  name  A C-style string containing the name of the operation.  The runner's Tokenizer will
+///    replace each occurrence of the name in the parsed text by the grounded atom which executes `exec`
+/// @param[in]  type_atom  A pointer to the `atom_t` or `atom_ref_t` representing the type of the operation,
+///    for example `(-> Number Number)`.  The atom is cloned by this function
+/// @param[in]  exec  The function which will be called to execute the operation
+/// @param[in]  context  A pointer to a caller-defined structure which will be passed to `exec`
+/// @note Unlike `metta_register_grounded_op()`, calls of the operation can be checked by `check_type()` and
+///    `validate_atom()`, and the interpreter doesn't call `exec` when the arguments have incorrect types
+/// @note The `context` must remain valid for as long as the runner or any atoms containing the operation exist
+///
+#[no_mangle]
+pub extern "C" fn metta_register_grounded_op_typed(metta: *mut metta_t, name: *const c_char,
+        type_atom: *const atom_ref_t, exec: grounded_op_exec_t, context: *mut c_void) {
+    let metta = unsafe{ &*metta }.borrow();
+    let typ = unsafe{ &*type_atom }.borrow().clone();
+    register_grounded_op(metta, cstr_as_str(name), typ, exec, context);
+}
+
+/// Internal function to register a `CGroundedOp` in the runner's tokenizer
+fn register_grounded_op(metta: &Metta, name: &str, typ: Atom, exec: grounded_op_exec_t, context: *mut c_void) {
+    let op = Atom::gnd(CGroundedOp{ name: name.into(), typ, exec, context });
+    let regex = Regex::new(&regex::escape(name)).unwrap();
+    metta.tokenizer().borrow_mut().register_token(regex, move |_| op.clone());
+}
+
 /// @brief Renders the working directory of the runner's environment into a buffer
 /// @ingroup interpreter_group
 /// @param[in]  metta  A pointer to the runner handle
@@ -1072,6 +2014,78 @@ pub extern "C" fn metta_run(metta: *mut metta_t, parser: sexpr_parser_t,
     }
 }
 
+/// @brief Runs the MeTTa runner until the input text has been fully parsed and evaluated, providing
+///    the results of each expression as soon as the expression is evaluated
+/// @ingroup interpreter_group
+/// @param[in]  metta  A pointer to the runner handle
+/// @param[in]  parser  An S-Expression Parser containing the MeTTa text
+/// @param[in]  callback  A function that will be called to provide a vector of atoms produced by the evaluation
+///    of each top-level expression
+/// @param[in]  context  A pointer to a caller-defined structure to facilitate communication with the `callback` function
+/// @note Unlike `metta_run`, this function doesn't wait until all the text is evaluated, so a REPL can print
+///    the results progressively.  The callback is called in the order the expressions appear in the text
+/// @note If this function encounters an error, the evaluation is stopped and the error may be accessed with
+///    `metta_err_str()`.  The results of the expressions evaluated before the error are already provided to the callback
+/// @warning  Ownership of the provided parser will be taken by this function, so it must not be subsequently accessed
+///     nor freed.
+///
+#[no_mangle]
+pub extern "C" fn metta_run_streaming(metta: *mut metta_t, parser: sexpr_parser_t,
+        callback: c_atom_vec_callback_t, context: *mut c_void) {
+    let metta = unsafe{ &mut *metta };
+    metta.free_err_string();
+    let parser = parser.into_boxed_dyn();
+    let mut state = RunnerState::new_with_parser(metta.borrow(), parser);
+    let mut returned = 0;
+    let mut run = || -> Result<(), String> {
+        while !state.is_complete() {
+            state.run_step()?;
+            let results = state.current_results();
+            for result in &results[returned..] {
+                return_atoms(result, callback, context);
+            }
+            returned = results.len();
+        }
+        Ok(())
+    };
+    if let Err(err) = run() {
+        drop(state);
+        let err_cstring = std::ffi::CString::new(err).unwrap();
+        metta.err_string = err_cstring.into_raw();
+    }
+}
+
+/// @brief Parses and runs a string of MeTTa code, until the text has been fully evaluated
+/// @ingroup interpreter_group
+/// @param[in]  metta  A pointer to the runner handle
+/// @param[in]  text  A C-style string containing the MeTTa code to run
+/// @param[in]  callback  A function that will be called to provide a vector of atoms produced by the evaluation
+/// @param[in]  context  A pointer to a caller-defined structure to facilitate communication with the `callback` function
+/// @note The text is parsed using the runner's own Tokenizer.  Unlike `metta_run`, there is no need to
+///     create a parser, and the `text` may be freed as soon as this function returns
+/// @note If this function encounters an error, the callback will not be called and the error may be accessed with `metta_err_str()`
+///
+#[no_mangle]
+pub extern "C" fn metta_run_str(metta: *mut metta_t, text: *const c_char,
+        callback: c_atom_vec_callback_t, context: *mut c_void) {
+    let metta = unsafe{ &mut *metta };
+    metta.free_err_string();
+    let parser = SExprParser::new(cstr_as_str(text));
+    let rust_metta = metta.borrow();
+    let results = rust_metta.run(parser);
+    match results {
+        Ok(results) => {
+            for result in results {
+                return_atoms(&result, callback, context);
+            }
+        },
+        Err(err) => {
+            let err_cstring = std::ffi::CString::new(err).unwrap();
+            metta.err_string = err_cstring.into_raw();
+        }
+    }
+}
+
 /// @brief Runs the MeTTa runner to evaluate an input Atom
 /// @ingroup interpreter_group
 /// @param[in]  metta  A pointer to the runner handle
@@ -1098,6 +2112,79 @@ pub extern "C" fn metta_evaluate_atom(metta: *mut metta_t, atom: atom_t,
     }
 }
 
+/// @brief Runs the MeTTa runner to evaluate an input Atom, stopping once enough results are found
+/// @ingroup interpreter_group
+/// @param[in]  metta  A pointer to the runner handle
+/// @param[in]  atom  The `atom_t` representing the atom to evaluate
+/// @param[in]  max_results  The maximal number of result atoms to produce
+/// @param[in]  callback  A function that will be called to provide a vector of at most `max_results`
+///    atoms produced by the evaluation
+/// @param[in]  context  A pointer to a caller-defined structure to facilitate communication with the `callback` function
+/// @note The evaluation stops as soon as `max_results` results are found, and the remaining alternatives of
+///    a nondeterministic evaluation are abandoned.  Which results are found first depends on the interpreter
+/// @note If this function encounters an error, the callback will not be called and the error may be accessed with `metta_err_str()`
+/// @warning This function takes ownership of the provided `atom_t`, so it must not be subsequently accessed or freed
+///
+#[no_mangle]
+pub extern "C" fn metta_evaluate_atom_limited(metta: *mut metta_t, atom: atom_t, max_results: usize,
+        callback: c_atom_vec_callback_t, context: *mut c_void) {
+    let metta = unsafe{ &mut *metta };
+    metta.free_err_string();
+    let atom = atom.into_inner();
+    let rust_metta = metta.borrow();
+    let result = rust_metta.evaluate_atom_limited(atom, max_results);
+    match result {
+        Ok(result) => return_atoms(&result, callback, context),
+        Err(err) => {
+            let err_cstring = std::ffi::CString::new(err).unwrap();
+            metta.err_string = err_cstring.into_raw();
+        }
+    }
+}
+
+/// @brief Function signature for a callback which is notified about each step of an evaluation
+/// @ingroup interpreter_group
+/// @param[in]  step  A pointer to the `step_result_t` representing the state of the interpreter after the step
+/// @param[in]  context  The context pointer passed to `metta_evaluate_atom_traced()`
+/// @warning The `step_result_t` is borrowed from the running evaluation.  It must not be freed, passed to
+///    `interpret_step()` or `step_get_result()`, nor accessed after the callback returns
+/// @see metta_evaluate_atom_traced
+///
+pub type c_step_callback_t = extern "C" fn(step: *const step_result_t, context: *mut c_void);
+
+/// @brief Runs the MeTTa runner to evaluate an input Atom, notifying a callback about each step
+/// @ingroup interpreter_group
+/// @param[in]  metta  A pointer to the runner handle
+/// @param[in]  atom  The `atom_t` representing the atom to evaluate
+/// @param[in]  step_callback  A function that will be called after each step of the interpreter
+/// @param[in]  callback  A function that will be called to provide a vector of atoms produced by the evaluation
+/// @param[in]  context  A pointer to a caller-defined structure passed to both `step_callback` and `callback`
+/// @note The evaluation produces the same results as `metta_evaluate_atom()`.  The state of the interpreter
+///    passed to `step_callback` can be inspected with the `step_` functions, for example `step_get_current_atoms()`
+/// @note `step_callback` is not called if the atom is rejected by the type checker
+/// @note If this function encounters an error, the callback will not be called and the error may be accessed with `metta_err_str()`
+/// @warning This function takes ownership of the provided `atom_t`, so it must not be subsequently accessed or freed
+///
+#[no_mangle]
+pub extern "C" fn metta_evaluate_atom_traced(metta: *mut metta_t, atom: atom_t, step_callback: c_step_callback_t,
+        callback: c_atom_vec_callback_t, context: *mut c_void) {
+    let metta = unsafe{ &mut *metta };
+    metta.free_err_string();
+    let atom = atom.into_inner();
+    let rust_metta = metta.borrow();
+    let result = rust_metta.evaluate_atom_traced(atom, |state| {
+        let step = step_result_t::ref_wrapper(state);
+        step_callback(&step, context);
+    });
+    match result {
+        Ok(result) => return_atoms(&result, callback, context),
+        Err(err) => {
+            let err_cstring = std::ffi::CString::new(err).unwrap();
+            metta.err_string = err_cstring.into_raw();
+        }
+    }
+}
+
 /// @brief Loads a module directly into the runner, from a mod_loader_callback_t
 /// @ingroup interpreter_group
 /// @param[in]  metta  A pointer to the handle specifying the runner into which to load the module
@@ -1487,9 +2574,57 @@ pub extern "C" fn environment_config_dir(buf: *mut c_char, buf_len: usize) -> us
     }
 }
 
+/// @brief Renders the working_dir path from the common environment into a text buffer
+/// @ingroup environment_group
+/// @param[out]  buf  A buffer into which the text will be written
+/// @param[in]  buf_len  The maximum allocated size of `buf`
+/// @return The length of the path string, minus the string terminator character.  If
+/// `return_value > buf_len + 1`, then the text was not fully written and this function should be
+/// called again with a larger buffer.  This function will return 0 if there is no working_dir.
+/// @note The working_dir can be set with `env_builder_set_working_dir()` before the common environment
+/// is initialized.  Otherwise it defaults to the process working directory at the time the common
+/// environment was initialized
+///
+#[no_mangle]
+pub extern "C" fn environment_working_dir(buf: *mut c_char, buf_len: usize) -> usize {
+    match Environment::common_env().working_dir() {
+        Some(path) => write_into_buf(path.display(), buf, buf_len),
+        None => write_into_buf("", buf, buf_len) //Write just the terminator char, if there is room
+    }
+}
+
+/// @brief Returns the number of directories the common environment searches for modules
+/// @ingroup environment_group
+/// @return The number of include paths, which includes the working_dir if there is one
+///
+#[no_mangle]
+pub extern "C" fn environment_include_path_count() -> usize {
+    Environment::common_env().include_paths().count()
+}
+
+/// @brief Renders one of the common environment's include paths into a text buffer
+/// @ingroup environment_group
+/// @param[in]  index  The index of the include path to render, in search priority order
+/// @param[out]  buf  A buffer into which the text will be written
+/// @param[in]  buf_len  The maximum allocated size of `buf`
+/// @return The length of the path string, minus the string terminator character.  If
+/// `return_value > buf_len + 1`, then the text was not fully written and this function should be
+/// called again with a larger buffer.  This function will return 0 if `index` is out of range.
+/// @note Include paths are searched in the order they were added with `env_builder_push_include_path()`,
+/// after the working_dir.  Paths added by the `environment.metta` config file follow those
+///
+#[no_mangle]
+pub extern "C" fn environment_include_path(index: usize, buf: *mut c_char, buf_len: usize) -> usize {
+    match Environment::common_env().include_paths().nth(index) {
+        Some(path) => write_into_buf(path.display(), buf, buf_len),
+        None => write_into_buf("", buf, buf_len) //Write just the terminator char, if there is room
+    }
+}
+
 /// @brief Represents an environment initialization, in progress
 /// @ingroup environment_group
-/// @note `env_builder_t` must be given to `environment_init_finish()` to properly release it
+/// @note `env_builder_t` must be given to `env_builder_finish()`, `env_builder_init_common_env()` or
+///     `env_builder_abort()` to properly release it
 ///
 #[repr(C)]
 pub struct env_builder_t {
@@ -1520,11 +2655,40 @@ impl env_builder_t {
     }
 }
 
+/// @brief Represents a fully initialized environment, that is independent from the common environment
+/// @ingroup environment_group
+/// @note `env_t` handles must be freed with `env_free()`
+///
+#[repr(C)]
+pub struct env_t {
+    /// Internal.  Should not be accessed directly
+    env: *const RustEnvironment,
+}
+
+// Internal wrapper type so CBindgen doesn't try and export Environment.  The pointer is cast back to
+// `Arc<Environment>` before use, so the field is never read through the wrapper.  The repr is
+// wrapped in `cfg_attr` so cbindgen keeps the type opaque
+#[cfg_attr(all(), repr(transparent))]
+#[allow(dead_code)]
+struct RustEnvironment(Environment);
+
+impl From<Arc<Environment>> for env_t {
+    fn from(env: Arc<Environment>) -> Self {
+        Self{ env: Arc::into_raw(env).cast() }
+    }
+}
+
+impl env_t {
+    fn into_inner(self) -> Arc<Environment> {
+        unsafe{ Arc::from_raw(self.env.cast()) }
+    }
+}
+
 /// @brief Begins initialization of an environment
 /// @ingroup environment_group
 /// @return The `env_builder_t` object representing the in-process environment initialization
-/// @note The `env_builder_t` must be passed to either `env_builder_init_common_env`
-///     or `metta_new_with_space` in order to properly deallocate it
+/// @note The `env_builder_t` must be passed to either `env_builder_init_common_env`,
+///     `env_builder_finish` or `metta_new_with_space` in order to properly deallocate it
 ///
 #[no_mangle]
 pub extern "C" fn env_builder_start() -> env_builder_t {
@@ -1566,6 +2730,47 @@ pub extern "C" fn env_builder_init_common_env(builder: env_builder_t) -> bool {
     builder.try_init_common_env().is_ok()
 }
 
+/// @brief Finishes initialization of an environment, that is independent from the common environment
+/// @ingroup environment_group
+/// @param[in]  builder  The in-process environment builder state to use to create the environment
+/// @return  An `env_t` handle to the newly created environment
+/// @note Unlike `env_builder_init_common_env`, this function may be called any number of times, to
+///     create multiple environments in the same process.  For example to isolate unit tests from each other
+/// @note The caller must take ownership responsibility for the returned `env_t`, and free it with `env_free()`
+///
+#[no_mangle]
+pub extern "C" fn env_builder_finish(builder: env_builder_t) -> env_t {
+    let builder = builder.into_inner();
+    Arc::new(builder.build()).into()
+}
+
+/// @brief Abandons an in-process environment initialization, releasing the builder state
+/// @ingroup environment_group
+/// @param[in]  builder  The in-process environment builder state to discard
+/// @note This function is intended for use when an error is encountered part-way through configuring
+///     the environment.  A new initialization may be started with `env_builder_start()` afterwards
+/// @note This function is a no-op if `builder` doesn't hold an initialization in progress, for example
+///     if it was created by `env_builder_use_default()`
+///
+#[no_mangle]
+pub extern "C" fn env_builder_abort(builder: env_builder_t) {
+    if !builder.is_default() {
+        drop(builder.into_inner());
+    }
+}
+
+/// @brief Frees an `env_t` handle
+/// @ingroup environment_group
+/// @param[in]  env  The `env_t` handle to free
+/// @note The underlying environment will be deallocated when it is no longer referenced by any handle
+///     or by any MeTTa runner
+///
+#[no_mangle]
+pub extern "C" fn env_free(env: env_t) {
+    let env = env.into_inner();
+    drop(env);
+}
+
 /// @brief Sets the working directory for the environment
 /// @ingroup environment_group
 /// @param[in]  builder  A pointer to the in-process environment builder state
@@ -1604,6 +2809,61 @@ pub extern "C" fn env_builder_set_config_dir(builder: *mut env_builder_t, path:
     *builder_arg_ref = builder.into();
 }
 
+/// @brief Sets the config directory for the environment, reporting an error instead of aborting
+/// @ingroup environment_group
+/// @param[in]  builder  A pointer to the in-process environment builder state
+/// @param[in]  path  A C-style string specifying a path to the config directory
+/// @param[out]  err_buf  A buffer into which the text describing the error will be written
+/// @param[in]  buf_len  The size of the allocated `err_buf`, in bytes
+/// @return  True if the config directory was set, False if `path` is NULL, is not a directory, or
+///     the directory (or its nearest existing parent, if the directory doesn't exist yet) is not writable
+/// @note Unlike `env_builder_set_config_dir`, the path is validated immediately, so the problem is
+///     reported by this function rather than when the environment is finished.  Nothing is created
+///     on disk by this function, the directory is created when the environment is finished according
+///     to `env_builder_create_config_dir`
+/// @note The builder is left unchanged when the function returns False
+///
+#[no_mangle]
+pub extern "C" fn env_builder_set_config_dir_checked(builder: *mut env_builder_t, path: *const c_char,
+        err_buf: *mut c_char, buf_len: usize) -> bool {
+    let result = if path.is_null() {
+        Err("path cannot be NULL".to_string())
+    } else {
+        let path = PathBuf::from(cstr_as_str(path));
+        check_config_dir(&path).map(|_| path)
+    };
+    match result {
+        Ok(path) => {
+            let builder_arg_ref = unsafe{ &mut *builder };
+            let builder = core::mem::replace(builder_arg_ref, env_builder_t::null()).into_inner();
+            *builder_arg_ref = builder.set_config_dir(&path).into();
+            true
+        },
+        Err(message) => {
+            write_into_buf(message, err_buf, buf_len);
+            false
+        },
+    }
+}
+
+/// Checks that `path` can be used as a config dir without creating anything: the path must be a
+/// directory or must not exist, and the nearest existing directory must be writable.  Writability
+/// is checked by creating and removing a file, because permission bits don't account for the owner
+/// of the directory
+fn check_config_dir(path: &std::path::Path) -> Result<(), String> {
+    let existing = path.ancestors()
+        .map(|dir| if dir.as_os_str().is_empty() { std::path::Path::new(".") } else { dir })
+        .find(|dir| dir.exists())
+        .ok_or_else(|| format!("Config dir {} has no existing parent", path.display()))?;
+    if !existing.is_dir() {
+        return Err(format!("Config dir {} is not a directory", existing.display()));
+    }
+    let probe = existing.join(format!(".hyperon_write_check_{}", std::process::id()));
+    std::fs::OpenOptions::new().write(true).create_new(true).open(&probe)
+        .and_then(|_| std::fs::remove_file(&probe))
+        .map_err(|e| format!("Config dir {} is not writable: {e}", path.display()))
+}
+
 /// @brief Sets whether the config dir should be created if it doesn't already exist
 /// @ingroup environment_group
 /// @param[in]  builder  A pointer to the in-process environment builder state
@@ -1660,6 +2920,41 @@ pub extern "C" fn env_builder_push_include_path(builder: *mut env_builder_t, pat
     *builder_arg_ref = builder.into();
 }
 
+/// @brief Adds a directory to search for module imports, reporting an error instead of aborting
+/// @ingroup environment_group
+/// @param[in]  builder  A pointer to the in-process environment builder state
+/// @param[in]  path  A C-style string specifying a path to a directory, to search for modules to load
+/// @param[out]  err_buf  A buffer into which the text describing the error will be written
+/// @param[in]  buf_len  The size of the allocated `err_buf`, in bytes
+/// @return  True if the path was added, False if `path` is NULL or doesn't refer to an existing directory
+/// @note The builder is left unchanged when the function returns False
+///
+#[no_mangle]
+pub extern "C" fn env_builder_push_include_path_checked(builder: *mut env_builder_t, path: *const c_char,
+        err_buf: *mut c_char, buf_len: usize) -> bool {
+    let result = if path.is_null() {
+        Err("path cannot be NULL".to_string())
+    } else {
+        let path = PathBuf::from(cstr_as_str(path));
+        match path.is_dir() {
+            true => Ok(path),
+            false => Err(format!("Include path {} is not an existing directory", path.display())),
+        }
+    };
+    match result {
+        Ok(path) => {
+            let builder_arg_ref = unsafe{ &mut *builder };
+            let builder = core::mem::replace(builder_arg_ref, env_builder_t::null()).into_inner();
+            *builder_arg_ref = builder.push_include_path(path).into();
+            true
+        },
+        Err(message) => {
+            write_into_buf(message, err_buf, buf_len);
+            false
+        },
+    }
+}
+
 /// @brief Adds logic to interpret a foreign format for MeTTa modules loaded from the file system
 /// @ingroup environment_group
 /// @param[in]  builder  A pointer to the in-process environment builder state
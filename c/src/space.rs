@@ -148,6 +148,7 @@ pub extern "C" fn space_add(space: *mut space_t, atom: atom_t) {
 /// @param[in]  space  A pointer to the `space_t` handle to access
 /// @param[in]  atom  A pointer to an `atom_t` or `atom_ref_t` to specifying the atom to remove from the Space
 /// @return `true` if the atom was found and removed from the Space, `false` otherwise
+/// @note The `atom` is borrowed, and it remains owned by the caller
 ///
 #[no_mangle]
 pub extern "C" fn space_remove(space: *mut space_t, atom: *const atom_ref_t) -> bool {
@@ -156,6 +157,30 @@ pub extern "C" fn space_remove(space: *mut space_t, atom: *const atom_ref_t) ->
     dyn_space.borrow_mut().remove(atom)
 }
 
+/// @brief Removes a list of atoms from the Space
+/// @ingroup space_client_group
+/// @param[in]  space  A pointer to the `space_t` handle to access
+/// @param[in]  atoms  A packed buffer of `atom_ref_t`, specifying the atoms to remove from the Space
+/// @param[in]  count  The number of elements in `atoms`
+/// @return The number of atoms from `atoms` which were found and removed from the Space
+/// @note The `atoms` are borrowed, and they remain owned by the caller
+/// @note Each atom is removed as by `space_remove()`, so observers receive a separate event per removed atom
+///
+#[no_mangle]
+pub extern "C" fn space_remove_all(space: *mut space_t, atoms: *const atom_ref_t, count: usize) -> usize {
+    if atoms.is_null() {
+        if count == 0 {
+            return 0;
+        } else {
+            panic!("Null pointer is passed to space_remove_all with non-zero count");
+        }
+    }
+    let dyn_space = unsafe{ &*space }.borrow();
+    let atoms = unsafe{ std::slice::from_raw_parts(atoms, count) };
+    let mut space = dyn_space.borrow_mut();
+    atoms.iter().filter(|atom| space.remove(atom.borrow())).count()
+}
+
 /// @brief Replaces an Atom in the Space with another Atom
 /// @ingroup space_client_group
 /// @param[in]  space  A pointer to the `space_t` handle to access
@@ -163,7 +188,8 @@ pub extern "C" fn space_remove(space: *mut space_t, atom: *const atom_ref_t) ->
 /// @param[in]  to  An `atom_t` to provide a new Atom, to replace the `from` atom in the Space
 /// @return `true` if an Atom was replaced in the Space, `false` otherwise
 /// @note The `to` atom will only be added to the Space if the `from` atom is found.  Regardless, this
-///    functino will take ownership of the `to` atom.
+///    function will take ownership of the `to` atom.  The `from` atom is borrowed, and it remains owned
+///    by the caller
 /// @warning This function takes ownership of the `to` atom, and it should not be freed or accessed
 ///    after it has been provided to this function
 ///
@@ -180,6 +206,8 @@ pub extern "C" fn space_replace(space: *mut space_t, from: *const atom_ref_t, to
 /// @param[in]  pattern  A pointer to an `atom_t` or `atom_ref_t` to specify the pattern to match within the Space
 /// @return A `bindings_set_t` representing all possible results of the match
 /// @note The caller must take ownership responsibility for the returned `bindings_set_t`, and free it with `bindings_set_free()`
+/// @note A conjunction of several patterns can be queried using an expression starting from the atom
+///    returned by `COMMA_SYMBOL()`
 ///
 #[no_mangle]
 pub extern "C" fn space_query(space: *const space_t, pattern: *const atom_ref_t) -> bindings_set_t
@@ -264,6 +292,59 @@ pub extern "C" fn space_new_grounding_space() -> space_t {
     DynSpace::new(GroundingSpace::new()).into()
 }
 
+/// @brief Creates a new Space, backed by a GroundSpace, containing the specified atoms
+/// @ingroup space_client_group
+/// @param[in]  atoms  A packed buffer of `atom_t *`, representing the atoms to put into the space
+/// @param[in]  count  The number of elements in `atoms`
+/// @return a `space_t` handle to the newly created Grounding Space
+/// @note The caller takes ownership responsibility for the returned `space_t`, and it must be
+///    freed with `space_free()`
+/// @warning This function takes ownership of all `atoms`, so they must not be subsequently accessed or freed
+///
+/// # Safety
+///
+/// `atoms` must either be NULL with `count` equal to 0, or point to `count` valid and initialized
+/// `atom_t` values which are not accessed by the caller after the call
+///
+#[no_mangle]
+pub unsafe extern "C" fn grounding_space_from_atoms(atoms: *mut atom_t, count: usize) -> space_t {
+    if atoms.is_null() {
+        if count == 0 {
+            return space_new_grounding_space()
+        } else {
+            panic!("Null pointer is passed to the constructor of the space with non-zero count");
+        }
+    }
+    let c_arr = std::slice::from_raw_parts_mut(atoms, count);
+    let atoms: Vec<Atom> = c_arr.iter_mut().map(|atom| {
+        core::mem::replace(atom, atom_t::null()).into_inner()
+    }).collect();
+    DynSpace::new(GroundingSpace::from_vec(atoms)).into()
+}
+
+/// @brief Performs a "deep copy" of a Grounding Space
+/// @ingroup space_client_group
+/// @param[in]  space  A pointer to the `space_t` handle of the Grounding Space to clone
+/// @return A `space_t` handle to the new Grounding Space, containing copies of all atoms of the original space
+/// @note Unlike `space_clone_handle()`, the new space is independent, so subsequent modifications of one
+///    space don't affect another.  The observers registered in the original space are not copied, the new
+///    space has no observers
+/// @note This function is only valid for Spaces created by `space_new_grounding_space()` or
+///    `grounding_space_from_atoms()`
+/// @note The caller takes ownership responsibility for the returned `space_t`, and it must be
+///    freed with `space_free()`
+///
+#[no_mangle]
+pub extern "C" fn space_clone(space: *const space_t) -> space_t {
+    let dyn_space = unsafe{ &*space }.borrow();
+    if let Some(any_ref) = dyn_space.borrow().as_any() {
+        if let Some(grounding_space) = any_ref.downcast_ref::<GroundingSpace>() {
+            return DynSpace::new(grounding_space.clone()).into();
+        }
+    }
+    panic!("Only GroundingSpace can be cloned")
+}
+
 // =-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
 // Space Observer Interface
 // =-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
@@ -279,6 +360,9 @@ pub enum space_event_type_t {
     SPACE_EVENT_TYPE_REMOVE,
     /// @brief The event is a `Replace` event
     SPACE_EVENT_TYPE_REPLACE,
+    /// @brief The event is a `Batch` event, containing several events delivered together.  Access
+    ///    them with `space_event_batch_len()` and `space_event_batch_get()`
+    SPACE_EVENT_TYPE_BATCH,
 }
 
 /// @brief Accessor constants, to access the fields of a `space_event_t`
@@ -410,6 +494,40 @@ pub extern "C" fn space_event_get_type(event: *const space_event_t) -> space_eve
         SpaceEvent::Add(_) => space_event_type_t::SPACE_EVENT_TYPE_ADD,
         SpaceEvent::Remove(_) => space_event_type_t::SPACE_EVENT_TYPE_REMOVE,
         SpaceEvent::Replace(_, _) => space_event_type_t::SPACE_EVENT_TYPE_REPLACE,
+        SpaceEvent::Batch(_) => space_event_type_t::SPACE_EVENT_TYPE_BATCH,
+    }
+}
+
+/// @brief Returns the number of events contained in a `Batch` event
+/// @ingroup space_observer_group
+/// @param[in]  event  A pointer to the `Batch` event to inspect
+/// @return The number of events in the batch
+///
+#[no_mangle]
+pub extern "C" fn space_event_batch_len(event: *const space_event_t) -> usize {
+    let event = unsafe{ &*event }.borrow();
+    if let SpaceEvent::Batch(events) = event {
+        events.len()
+    } else {
+        panic!("SpaceEvent wasn't a Batch event")
+    }
+}
+
+/// @brief Accesses one of the events contained in a `Batch` event
+/// @ingroup space_observer_group
+/// @param[in]  event  A pointer to the `Batch` event to access
+/// @param[in]  idx  The index of the event within the batch
+/// @return A `space_event_t` referencing the event within the batch
+/// @warning The returned `space_event_t` is borrowed from the batch event.  It must not be freed with
+///    `space_event_free()`, nor accessed after the batch event has been freed
+///
+#[no_mangle]
+pub extern "C" fn space_event_batch_get(event: *const space_event_t, idx: usize) -> space_event_t {
+    let event = unsafe{ &*event }.borrow();
+    if let SpaceEvent::Batch(events) = event {
+        space_event_t::ref_wrapper(&events[idx])
+    } else {
+        panic!("SpaceEvent wasn't a Batch event")
     }
 }
 
@@ -502,6 +620,87 @@ pub extern "C" fn space_observer_get_payload(observer: *const space_observer_t)
     c_observer_ref.payload
 }
 
+/// @brief Function signature for a callback notified about the events of a Space
+/// @ingroup space_observer_group
+/// @param[in]  event_type  The type of the event.  It is never `SPACE_EVENT_TYPE_BATCH`, because the
+///    events of a batch are delivered one by one
+/// @param[in]  atom_a  The added or removed atom, or the replaced atom of a `Replace` event
+/// @param[in]  atom_b  The new atom of a `Replace` event, or NULL for other events
+/// @param[in]  context  The context pointer passed to `space_register_callback_observer()`
+/// @warning The atoms are borrowed from the event, and must not be accessed after the callback returns
+/// @see space_register_callback_observer
+///
+pub type space_event_callback_t = extern "C" fn(event_type: space_event_type_t,
+    atom_a: *const atom_ref_t, atom_b: *const atom_ref_t, context: *mut c_void);
+
+struct CCallbackObserver {
+    callback: space_event_callback_t,
+    context: *mut c_void,
+}
+
+impl SpaceObserver for CCallbackObserver {
+    fn notify(&mut self, event: &SpaceEvent) {
+        for event in event.flatten() {
+            match event {
+                SpaceEvent::Add(atom) => (self.callback)(space_event_type_t::SPACE_EVENT_TYPE_ADD,
+                    &atom.into(), std::ptr::null(), self.context),
+                SpaceEvent::Remove(atom) => (self.callback)(space_event_type_t::SPACE_EVENT_TYPE_REMOVE,
+                    &atom.into(), std::ptr::null(), self.context),
+                SpaceEvent::Replace(from, to) => (self.callback)(space_event_type_t::SPACE_EVENT_TYPE_REPLACE,
+                    &from.into(), &to.into(), self.context),
+                SpaceEvent::Batch(_) => unreachable!(),
+            }
+        }
+    }
+}
+
+/// @struct space_callback_observer_t
+/// @brief Represents a callback registered with a Space by `space_register_callback_observer()`
+/// @ingroup space_observer_group
+///
+#[repr(C)]
+pub struct space_callback_observer_t {
+    /// Internal.  Should not be accessed directly
+    observer: *mut RustCallbackObserver
+}
+
+/// Keeps the observer registered in the space until the wrapper is dropped
+struct RustCallbackObserver {
+    _token: SpaceObserverRef<CCallbackObserver>,
+}
+
+/// @brief Registers a callback function, to be notified about the activity within the Space
+/// @ingroup space_observer_group
+/// @param[in]  space  A pointer to the `space_t` handle of the space to observe
+/// @param[in]  callback  A function that will be called for each atom added, removed or replaced in the space
+/// @param[in]  context  A pointer to a caller-defined structure to facilitate communication with the `callback` function
+/// @return A `space_callback_observer_t` keeping the callback registered
+/// @note It is a simpler alternative to `space_register_observer()` for observers which don't need to
+///    own a payload.  The `context` is not freed by the space and must remain valid for as long as the
+///    callback is registered
+/// @note The caller must take ownership responsibility for the returned `space_callback_observer_t`, and
+///    it must be freed with `space_callback_observer_free()`, which unregisters the callback
+///
+#[no_mangle]
+pub extern "C" fn space_register_callback_observer(space: *mut space_t,
+        callback: space_event_callback_t, context: *mut c_void) -> space_callback_observer_t {
+    let dyn_space = unsafe{ &*space }.borrow();
+    let space = dyn_space.borrow_mut();
+    let observer = space.common().register_observer(CCallbackObserver{ callback, context });
+    space_callback_observer_t{ observer: Box::into_raw(Box::new(RustCallbackObserver{ _token: observer })) }
+}
+
+/// @brief Frees a `space_callback_observer_t`
+/// @ingroup space_observer_group
+/// @param[in]  observer  The `space_callback_observer_t` to free
+/// @note Freeing the observer unregisters the callback, so it is not called after this function returns
+///
+#[no_mangle]
+pub extern "C" fn space_callback_observer_free(observer: space_callback_observer_t) {
+    let observer = unsafe{ Box::from_raw(observer.observer) };
+    drop(observer);
+}
+
 //-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-
 // Space Implementation Interface (Space & SpaceMut trait interface wrapper)
 //-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-=-+-
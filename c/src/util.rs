@@ -9,6 +9,14 @@ pub fn cstr_as_str<'a>(s: *const c_char) -> &'a str {
     unsafe{ CStr::from_ptr(s) }.to_str().expect("Incorrect UTF-8 sequence")
 }
 
+pub fn buf_as_str<'a>(buf: *const c_char, len: usize) -> &'a str {
+    if len == 0 {
+        return "";
+    }
+    let bytes = unsafe{ slice::from_raw_parts(buf as *const u8, len) };
+    std::str::from_utf8(bytes).expect("Incorrect UTF-8 sequence")
+}
+
 pub fn cstr_into_string(s: *const c_char) -> String {
     String::from(cstr_as_str(s))
 }
@@ -21,22 +29,27 @@ pub fn string_as_cstr(s: String) -> CString {
     CString::new(s).expect("CString::new failed")
 }
 
+/// Returns the length in bytes of the text form of `obj`, without allocating memory for the text
+pub(crate) fn display_len<T: std::fmt::Display>(obj: T) -> usize {
+    struct LengthTracker(usize);
+    impl Write for LengthTracker {
+        fn write(&mut self, slice: &[u8]) -> Result<usize, std::io::Error> {
+            self.0 += slice.len();
+            Ok(slice.len())
+        }
+        fn flush(&mut self) -> Result<(), std::io::Error> { Ok (())}
+    }
+
+    let mut length_tracker = LengthTracker(0);
+    write!(length_tracker, "{obj}").unwrap();
+    length_tracker.0
+}
+
 pub(crate) fn write_into_buf<T: std::fmt::Display>(obj: T, buf: *mut c_char, buf_len: usize) -> usize {
 
     //If buf_len == 0, the caller is just interested in the size of buffer they will need
     if buf_len == 0 {
-        struct LengthTracker(usize);
-        impl Write for LengthTracker {
-            fn write(&mut self, slice: &[u8]) -> Result<usize, std::io::Error> {
-                self.0 += slice.len();
-                Ok(slice.len())
-            }
-            fn flush(&mut self) -> Result<(), std::io::Error> { Ok (())}
-        }
-
-        let mut length_tracker = LengthTracker(0);
-        write!(length_tracker, "{obj}").unwrap();
-        length_tracker.0
+        display_len(obj)
     } else {
         //We are goint to try and actually render the object into the buffer, saving room for the terminator
         let slice = unsafe{ slice::from_raw_parts_mut(buf as *mut u8, buf_len) };
@@ -11,6 +11,7 @@ void teardown(void) {
 }
 
 #include "stdio.h"
+#include <stdint.h>
 
 void bindings_to_buf(bindings_t* bindings, void *context) {
     char* dst_buf = context;
@@ -101,12 +102,404 @@ START_TEST (test_expr)
 }
 END_TEST
 
+START_TEST (test_var)
+{
+    atom_t var = atom_var("x");
+    atom_t atom = expr(atom_sym("foo"), atom_clone(&var), atom_var("x"), atom_ref_null());
+
+    char* actual = stratom(&atom);
+    ck_assert_str_eq(actual, "(foo $x $x)");
+    free(actual);
+
+    char name[8];
+    atom_get_name(&var, name, 8);
+    ck_assert_str_eq(name, "x");
+    atom_ref_t child = atom_expr_child(&atom, 2);
+    ck_assert(atom_eq(&child, &var));
+
+    atom_free(atom);
+    atom_free(var);
+}
+END_TEST
+
+void copy_atom_vec(const atom_vec_t* atoms, void* context) {
+    atom_vec_t* dst = context;
+    *dst = atom_vec_clone(atoms);
+}
+
+int atom_vec_contains(const atom_vec_t* atoms, atom_t atom) {
+    int found = 0;
+    for (size_t i = 0; i < atom_vec_len(atoms); i++) {
+        atom_ref_t item = atom_vec_get(atoms, i);
+        found |= atom_eq(&item, &atom);
+    }
+    atom_free(atom);
+    return found;
+}
+
+START_TEST (test_free_variables)
+{
+    atom_t atom = expr(atom_sym("foo"), atom_var("x"), expr(atom_sym("bar"), atom_var("y"), atom_var("x"), atom_ref_null()), atom_ref_null());
+
+    atom_vec_t vars;
+    atom_free_variables(&atom, &copy_atom_vec, &vars);
+    ck_assert_int_eq(atom_vec_len(&vars), 2);
+    ck_assert(atom_vec_contains(&vars, atom_var("x")));
+    ck_assert(atom_vec_contains(&vars, atom_var("y")));
+
+    atom_vec_free(vars);
+    atom_free(atom);
+}
+END_TEST
+
+START_TEST (test_make_variables_unique)
+{
+    atom_t a = atom_make_variables_unique(expr(atom_sym("foo"), atom_var("x"), atom_var("y"), atom_ref_null()));
+    atom_t b = atom_make_variables_unique(expr(atom_sym("foo"), atom_var("x"), atom_var("y"), atom_ref_null()));
+
+    atom_vec_t a_vars;
+    atom_free_variables(&a, &copy_atom_vec, &a_vars);
+    atom_vec_t b_vars;
+    atom_free_variables(&b, &copy_atom_vec, &b_vars);
+    ck_assert_int_eq(atom_vec_len(&a_vars), 2);
+    ck_assert_int_eq(atom_vec_len(&b_vars), 2);
+    for (size_t i = 0; i < atom_vec_len(&a_vars); i++) {
+        atom_ref_t var = atom_vec_get(&a_vars, i);
+        ck_assert(!atom_vec_contains(&b_vars, atom_clone(&var)));
+    }
+
+    atom_vec_free(b_vars);
+    atom_vec_free(a_vars);
+    atom_free(b);
+    atom_free(a);
+}
+END_TEST
+
+START_TEST (test_atom_hash)
+{
+    atom_t a = expr(atom_sym("a"), atom_sym("b"), atom_ref_null());
+    atom_t b = expr(atom_sym("a"), atom_sym("b"), atom_ref_null());
+    atom_t c = expr(atom_sym("b"), atom_sym("a"), atom_ref_null());
+
+    ck_assert_uint_eq(atom_hash(&a), atom_hash(&a));
+    ck_assert_uint_eq(atom_hash(&a), atom_hash(&b));
+    ck_assert_uint_ne(atom_hash(&a), atom_hash(&c));
+
+    atom_free(c);
+    atom_free(b);
+    atom_free(a);
+}
+END_TEST
+
+START_TEST (test_atom_serialize)
+{
+    atom_t atom = expr(atom_sym("foo"), atom_var("x"), expr(atom_sym("bar"), atom_ref_null()), expr(atom_ref_null()), atom_ref_null());
+
+    size_t len = atom_serialize(&atom, NULL, 0);
+    ck_assert(len > 0);
+    unsigned char* buf = malloc(len);
+    ck_assert_int_eq(atom_serialize(&atom, buf, len), len);
+
+    atom_t decoded = atom_deserialize(buf, len);
+    ck_assert(atom_eq(&decoded, &atom));
+    atom_free(decoded);
+
+    atom_t truncated = atom_deserialize(buf, len - 1);
+    ck_assert(atom_is_null(&truncated));
+    free(buf);
+
+    atom_t grounded = expr(atom_sym("foo"), atom_gnd(int_new(42)), atom_ref_null());
+    ck_assert_int_eq(atom_serialize(&grounded, NULL, 0), 0);
+    atom_free(grounded);
+
+    atom_free(atom);
+}
+END_TEST
+
+START_TEST (test_atom_deserialize_malformed)
+{
+    // Expression tag followed by an unfinished length
+    unsigned char truncated_len[] = { 2, 0x80 };
+    atom_t atom = atom_deserialize(truncated_len, sizeof(truncated_len));
+    ck_assert(atom_is_null(&atom));
+
+    // Symbol length with bits above 64 which would be dropped silently, leaving length 1
+    unsigned char overlong[] = { 0, 0x81, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x02, 'a' };
+    atom = atom_deserialize(overlong, sizeof(overlong));
+    ck_assert(atom_is_null(&atom));
+
+    // Expression claiming more children than bytes left in the buffer
+    unsigned char huge_count[] = { 2, 0xff, 0xff, 0xff, 0x0f, 0, 1, 'a' };
+    atom = atom_deserialize(huge_count, sizeof(huge_count));
+    ck_assert(atom_is_null(&atom));
+
+    // Deeply nested expressions, each one containing the next one
+    size_t depth = 200000;
+    size_t len = depth * 2 + 3;
+    unsigned char* nested = malloc(len);
+    for (size_t i = 0; i < depth; i++) {
+        nested[i * 2] = 2;
+        nested[i * 2 + 1] = 1;
+    }
+    nested[len - 3] = 0;
+    nested[len - 2] = 1;
+    nested[len - 1] = 'a';
+    atom = atom_deserialize(nested, len);
+    ck_assert(atom_is_null(&atom));
+
+    // Moderate nesting is still decoded
+    size_t shallow = 100;
+    atom = atom_deserialize(nested + (depth - shallow) * 2, shallow * 2 + 3);
+    ck_assert(!atom_is_null(&atom));
+    ck_assert_int_eq(atom_node_count(&atom), shallow + 1);
+    atom_free(atom);
+    free(nested);
+}
+END_TEST
+
+typedef struct _depth_counter_t {
+    size_t count;
+    size_t depth_sum;
+} depth_counter_t;
+
+void count_atom_depth(atom_ref_t atom, size_t depth, void* context) {
+    depth_counter_t* counter = context;
+    counter->count++;
+    counter->depth_sum += depth;
+}
+
+START_TEST (test_atom_iterate_depth)
+{
+    atom_t atom = expr(atom_sym("a"), expr(atom_sym("b"), atom_sym("c"), atom_ref_null()), atom_sym("d"), atom_ref_null());
+
+    depth_counter_t counter = { 0, 0 };
+    atom_iterate_depth(&atom, SIZE_MAX, &count_atom_depth, &counter);
+    ck_assert_int_eq(counter.count, 6);
+    ck_assert_int_eq(counter.depth_sum, 7);
+
+    counter = (depth_counter_t){ 0, 0 };
+    atom_iterate_depth(&atom, 1, &count_atom_depth, &counter);
+    ck_assert_int_eq(counter.count, 4);
+    ck_assert_int_eq(counter.depth_sum, 3);
+
+    counter = (depth_counter_t){ 0, 0 };
+    atom_iterate_depth(&atom, 0, &count_atom_depth, &counter);
+    ck_assert_int_eq(counter.count, 1);
+
+    atom_free(atom);
+}
+END_TEST
+
+START_TEST (test_atom_deep_clone)
+{
+    space_t space = space_new_grounding_space();
+    space_add(&space, atom_sym("A"));
+    atom_t atom = expr(atom_sym("S"), atom_gnd_for_space(&space), atom_ref_null());
+
+    atom_t shallow = atom_clone(&atom);
+    atom_t deep = atom_deep_clone(&atom);
+    ck_assert(atom_eq(&atom, &shallow));
+    ck_assert(!atom_eq(&atom, &deep));
+
+    atom_vec_t children;
+    atom_get_children(&deep, copy_atom_vec, &children);
+    atom_ref_t deep_space_atom = atom_vec_get(&children, 1);
+    space_t deep_space = atom_get_space(&deep_space_atom);
+    ck_assert_int_eq(space_atom_count(&deep_space), 1);
+    space_add(&deep_space, atom_sym("B"));
+    ck_assert_int_eq(space_atom_count(&deep_space), 2);
+    ck_assert_int_eq(space_atom_count(&space), 1);
+
+    space_free(deep_space);
+    atom_vec_free(children);
+    atom_free(deep);
+    atom_free(shallow);
+    atom_free(atom);
+    space_free(space);
+}
+END_TEST
+
+START_TEST (test_atom_expr_child)
+{
+    atom_t atom = expr(atom_sym("a"), atom_sym("b"), atom_sym("c"), atom_ref_null());
+    ck_assert_int_eq(atom_expr_len(&atom), 3);
+
+    char name[8];
+    atom_ref_t child = atom_expr_child(&atom, 0);
+    atom_get_name(&child, name, 8);
+    ck_assert_str_eq(name, "a");
+    child = atom_expr_child(&atom, 2);
+    atom_get_name(&child, name, 8);
+    ck_assert_str_eq(name, "c");
+    child = atom_expr_child(&atom, 3);
+    ck_assert(atom_is_null(&child));
+
+    atom_t sym = atom_sym("a");
+    ck_assert_int_eq(atom_expr_len(&sym), 0);
+    child = atom_expr_child(&sym, 0);
+    ck_assert(atom_is_null(&child));
+
+    atom_free(sym);
+    atom_free(atom);
+}
+END_TEST
+
+START_TEST (test_expr_builder)
+{
+    expr_builder_t builder = expr_builder_new();
+    const char* names[] = { "a", "b", "c" };
+    for (int i = 0; i < 3; i++) {
+        expr_builder_push(&builder, atom_sym(names[i]));
+    }
+    atom_t built = expr_builder_finish(builder);
+    atom_t expected = expr(atom_sym("a"), atom_sym("b"), atom_sym("c"), atom_ref_null());
+    ck_assert(atom_eq(&built, &expected));
+
+    expr_builder_t empty = expr_builder_new();
+    atom_t empty_expr = expr_builder_finish(empty);
+    ck_assert_int_eq(atom_get_metatype(&empty_expr), EXPR);
+    ck_assert_int_eq(atom_expr_len(&empty_expr), 0);
+
+    expr_builder_t dropped = expr_builder_new();
+    expr_builder_push(&dropped, atom_sym("a"));
+    expr_builder_free(dropped);
+
+    atom_free(empty_expr);
+    atom_free(expected);
+    atom_free(built);
+}
+END_TEST
+
+void append_name(const char* name, void* context) {
+    char* dst_buf = context;
+    strcat(dst_buf, name);
+    strcat(dst_buf, " ");
+}
+
+START_TEST (test_atom_variable_names)
+{
+    atom_t atom = expr(atom_var("x"), expr(atom_sym("f"), atom_var("y"), atom_ref_null()), atom_var("x"), atom_ref_null());
+
+    char names[BUF_SIZE] = "";
+    atom_variable_names(&atom, &append_name, names);
+    ck_assert_str_eq(names, "x y ");
+
+    atom_t sym = atom_sym("x");
+    names[0] = 0;
+    atom_variable_names(&sym, &append_name, names);
+    ck_assert_str_eq(names, "");
+
+    atom_free(sym);
+    atom_free(atom);
+}
+END_TEST
+
+START_TEST (test_atom_to_str_len)
+{
+    atom_t atoms[] = {
+        atom_sym("A"),
+        atom_var("x"),
+        expr(atom_sym("test"), atom_var("var"), atom_gnd(int_new(42)), atom_ref_null()),
+        expr(atom_ref_null()),
+        atom_sym("\"quoted string\""),
+    };
+    for (size_t i = 0; i < sizeof(atoms) / sizeof(atoms[0]); i++) {
+        char* str = stratom(&atoms[i]);
+        ck_assert_int_eq(atom_to_str_len(&atoms[i]), strlen(str));
+        free(str);
+        atom_free(atoms[i]);
+    }
+}
+END_TEST
+
+START_TEST (test_atom_alpha_canonical)
+{
+    atom_t fxx = expr(atom_sym("f"), atom_var("x"), atom_var("x"), atom_ref_null());
+    atom_t fyy = expr(atom_sym("f"), atom_var("y"), atom_var("y"), atom_ref_null());
+    atom_t fxy = expr(atom_sym("f"), atom_var("x"), atom_var("y"), atom_ref_null());
+    atom_t cxx = atom_alpha_canonical(&fxx);
+    atom_t cyy = atom_alpha_canonical(&fyy);
+    atom_t cxy = atom_alpha_canonical(&fxy);
+
+    ck_assert(atom_eq(&cxx, &cyy));
+    ck_assert_int_eq(atom_hash(&cxx), atom_hash(&cyy));
+    ck_assert(!atom_eq(&cxx, &cxy));
+    ck_assert(atoms_are_equivalent(&fxx, &fyy));
+    ck_assert(!atoms_are_equivalent(&fxx, &fxy));
+    ck_assert(atom_alpha_eq(&fxx, &fyy));
+    ck_assert(!atom_alpha_eq(&fxx, &fxy));
+
+    char* text = stratom(&cxy);
+    ck_assert_str_eq(text, "(f $_0 $_1)");
+    free(text);
+
+    atom_free(cxy);
+    atom_free(cyy);
+    atom_free(cxx);
+    atom_free(fxy);
+    atom_free(fyy);
+    atom_free(fxx);
+}
+END_TEST
+
+START_TEST (test_atom_to_str_pretty)
+{
+    atom_t atom = expr(atom_sym("="),
+        expr(atom_sym("f"), atom_var("x"), atom_ref_null()),
+        expr(atom_sym("g"), expr(atom_sym("h"), atom_var("x"), atom_ref_null()), atom_ref_null()),
+        atom_ref_null());
+
+    char buf[64];
+    size_t len = atom_to_str_pretty(&atom, 2, buf, 64);
+    ck_assert_str_eq(buf, "(=\n  (f $x)\n  (g\n    (h $x)))");
+    ck_assert_int_eq(len, strlen(buf));
+
+    char small[4];
+    ck_assert_int_eq(atom_to_str_pretty(&atom, 2, small, 4), len);
+
+    atom_free(atom);
+}
+END_TEST
+
+START_TEST (test_atom_node_count)
+{
+    atom_t sym = atom_sym("A");
+    ck_assert_int_eq(atom_node_count(&sym), 1);
+    atom_free(sym);
+
+    atom_t flat = expr(atom_sym("f"), atom_var("x"), atom_sym("A"), atom_ref_null());
+    ck_assert_int_eq(atom_node_count(&flat), 4);
+    atom_free(flat);
+
+    atom_t nested = expr(atom_sym("f"),
+        expr(atom_sym("g"), expr(atom_sym("h"), atom_var("x"), atom_ref_null()), atom_ref_null()),
+        atom_ref_null());
+    ck_assert_int_eq(atom_node_count(&nested), 7);
+    atom_free(nested);
+}
+END_TEST
+
 void init_test(TCase* test_case) {
     tcase_set_timeout(test_case, 300); //300s = 5min.  To test for memory leaks
     tcase_add_checked_fixture(test_case, setup, teardown);
     tcase_add_test(test_case, test_bindings_set);
     tcase_add_test(test_case, test_sym);
     tcase_add_test(test_case, test_expr);
+    tcase_add_test(test_case, test_var);
+    tcase_add_test(test_case, test_free_variables);
+    tcase_add_test(test_case, test_make_variables_unique);
+    tcase_add_test(test_case, test_atom_hash);
+    tcase_add_test(test_case, test_atom_serialize);
+    tcase_add_test(test_case, test_atom_deserialize_malformed);
+    tcase_add_test(test_case, test_atom_iterate_depth);
+    tcase_add_test(test_case, test_atom_deep_clone);
+    tcase_add_test(test_case, test_atom_expr_child);
+    tcase_add_test(test_case, test_expr_builder);
+    tcase_add_test(test_case, test_atom_variable_names);
+    tcase_add_test(test_case, test_atom_to_str_len);
+    tcase_add_test(test_case, test_atom_to_str_pretty);
+    tcase_add_test(test_case, test_atom_alpha_canonical);
+    tcase_add_test(test_case, test_atom_node_count);
 }
 
 TEST_MAIN(init_test);
@@ -1,9 +1,13 @@
 
 #include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <sys/stat.h>
 #include <hyperon/hyperon.h>
 
 #include "test.h"
 #include "util.h"
+#include "int_gnd.h"
 
 void setup(void) {
 }
@@ -96,6 +100,645 @@ START_TEST (test_runner_errors)
 }
 END_TEST
 
+void append_first_atom(const atom_vec_t* atoms, void* context) {
+    char* buf = context;
+    size_t len = strlen(buf);
+    atom_ref_t atom = atom_vec_get(atoms, 0);
+    len += atom_to_str(&atom, buf + len, 256 - len);
+    snprintf(buf + len, 256 - len, ", ");
+}
+
+START_TEST (test_run_streaming)
+{
+    metta_t runner = new_test_metta();
+
+    char buf[256] = "";
+    sexpr_parser_t parser = sexpr_parser_new("!(+ 1 2) (= (foo) 42) !(foo)");
+    metta_run_streaming(&runner, parser, &append_first_atom, buf);
+    ck_assert(metta_err_str(&runner) == NULL);
+    ck_assert_str_eq(buf, "3, 42, ");
+
+    buf[0] = 0;
+    parser = sexpr_parser_new("!(+ 1 2) !(+ 1");
+    metta_run_streaming(&runner, parser, &append_first_atom, buf);
+    ck_assert_str_eq(metta_err_str(&runner), "Unexpected end of expression");
+    ck_assert_str_eq(buf, "3, ");
+
+    metta_free(runner);
+}
+END_TEST
+
+START_TEST (test_evaluate_atom_limited)
+{
+    metta_t runner = new_test_metta();
+
+    sexpr_parser_t parser = sexpr_parser_new("(= (foo) A) (= (foo) B) (= (foo) C) (= (foo) D)");
+    atom_vec_t* results = NULL;
+    metta_run(&runner, parser, &copy_atom_vec, &results);
+    ck_assert(metta_err_str(&runner) == NULL);
+
+    results = NULL;
+    metta_evaluate_atom_limited(&runner, expr(atom_sym("foo"), atom_ref_null()), 2, &copy_atom_vec, &results);
+    ck_assert(metta_err_str(&runner) == NULL);
+    ck_assert_int_eq(atom_vec_len(results), 2);
+    atom_vec_free(*results);
+    free(results);
+
+    results = NULL;
+    metta_evaluate_atom_limited(&runner, expr(atom_sym("foo"), atom_ref_null()), 10, &copy_atom_vec, &results);
+    ck_assert_int_eq(atom_vec_len(results), 4);
+    atom_vec_free(*results);
+    free(results);
+
+    metta_free(runner);
+}
+END_TEST
+
+struct trace_t {
+    size_t steps;
+    size_t finished_steps;
+    atom_vec_t* results;
+};
+
+void count_step(const step_result_t* step, void* context) {
+    struct trace_t* trace = context;
+    trace->steps++;
+    if (!step_has_next(step)) {
+        trace->finished_steps++;
+    }
+}
+
+void copy_trace_results(const atom_vec_t* atoms, void* context) {
+    struct trace_t* trace = context;
+    copy_atom_vec(atoms, &trace->results);
+}
+
+START_TEST (test_evaluate_atom_traced)
+{
+    metta_t runner = new_test_metta();
+
+    sexpr_parser_t parser = sexpr_parser_new("(= (foo) (bar)) (= (bar) ok)");
+    atom_vec_t* results = NULL;
+    metta_run(&runner, parser, &copy_atom_vec, &results);
+    ck_assert(metta_err_str(&runner) == NULL);
+
+    struct trace_t first = { 0, 0, NULL };
+    metta_evaluate_atom_traced(&runner, expr(atom_sym("foo"), atom_ref_null()), &count_step, &copy_trace_results, &first);
+    ck_assert(metta_err_str(&runner) == NULL);
+    ck_assert(first.steps > 0);
+    ck_assert_int_eq(first.finished_steps, 1);
+    ck_assert_int_eq(atom_vec_len(first.results), 1);
+    atom_ref_t result = atom_vec_get(first.results, 0);
+    char* result_str = stratom(&result);
+    ck_assert_str_eq(result_str, "ok");
+    free(result_str);
+    atom_vec_free(*first.results);
+    free(first.results);
+
+    struct trace_t second = { 0, 0, NULL };
+    metta_evaluate_atom_traced(&runner, expr(atom_sym("foo"), atom_ref_null()), &count_step, &copy_trace_results, &second);
+    ck_assert_int_eq(second.steps, first.steps);
+    atom_vec_free(*second.results);
+    free(second.results);
+
+    metta_free(runner);
+}
+END_TEST
+
+bool double_op(const atom_vec_t* args, atom_vec_t* out, void* context) {
+    int* call_count = context;
+    (*call_count)++;
+    if (atom_vec_len(args) != 1) {
+        return false;
+    }
+    atom_ref_t arg = atom_vec_get(args, 0);
+    char* arg_str = stratom(&arg);
+    int n = atoi(arg_str);
+    free(arg_str);
+    atom_vec_push(out, atom_gnd(int_new(n * 2)));
+    return true;
+}
+
+START_TEST (test_register_grounded_op)
+{
+    metta_t runner = new_test_metta();
+    int call_count = 0;
+    metta_register_grounded_op(&runner, "double", &double_op, &call_count);
+
+    ck_assert(run_metta_and_compare_result(&runner, "!(double 21)", "42"));
+    ck_assert_int_eq(call_count, 1);
+
+    metta_free(runner);
+}
+END_TEST
+
+START_TEST (test_register_grounded_op_typed)
+{
+    metta_t runner = new_test_metta();
+    int call_count = 0;
+    atom_t op_type = expr(atom_sym("->"), atom_sym("Number"), atom_sym("Number"), atom_ref_null());
+    metta_register_grounded_op_typed(&runner, "double", &op_type, &double_op, &call_count);
+    atom_free(op_type);
+
+    tokenizer_t tokenizer = metta_tokenizer(&runner);
+    space_t space = metta_space(&runner);
+    sexpr_parser_t parser = sexpr_parser_new("(double 21) (double \"21\")");
+    atom_t good_call = sexpr_parser_parse(&parser, &tokenizer);
+    atom_t bad_call = sexpr_parser_parse(&parser, &tokenizer);
+    ck_assert(validate_atom(&space, &good_call));
+    ck_assert(!validate_atom(&space, &bad_call));
+    atom_free(bad_call);
+    atom_free(good_call);
+    sexpr_parser_free(parser);
+    space_free(space);
+    tokenizer_free(tokenizer);
+
+    ck_assert(run_metta_and_compare_result(&runner, "!(double \"21\")", "(Error \"21\" BadType)"));
+    ck_assert_int_eq(call_count, 0);
+    ck_assert(run_metta_and_compare_result(&runner, "!(double 21)", "42"));
+    ck_assert_int_eq(call_count, 1);
+
+    metta_free(runner);
+}
+END_TEST
+
+START_TEST (test_atom_literals)
+{
+    metta_t runner = new_test_metta();
+    tokenizer_t tokenizer = metta_tokenizer(&runner);
+
+    atom_t atoms[] = { atom_int(-42), atom_float(2.5), atom_string("some text") };
+    const char* expected[] = { "-42", "2.5", "\"some text\"" };
+    for (size_t i = 0; i < 3; i++) {
+        char* text = stratom(&atoms[i]);
+        ck_assert_str_eq(text, expected[i]);
+
+        sexpr_parser_t parser = sexpr_parser_new(text);
+        atom_t parsed = sexpr_parser_parse(&parser, &tokenizer);
+        ck_assert(atom_eq(&parsed, &atoms[i]));
+        atom_free(parsed);
+        sexpr_parser_free(parser);
+
+        free(text);
+        atom_free(atoms[i]);
+    }
+
+    tokenizer_free(tokenizer);
+    metta_free(runner);
+}
+END_TEST
+
+START_TEST (test_atom_type_arrow)
+{
+    atom_t children[] = { ATOM_TYPE_ARROW(), atom_sym("Number"), atom_sym("Number") };
+    atom_t op_type = atom_expr(children, 3);
+    char* type_str = stratom(&op_type);
+    ck_assert_str_eq(type_str, "(-> Number Number)");
+    free(type_str);
+
+    metta_t runner = new_test_metta();
+    int call_count = 0;
+    metta_register_grounded_op_typed(&runner, "double", &op_type, &double_op, &call_count);
+    atom_free(op_type);
+
+    tokenizer_t tokenizer = metta_tokenizer(&runner);
+    space_t space = metta_space(&runner);
+    sexpr_parser_t parser = sexpr_parser_new("(double 21) (double \"21\")");
+    atom_t good_call = sexpr_parser_parse(&parser, &tokenizer);
+    atom_t bad_call = sexpr_parser_parse(&parser, &tokenizer);
+    ck_assert(validate_atom(&space, &good_call));
+    ck_assert(!validate_atom(&space, &bad_call));
+    atom_free(bad_call);
+    atom_free(good_call);
+    sexpr_parser_free(parser);
+    space_free(space);
+    tokenizer_free(tokenizer);
+
+    metta_free(runner);
+}
+END_TEST
+
+START_TEST (test_atom_execute)
+{
+    metta_t runner = new_test_metta();
+    int call_count = 0;
+    metta_register_grounded_op(&runner, "double", &double_op, &call_count);
+
+    tokenizer_t tokenizer = metta_tokenizer(&runner);
+    sexpr_parser_t parser = sexpr_parser_new("double");
+    atom_t op = sexpr_parser_parse(&parser, &tokenizer);
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+
+    atom_t args[] = { atom_gnd(int_new(21)) };
+    atom_vec_t* results = NULL;
+    exec_error_t err;
+    ck_assert(atom_execute(&op, args, 1, &copy_atom_vec, &results, &err));
+    ck_assert_int_eq(call_count, 1);
+    ck_assert_int_eq(atom_vec_len(results), 1);
+    atom_ref_t result = atom_vec_get(results, 0);
+    char* result_str = stratom(&result);
+    ck_assert_str_eq(result_str, "42");
+    free(result_str);
+    atom_vec_free(*results);
+    free(results);
+    exec_error_free(err);
+
+    results = NULL;
+    ck_assert(!atom_execute(&op, NULL, 0, &copy_atom_vec, &results, &err));
+    ck_assert(results == NULL);
+    exec_error_free(err);
+
+    atom_t not_executable = atom_gnd(int_new(1));
+    ck_assert(!atom_execute(&not_executable, args, 1, &copy_atom_vec, &results, NULL));
+    ck_assert(results == NULL);
+    atom_free(not_executable);
+
+    atom_free(args[0]);
+    atom_free(op);
+    metta_free(runner);
+}
+END_TEST
+
+START_TEST (test_run_str)
+{
+    metta_t runner = new_test_metta();
+
+    char* text = strdup("(= (foo) 42) !(foo)");
+    atom_vec_t* results = NULL;
+    metta_run_str(&runner, text, &copy_atom_vec, &results);
+    //The text isn't borrowed by the runner, so it can be freed right away
+    free(text);
+
+    ck_assert(metta_err_str(&runner) == NULL);
+    ck_assert(results != NULL);
+    ck_assert_int_eq(atom_vec_len(results), 1);
+    char atom_str_buf[64];
+    atom_ref_t result_atom = atom_vec_get(results, 0);
+    atom_to_str(&result_atom, atom_str_buf, 64);
+    ck_assert_str_eq(atom_str_buf, "42");
+    atom_vec_free(*results);
+    free(results);
+
+    results = NULL;
+    metta_run_str(&runner, "!(foo", &copy_atom_vec, &results);
+    ck_assert(results == NULL);
+    ck_assert_str_eq(metta_err_str(&runner), "Unexpected end of expression");
+
+    metta_free(runner);
+}
+END_TEST
+
+START_TEST (test_clone_handle)
+{
+    metta_t runner = new_test_metta();
+    metta_t runner_clone = metta_clone_handle(&runner);
+    ck_assert(metta_eq(&runner, &runner_clone));
+
+    atom_vec_t* results = NULL;
+    metta_run_str(&runner, "(= (foo) 42)", &copy_atom_vec, &results);
+    ck_assert(metta_err_str(&runner) == NULL);
+    metta_free(runner);
+
+    //The runner is still accessible through the remaining handle
+    ck_assert(run_metta_and_compare_result(&runner_clone, "!(foo)", "42"));
+    metta_free(runner_clone);
+}
+END_TEST
+
+START_TEST (test_interpret_step_with_limit)
+{
+    space_t space = space_new_grounding_space();
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("(chain (chain (chain A $x $x) $y $y) $z $z)");
+    atom_t atom = sexpr_parser_parse(&parser, &tokenizer);
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+
+    step_result_t step = interpret_init(&space, &atom);
+    step = interpret_step_with_limit(step, 1);
+    ck_assert(step_has_next(&step));
+
+    //A budget of zero performs no work
+    step = interpret_step_with_limit(step, 0);
+    ck_assert(step_has_next(&step));
+
+    //The operation will eventually complete, and the remaining budget will be discarded
+    int loop_count = 0;
+    while (step_has_next(&step)) {
+        step = interpret_step_with_limit(step, 1000);
+        loop_count++;
+    }
+    ck_assert_int_eq(loop_count, 1);
+
+    atom_vec_t* results = NULL;
+    step_get_result(step, &copy_atom_vec, &results);
+    ck_assert(results != NULL);
+    ck_assert_int_eq(atom_vec_len(results), 1);
+    atom_vec_free(*results);
+    free(results);
+
+    atom_free(atom);
+    space_free(space);
+}
+END_TEST
+
+START_TEST (test_step_get_current_atoms)
+{
+    space_t space = space_new_grounding_space();
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("(chain (chain A $x $x) $y $y)");
+    atom_t atom = sexpr_parser_parse(&parser, &tokenizer);
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+
+    step_result_t step = interpret_init(&space, &atom);
+    atom_vec_t* current = NULL;
+    step_get_current_atoms(&step, &copy_atom_vec, &current);
+    ck_assert(current != NULL);
+    ck_assert_int_eq(atom_vec_len(current), 1);
+    char atom_str_buf[64];
+    atom_ref_t current_atom = atom_vec_get(current, 0);
+    atom_to_str(&current_atom, atom_str_buf, 64);
+    ck_assert_str_eq(atom_str_buf, "A");
+    atom_vec_free(*current);
+    free(current);
+
+    while (step_has_next(&step)) {
+        step = interpret_step(step);
+    }
+    current = NULL;
+    step_get_current_atoms(&step, &copy_atom_vec, &current);
+    ck_assert(current != NULL);
+    ck_assert_int_eq(atom_vec_len(current), 0);
+    atom_vec_free(*current);
+    free(current);
+
+    current = NULL;
+    step_get_result(step, &copy_atom_vec, &current);
+    atom_vec_free(*current);
+    free(current);
+    atom_free(atom);
+    space_free(space);
+}
+END_TEST
+
+START_TEST (test_step_result_count)
+{
+    space_t space = space_new_grounding_space();
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("(= (foo) A) (= (foo) B) (= (foo) C) (eval (foo))");
+    for (int i = 0; i < 3; i++) {
+        space_add(&space, sexpr_parser_parse(&parser, &tokenizer));
+    }
+    atom_t atom = sexpr_parser_parse(&parser, &tokenizer);
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+
+    step_result_t step = interpret_init(&space, &atom);
+    while (step_has_next(&step)) {
+        step = interpret_step(step);
+    }
+    ck_assert_int_eq(step_result_count(&step), 3);
+
+    atom_vec_t* results = NULL;
+    step_get_result(step, &copy_atom_vec, &results);
+    ck_assert_int_eq(atom_vec_len(results), 3);
+    atom_vec_free(*results);
+    free(results);
+
+    atom_free(atom);
+    space_free(space);
+}
+END_TEST
+
+START_TEST (test_step_is_error)
+{
+    space_t space = space_new_grounding_space();
+    atom_t atom = expr(atom_sym("eval"), atom_ref_null());
+
+    step_result_t step = interpret_init(&space, &atom);
+    while (step_has_next(&step)) {
+        ck_assert(!step_is_error(&step));
+        step = interpret_step(step);
+    }
+    ck_assert(step_is_error(&step));
+    char message[128];
+    step_get_error_message(&step, message, 128);
+    ck_assert_str_eq(message, "expected: (eval <atom>), found: (eval)");
+
+    atom_vec_t* results = NULL;
+    step_get_result(step, &copy_atom_vec, &results);
+    ck_assert_int_eq(atom_vec_len(results), 1);
+    atom_vec_free(*results);
+    free(results);
+    atom_free(atom);
+
+    atom = expr(atom_sym("eval"), atom_sym("A"), atom_ref_null());
+    step = interpret_init(&space, &atom);
+    while (step_has_next(&step)) {
+        step = interpret_step(step);
+    }
+    ck_assert(!step_is_error(&step));
+    ck_assert_int_eq(step_get_error_message(&step, message, 128), 0);
+    results = NULL;
+    step_get_result(step, &copy_atom_vec, &results);
+    atom_vec_free(*results);
+    free(results);
+
+    atom_free(atom);
+    space_free(space);
+}
+END_TEST
+
+START_TEST (test_interpret_init_with_bindings)
+{
+    space_t space = space_new_grounding_space();
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("(= (foo $y) (bar $y)) (eval (foo $x))");
+    space_add(&space, sexpr_parser_parse(&parser, &tokenizer));
+    atom_t atom = sexpr_parser_parse(&parser, &tokenizer);
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+
+    bindings_t bindings = bindings_new();
+    bindings_add_var_binding(&bindings, atom_var("x"), atom_sym("A"));
+
+    step_result_t step = interpret_init_with_bindings(&space, &atom, &bindings);
+    while (step_has_next(&step)) {
+        step = interpret_step(step);
+    }
+    atom_vec_t* results = NULL;
+    step_get_result(step, &copy_atom_vec, &results);
+    ck_assert(results != NULL);
+    ck_assert_int_eq(atom_vec_len(results), 1);
+    char atom_str_buf[64];
+    atom_ref_t result_atom = atom_vec_get(results, 0);
+    atom_to_str(&result_atom, atom_str_buf, 64);
+    ck_assert_str_eq(atom_str_buf, "(bar A)");
+    atom_vec_free(*results);
+    free(results);
+
+    bindings_free(bindings);
+    atom_free(atom);
+    space_free(space);
+}
+END_TEST
+
+START_TEST (test_environment_working_dir)
+{
+    //The common environment isn't initialized explicitly, so it uses the process working directory
+    char cwd_buf[1024];
+    ck_assert(getcwd(cwd_buf, 1024) != NULL);
+
+    size_t len = environment_working_dir(NULL, 0);
+    ck_assert_int_eq(len, strlen(cwd_buf));
+    char* path_buf = malloc(len+1);
+    environment_working_dir(path_buf, len+1);
+    ck_assert_str_eq(path_buf, cwd_buf);
+    free(path_buf);
+}
+END_TEST
+
+START_TEST (test_environment_include_paths)
+{
+    //The working_dir is always the first include path
+    size_t count = environment_include_path_count();
+    ck_assert(count >= 1);
+    char working_dir_buf[1024];
+    char path_buf[1024];
+    environment_working_dir(working_dir_buf, 1024);
+    environment_include_path(0, path_buf, 1024);
+    ck_assert_str_eq(path_buf, working_dir_buf);
+
+    for (size_t i = 0; i < count; i++) {
+        ck_assert(environment_include_path(i, NULL, 0) > 0);
+    }
+    ck_assert_int_eq(environment_include_path(count, path_buf, 1024), 0);
+    ck_assert_str_eq(path_buf, "");
+}
+END_TEST
+
+START_TEST (test_env_builder_finish)
+{
+    //Several independent environments can be built in the same process
+    env_builder_t builder_1 = env_builder_start();
+    env_builder_set_is_test(&builder_1, true);
+    env_t env_1 = env_builder_finish(builder_1);
+
+    env_builder_t builder_2 = env_builder_start();
+    env_builder_set_is_test(&builder_2, true);
+    env_builder_set_working_dir(&builder_2, "/tmp");
+    env_t env_2 = env_builder_finish(builder_2);
+
+    env_free(env_1);
+    env_free(env_2);
+}
+END_TEST
+
+START_TEST (test_env_builder_abort)
+{
+    env_builder_t builder = env_builder_start();
+    env_builder_set_is_test(&builder, true);
+    env_builder_set_working_dir(&builder, "/tmp");
+    env_builder_abort(builder);
+
+    //Aborting the default builder does nothing
+    env_builder_abort(env_builder_use_default());
+
+    builder = env_builder_start();
+    env_builder_set_is_test(&builder, true);
+    env_t env = env_builder_finish(builder);
+    env_free(env);
+}
+END_TEST
+
+START_TEST (test_env_builder_checked_paths)
+{
+    char err_buf[256];
+    env_builder_t builder = env_builder_start();
+    env_builder_set_is_test(&builder, true);
+
+    ck_assert(!env_builder_set_config_dir_checked(&builder, NULL, err_buf, 256));
+    ck_assert_str_eq(err_buf, "path cannot be NULL");
+    ck_assert(!env_builder_set_config_dir_checked(&builder, "/proc/ctest_config", err_buf, 256));
+    ck_assert(strncmp(err_buf, "Config dir /proc/ctest_config is not writable", 45) == 0);
+    ck_assert(!env_builder_set_config_dir_checked(&builder, "/dev/null/ctest_config", err_buf, 256));
+    ck_assert_str_eq(err_buf, "Config dir /dev/null is not a directory");
+
+    ck_assert(!env_builder_push_include_path_checked(&builder, NULL, err_buf, 256));
+    ck_assert_str_eq(err_buf, "path cannot be NULL");
+    ck_assert(!env_builder_push_include_path_checked(&builder, "/proc/ctest_missing", err_buf, 256));
+    ck_assert_str_eq(err_buf, "Include path /proc/ctest_missing is not an existing directory");
+    ck_assert(env_builder_push_include_path_checked(&builder, "/tmp", err_buf, 256));
+
+    //The builder is still usable after the errors
+    env_t env = env_builder_finish(builder);
+    env_free(env);
+
+    //Checking the path doesn't create the config dir, it's created when the builder is finished
+    env_builder_t config_builder = env_builder_start();
+    env_builder_set_is_test(&config_builder, true);
+    char dir_buf[] = "/tmp/ctest_config_XXXXXX";
+    ck_assert(mkdtemp(dir_buf) != NULL);
+    char config_path[256];
+    snprintf(config_path, 256, "%s/config", dir_buf);
+    ck_assert(env_builder_set_config_dir_checked(&config_builder, config_path, err_buf, 256));
+    ck_assert(access(config_path, F_OK) != 0);
+
+    //An existing read-only dir is not writable, unless the test runs as root
+    ck_assert(chmod(dir_buf, 0555) == 0);
+    bool writable = env_builder_set_config_dir_checked(&config_builder, dir_buf, err_buf, 256);
+    ck_assert(writable == (geteuid() == 0));
+    if (!writable) {
+        char expected[256];
+        snprintf(expected, 256, "Config dir %s is not writable", dir_buf);
+        ck_assert(strncmp(err_buf, expected, strlen(expected)) == 0);
+    }
+    chmod(dir_buf, 0755);
+    rmdir(dir_buf);
+    env_builder_abort(config_builder);
+    ck_assert(access(config_path, F_OK) != 0);
+}
+END_TEST
+
+START_TEST (test_metta_new_with_env)
+{
+    //Make a module that can only be found via a custom include path
+    char dir_buf[] = "/tmp/ctest_include_XXXXXX";
+    ck_assert(mkdtemp(dir_buf) != NULL);
+    char mod_path[256];
+    snprintf(mod_path, 256, "%s/ctest_incl_mod.metta", dir_buf);
+    FILE* mod_file = fopen(mod_path, "w");
+    ck_assert(mod_file != NULL);
+    fputs("(= (incl-value) found)", mod_file);
+    fclose(mod_file);
+
+    env_builder_t builder = env_builder_start();
+    env_builder_set_is_test(&builder, true);
+    env_builder_push_include_path(&builder, dir_buf);
+    env_t env = env_builder_finish(builder);
+
+    space_t space = space_new_grounding_space();
+    tokenizer_t tokenizer = tokenizer_new();
+    metta_t runner = metta_new_with_env(&space, &tokenizer, env);
+    ck_assert(run_metta_and_compare_result(&runner, "!(import! &self ctest_incl_mod)", "()"));
+    ck_assert(run_metta_and_compare_result(&runner, "!(incl-value)", "found"));
+    metta_free(runner);
+    tokenizer_free(tokenizer);
+    space_free(space);
+
+    //A runner with the default environment can't resolve the module
+    metta_t default_runner = new_test_metta();
+    ck_assert(!run_metta_and_compare_result(&default_runner, "!(import! &self ctest_incl_mod)", "()"));
+    ck_assert(run_metta_and_compare_result(&default_runner, "!(incl-value)", "(incl-value)"));
+    metta_free(default_runner);
+
+    remove(mod_path);
+    rmdir(dir_buf);
+}
+END_TEST
+
 size_t path_for_name(const void *payload, const char *parent_dir, const char *mod_name, char *dst_buf, uintptr_t buf_size) {
     const char* suffix = ".ctestmod";
     size_t parent_dir_len = strlen(parent_dir);
@@ -220,6 +863,27 @@ void init_test(TCase* test_case) {
     tcase_add_checked_fixture(test_case, setup, teardown);
     tcase_add_test(test_case, test_incremental_runner);
     tcase_add_test(test_case, test_runner_errors);
+    tcase_add_test(test_case, test_run_str);
+    tcase_add_test(test_case, test_run_streaming);
+    tcase_add_test(test_case, test_evaluate_atom_limited);
+    tcase_add_test(test_case, test_evaluate_atom_traced);
+    tcase_add_test(test_case, test_register_grounded_op);
+    tcase_add_test(test_case, test_register_grounded_op_typed);
+    tcase_add_test(test_case, test_atom_literals);
+    tcase_add_test(test_case, test_atom_type_arrow);
+    tcase_add_test(test_case, test_atom_execute);
+    tcase_add_test(test_case, test_clone_handle);
+    tcase_add_test(test_case, test_interpret_step_with_limit);
+    tcase_add_test(test_case, test_step_get_current_atoms);
+    tcase_add_test(test_case, test_step_result_count);
+    tcase_add_test(test_case, test_step_is_error);
+    tcase_add_test(test_case, test_interpret_init_with_bindings);
+    tcase_add_test(test_case, test_environment_working_dir);
+    tcase_add_test(test_case, test_environment_include_paths);
+    tcase_add_test(test_case, test_env_builder_finish);
+    tcase_add_test(test_case, test_env_builder_abort);
+    tcase_add_test(test_case, test_env_builder_checked_paths);
+    tcase_add_test(test_case, test_metta_new_with_env);
     tcase_add_test(test_case, test_custom_module_format);
     tcase_add_test(test_case, test_custom_stdlib);
 }
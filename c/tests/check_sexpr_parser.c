@@ -41,6 +41,416 @@ START_TEST (test_tokenizer_parser)
 }
 END_TEST
 
+atom_t word_atom_from_str(char const* str, void* context) {
+    return atom_sym("word");
+}
+
+START_TEST (test_tokenizer_priority)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    static token_api_t int_atom_token = { .construct_atom = &int_atom_from_str, .free_context = NULL };
+    static token_api_t word_atom_token = { .construct_atom = &word_atom_from_str, .free_context = NULL };
+    tokenizer_register_token_with_priority(&tokenizer, "\\d+", &int_atom_token, NULL, 1);
+    tokenizer_register_token(&tokenizer, "\\w+", &word_atom_token, NULL);
+
+    //The higher-priority int token wins, even though the word token was registered later
+    sexpr_parser_t parser = sexpr_parser_new("42");
+    atom_t result = sexpr_parser_parse(&parser, &tokenizer);
+    atom_t expected = atom_gnd(int_new(42));
+    ck_assert(atom_eq(&result, &expected));
+    atom_free(result);
+    atom_free(expected);
+    sexpr_parser_free(parser);
+
+    tokenizer_register_token_with_priority(&tokenizer, "\\w+", &word_atom_token, NULL, 2);
+    parser = sexpr_parser_new("42");
+    result = sexpr_parser_parse(&parser, &tokenizer);
+    expected = atom_sym("word");
+    ck_assert(atom_eq(&result, &expected));
+    atom_free(result);
+    atom_free(expected);
+    sexpr_parser_free(parser);
+
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+atom_t context_atom_from_str(char const* str, void* context) {
+    return atom_sym((char const*)context);
+}
+
+START_TEST (test_tokenizer_merge)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    static token_api_t int_atom_token = { .construct_atom = &int_atom_from_str, .free_context = NULL };
+    tokenizer_register_token(&tokenizer, "\\d+", &int_atom_token, NULL);
+
+    tokenizer_t other = tokenizer_new();
+    static token_api_t context_atom_token = { .construct_atom = &context_atom_from_str, .free_context = &free };
+    char* name = malloc(8);
+    strcpy(name, "plus");
+    tokenizer_register_token(&other, "\\+", &context_atom_token, name);
+
+    tokenizer_merge(&tokenizer, &other);
+    //The context is shared, so freeing the source Tokenizer must not free it
+    tokenizer_free(other);
+
+    sexpr_parser_t parser = sexpr_parser_new("(+ 1 2)");
+    atom_t result = sexpr_parser_parse(&parser, &tokenizer);
+    atom_t expected = expr(atom_sym("plus"), atom_gnd(int_new(1)), atom_gnd(int_new(2)), atom_ref_null());
+    ck_assert(atom_eq(&result, &expected));
+
+    atom_free(result);
+    atom_free(expected);
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+START_TEST (test_tokenizer_matched_regex)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    static token_api_t int_atom_token = { .construct_atom = &int_atom_from_str, .free_context = NULL };
+    static token_api_t word_atom_token = { .construct_atom = &word_atom_from_str, .free_context = NULL };
+    tokenizer_register_token_with_priority(&tokenizer, "\\d+", &int_atom_token, NULL, 1);
+    tokenizer_register_token(&tokenizer, "\\w+", &word_atom_token, NULL);
+
+    char buf[64];
+    ck_assert(tokenizer_matches(&tokenizer, "123"));
+    ck_assert_int_eq(tokenizer_matched_regex(&tokenizer, "123", buf, 64), 3);
+    ck_assert_str_eq(buf, "\\d+");
+    tokenizer_matched_regex(&tokenizer, "x1", buf, 64);
+    ck_assert_str_eq(buf, "\\w+");
+
+    ck_assert(!tokenizer_matches(&tokenizer, "1+"));
+    ck_assert_int_eq(tokenizer_matched_regex(&tokenizer, "1+", buf, 64), 0);
+    ck_assert_str_eq(buf, "");
+
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+START_TEST (test_tokenizer_register_token_checked)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    static token_api_t word_atom_token = { .construct_atom = &word_atom_from_str, .free_context = NULL };
+
+    ck_assert(!tokenizer_register_token_checked(&tokenizer, "(\\w+", &word_atom_token, NULL));
+    ck_assert(tokenizer_err_str(&tokenizer) != NULL);
+
+    //Nothing was registered by the failed call
+    sexpr_parser_t parser = sexpr_parser_new("foo");
+    atom_t result = sexpr_parser_parse(&parser, &tokenizer);
+    atom_t expected = atom_sym("foo");
+    ck_assert(atom_eq(&result, &expected));
+    atom_free(result);
+    atom_free(expected);
+    sexpr_parser_free(parser);
+
+    ck_assert(tokenizer_register_token_checked(&tokenizer, "\\w+", &word_atom_token, NULL));
+    ck_assert(tokenizer_err_str(&tokenizer) == NULL);
+    parser = sexpr_parser_new("foo");
+    result = sexpr_parser_parse(&parser, &tokenizer);
+    expected = atom_sym("word");
+    ck_assert(atom_eq(&result, &expected));
+    atom_free(result);
+    atom_free(expected);
+    sexpr_parser_free(parser);
+
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+void copy_atom_vec(const atom_vec_t* atoms, void* context) {
+    atom_vec_t* dst = (atom_vec_t*)context;
+    *dst = atom_vec_clone(atoms);
+}
+
+START_TEST (test_parser_keep_comments)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("; hello\n(foo)");
+    sexpr_parser_set_keep_comments(&parser, true);
+
+    atom_t comment = sexpr_parser_parse(&parser, &tokenizer);
+    atom_t expected = expr(atom_sym("Comment"), atom_sym("; hello"), atom_ref_null());
+    ck_assert(atom_eq(&comment, &expected));
+    atom_free(expected);
+
+    //The comment text is recoverable from the second child of the comment atom
+    atom_vec_t children;
+    atom_get_children(&comment, &copy_atom_vec, &children);
+    atom_ref_t text = atom_vec_get(&children, 1);
+    char buf[64];
+    atom_get_name(&text, buf, 64);
+    ck_assert_str_eq(buf, "; hello");
+    atom_vec_free(children);
+    atom_free(comment);
+
+    atom_t foo = sexpr_parser_parse(&parser, &tokenizer);
+    expected = expr(atom_sym("foo"), atom_ref_null());
+    ck_assert(atom_eq(&foo, &expected));
+    atom_free(expected);
+    atom_free(foo);
+
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+START_TEST (test_parser_max_depth)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("(a (b)) (a (b (c)))");
+    sexpr_parser_set_max_depth(&parser, 2);
+    parse_error_t err;
+
+    atom_t atom = sexpr_parser_parse_checked(&parser, &tokenizer, &err);
+    ck_assert(parse_error_is_null(&err));
+    ck_assert(!atom_is_null(&atom));
+    atom_free(atom);
+    parse_error_free(err);
+
+    atom = sexpr_parser_parse_checked(&parser, &tokenizer, &err);
+    ck_assert(atom_is_null(&atom));
+    ck_assert(!parse_error_is_null(&err));
+    char buf[64];
+    parse_error_message(&err, buf, 64);
+    ck_assert_str_eq(buf, "Expression nesting depth exceeds the limit of 2");
+    parse_error_free(err);
+
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+START_TEST (test_parser_variable_sigil)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("(foo ?x)");
+    ck_assert(!sexpr_parser_set_variable_sigil(&parser, '('));
+    ck_assert(sexpr_parser_set_variable_sigil(&parser, '?'));
+
+    atom_t atom = sexpr_parser_parse(&parser, &tokenizer);
+    atom_t expected = expr(atom_sym("foo"), atom_var("x"), atom_ref_null());
+    ck_assert(atom_eq(&atom, &expected));
+
+    atom_free(expected);
+    atom_free(atom);
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+START_TEST (test_parser_extra_separators)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("(a, b, c)");
+    ck_assert(!sexpr_parser_set_extra_separators(&parser, "\xc2\xa7"));
+    ck_assert(sexpr_parser_set_extra_separators(&parser, ","));
+
+    atom_t atom = sexpr_parser_parse(&parser, &tokenizer);
+    atom_t expected = expr(atom_sym("a"), atom_sym("b"), atom_sym("c"), atom_ref_null());
+    ck_assert(atom_eq(&atom, &expected));
+
+    atom_free(expected);
+    atom_free(atom);
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+START_TEST (test_parser_at_end)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("(a b) ; trailing comment\n");
+    ck_assert(!sexpr_parser_at_end(&parser));
+
+    atom_t atom = sexpr_parser_parse(&parser, &tokenizer);
+    ck_assert(!atom_is_null(&atom));
+    ck_assert(sexpr_parser_at_end(&parser));
+    atom_free(atom);
+
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+START_TEST (test_parse_checked)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("(a) (b \"c)");
+    parse_error_t err;
+
+    atom_t atom = sexpr_parser_parse_checked(&parser, &tokenizer, &err);
+    ck_assert(parse_error_is_null(&err));
+    ck_assert(!atom_is_null(&atom));
+    atom_free(atom);
+    parse_error_free(err);
+
+    atom = sexpr_parser_parse_checked(&parser, &tokenizer, &err);
+    ck_assert(atom_is_null(&atom));
+    ck_assert(!parse_error_is_null(&err));
+    ck_assert_int_eq(parse_error_offset(&err), 7);
+    char buf[64];
+    parse_error_message(&err, buf, 64);
+    ck_assert_str_eq(buf, "Unclosed String Literal");
+    syntax_node_t node = parse_error_node(&err);
+    ck_assert(!syntax_node_is_null(&node));
+    syntax_node_text(&node, buf, 64);
+    ck_assert_str_eq(buf, "\"c)");
+    syntax_node_free(node);
+    parse_error_free(err);
+
+    sexpr_parser_free(parser);
+
+    parser = sexpr_parser_new_copy_src("(a) (b \"c)");
+    atom = sexpr_parser_parse_checked(&parser, &tokenizer, &err);
+    atom_free(atom);
+    parse_error_free(err);
+    atom = sexpr_parser_parse_checked(&parser, &tokenizer, &err);
+    ck_assert(!parse_error_is_null(&err));
+    node = parse_error_node(&err);
+    syntax_node_text(&node, buf, 64);
+    ck_assert_str_eq(buf, "\"c)");
+    syntax_node_free(node);
+    parse_error_free(err);
+
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+START_TEST (test_parse_all)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("(a) (b c) d");
+
+    atom_vec_t results = atom_vec_new();
+    sexpr_parser_parse_all(&parser, &tokenizer, &copy_atom_vec, &results);
+    ck_assert(sexpr_parser_err_str(&parser) == NULL);
+    ck_assert_int_eq(atom_vec_len(&results), 3);
+
+    char buf[64];
+    atom_ref_t atom = atom_vec_get(&results, 0);
+    atom_to_str(&atom, buf, 64);
+    ck_assert_str_eq(buf, "(a)");
+    atom = atom_vec_get(&results, 1);
+    atom_to_str(&atom, buf, 64);
+    ck_assert_str_eq(buf, "(b c)");
+    atom = atom_vec_get(&results, 2);
+    atom_to_str(&atom, buf, 64);
+    ck_assert_str_eq(buf, "d");
+    atom_vec_free(results);
+    sexpr_parser_free(parser);
+
+    //A parse error should be reported without calling the callback
+    parser = sexpr_parser_new("(a) (b c");
+    results = atom_vec_new();
+    sexpr_parser_parse_all(&parser, &tokenizer, &copy_atom_vec, &results);
+    ck_assert(sexpr_parser_err_str(&parser) != NULL);
+    ck_assert_int_eq(atom_vec_len(&results), 0);
+    atom_vec_free(results);
+    sexpr_parser_free(parser);
+
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+struct recovering_results {
+    atom_vec_t atoms;
+    size_t error_offsets[4];
+    size_t error_count;
+};
+
+void collect_recovered_atom(atom_ref_t atom, void* context) {
+    struct recovering_results* results = context;
+    atom_vec_push(&results->atoms, atom_clone(&atom));
+}
+
+void collect_parse_error(const parse_error_t* err, void* context) {
+    struct recovering_results* results = context;
+    ck_assert(results->error_count < 4);
+    results->error_offsets[results->error_count++] = parse_error_offset(err);
+}
+
+START_TEST (test_parse_recovering)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("(a) (b \"c) (d)\n) (e)");
+
+    struct recovering_results results = { atom_vec_new(), { 0 }, 0 };
+    sexpr_parser_parse_recovering(&parser, &tokenizer, &collect_recovered_atom, &collect_parse_error, &results);
+    ck_assert(sexpr_parser_err_str(&parser) == NULL);
+
+    ck_assert_int_eq(results.error_count, 2);
+    ck_assert_int_eq(results.error_offsets[0], 7);
+    ck_assert_int_eq(results.error_offsets[1], 15);
+
+    ck_assert_int_eq(atom_vec_len(&results.atoms), 3);
+    char buf[64];
+    atom_ref_t atom = atom_vec_get(&results.atoms, 0);
+    atom_to_str(&atom, buf, 64);
+    ck_assert_str_eq(buf, "(a)");
+    atom = atom_vec_get(&results.atoms, 1);
+    atom_to_str(&atom, buf, 64);
+    ck_assert_str_eq(buf, "(d)");
+    atom = atom_vec_get(&results.atoms, 2);
+    atom_to_str(&atom, buf, 64);
+    ck_assert_str_eq(buf, "(e)");
+
+    atom_vec_free(results.atoms);
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+START_TEST (test_parser_offset)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    sexpr_parser_t parser = sexpr_parser_new("(a b) c");
+    ck_assert_int_eq(sexpr_parser_offset(&parser), 0);
+
+    atom_t atom = sexpr_parser_parse(&parser, &tokenizer);
+    ck_assert_int_eq(sexpr_parser_offset(&parser), 5);
+    atom_free(atom);
+
+    atom = sexpr_parser_parse(&parser, &tokenizer);
+    ck_assert_int_eq(sexpr_parser_offset(&parser), 7);
+    atom_free(atom);
+
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
+START_TEST (test_parser_new_with_len)
+{
+    tokenizer_t tokenizer = tokenizer_new();
+    const char text[] = "(a b) c d";
+    sexpr_parser_t parser = sexpr_parser_new_with_len(text, 7);
+
+    atom_t atom = sexpr_parser_parse(&parser, &tokenizer);
+    atom_t expected = expr(atom_sym("a"), atom_sym("b"), atom_ref_null());
+    ck_assert(atom_eq(&atom, &expected));
+    atom_free(atom);
+    atom_free(expected);
+
+    atom = sexpr_parser_parse(&parser, &tokenizer);
+    expected = atom_sym("c");
+    ck_assert(atom_eq(&atom, &expected));
+    atom_free(atom);
+    atom_free(expected);
+
+    //The text beyond `len` must not be parsed
+    atom = sexpr_parser_parse(&parser, &tokenizer);
+    ck_assert(atom_is_null(&atom));
+
+    sexpr_parser_free(parser);
+    tokenizer_free(tokenizer);
+}
+END_TEST
+
 typedef struct node_types {
     int32_t count;
     syntax_node_type_t type_buf[32];
@@ -77,11 +487,61 @@ START_TEST (test_syntax_tree_parser)
 }
 END_TEST
 
+typedef struct node_texts {
+    int32_t count;
+    char text_buf[8][32];
+} node_texts;
+
+void save_node_texts(const syntax_node_t* node, void *context) {
+    node_texts* nodes = (node_texts*)context;
+    syntax_node_text(node, nodes->text_buf[nodes->count], 32);
+    nodes->count++;
+};
+
+START_TEST (test_syntax_node_text)
+{
+    sexpr_parser_t parser = sexpr_parser_new("(+ $one \"one\")");
+
+    syntax_node_t root_node = sexpr_parser_parse_to_syntax_tree(&parser);
+
+    node_texts nodes;
+    nodes.count = 0;
+    syntax_node_iterate(&root_node, &save_node_texts, &nodes);
+
+    ck_assert_int_eq(nodes.count, 8);
+    ck_assert_str_eq(nodes.text_buf[1], "+");
+    ck_assert_str_eq(nodes.text_buf[3], "$one");
+    ck_assert_str_eq(nodes.text_buf[5], "\"one\"");
+    ck_assert_str_eq(nodes.text_buf[7], "(+ $one \"one\")");
+
+    //Passing a zero-length buffer returns the required length
+    ck_assert_int_eq(syntax_node_text(&root_node, NULL, 0), 14);
+
+    syntax_node_free(root_node);
+    sexpr_parser_free(parser);
+}
+END_TEST
+
 void init_test(TCase* test_case) {
     tcase_set_timeout(test_case, 300); //300s = 5min.  To test for memory leaks
     tcase_add_checked_fixture(test_case, setup, teardown);
     tcase_add_test(test_case, test_tokenizer_parser);
+    tcase_add_test(test_case, test_tokenizer_priority);
+    tcase_add_test(test_case, test_tokenizer_matched_regex);
+    tcase_add_test(test_case, test_tokenizer_merge);
+    tcase_add_test(test_case, test_tokenizer_register_token_checked);
+    tcase_add_test(test_case, test_parser_keep_comments);
+    tcase_add_test(test_case, test_parse_checked);
+    tcase_add_test(test_case, test_parser_max_depth);
+    tcase_add_test(test_case, test_parser_variable_sigil);
+    tcase_add_test(test_case, test_parser_extra_separators);
+    tcase_add_test(test_case, test_parser_at_end);
+    tcase_add_test(test_case, test_parse_all);
+    tcase_add_test(test_case, test_parse_recovering);
+    tcase_add_test(test_case, test_parser_offset);
+    tcase_add_test(test_case, test_parser_new_with_len);
     tcase_add_test(test_case, test_syntax_tree_parser);
+    tcase_add_test(test_case, test_syntax_node_text);
 }
 
 TEST_MAIN(init_test);
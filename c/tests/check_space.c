@@ -1,4 +1,5 @@
 #include <stdio.h>
+#include <string.h>
 #include <hyperon/hyperon.h>
 
 #include "test.h"
@@ -66,6 +67,29 @@ START_TEST (test_grounding_space_query)
 }
 END_TEST
 
+START_TEST (test_grounding_space_conjunction_query)
+{
+    space_t space = space_new_grounding_space();
+    space_add(&space, expr(atom_sym("A"), atom_sym("B"), atom_ref_null()));
+    space_add(&space, expr(atom_sym("B"), atom_sym("C"), atom_ref_null()));
+    space_add(&space, expr(atom_sym("A"), atom_sym("D"), atom_ref_null()));
+    atom_t query = expr(COMMA_SYMBOL(),
+        expr(atom_sym("A"), atom_var("x"), atom_ref_null()),
+        expr(atom_var("x"), atom_var("y"), atom_ref_null()), atom_ref_null());
+
+    struct output_t result = { "", 0 };
+    bindings_set_t bindings_set = space_query(&space, &query);
+    bindings_set_iterate(&bindings_set, query_callback, &result);
+    ck_assert(strstr(result.str, "x: B, ") != NULL);
+    ck_assert(strstr(result.str, "y: C, ") != NULL);
+    ck_assert(strstr(result.str, "D") == NULL);
+
+    bindings_set_free(bindings_set);
+    atom_free(query);
+    space_free(space);
+}
+END_TEST
+
 START_TEST (test_grounding_space_add)
 {
     space_t space = space_new_grounding_space();;
@@ -101,6 +125,27 @@ START_TEST (test_grounding_space_remove)
 }
 END_TEST
 
+START_TEST (test_grounding_space_remove_all)
+{
+    space_t space = space_new_grounding_space();
+    space_add(&space, atom_sym("A"));
+    space_add(&space, atom_sym("B"));
+    space_add(&space, atom_sym("C"));
+
+    atom_t a = atom_sym("A");
+    atom_t c = atom_sym("C");
+    atom_t d = atom_sym("D");
+    atom_ref_t atoms[] = { atom_ref(&a), atom_ref(&c), atom_ref(&d) };
+    ck_assert_int_eq(space_remove_all(&space, atoms, 3), 2);
+    ck_assert_int_eq(space_atom_count(&space), 1);
+
+    atom_free(a);
+    atom_free(c);
+    atom_free(d);
+    space_free(space);
+}
+END_TEST
+
 START_TEST (test_grounding_space_replace)
 {
     space_t space = space_new_grounding_space();;
@@ -124,6 +169,48 @@ START_TEST (test_grounding_space_replace)
 }
 END_TEST
 
+START_TEST (test_grounding_space_from_atoms)
+{
+    atom_t atoms[] = { atom_sym("A"), expr(atom_sym("B"), atom_var("x"), atom_ref_null()), atom_sym("C") };
+    space_t space = grounding_space_from_atoms(atoms, 3);
+
+    ck_assert_int_eq(space_atom_count(&space), 3);
+
+    struct output_t result = { "", 0 };
+    space_iterate(&space, atom_string_callback, &result);
+    ck_assert_str_eq(result.str, "A, (B $x), C, ");
+
+    space_free(space);
+}
+END_TEST
+
+START_TEST (test_grounding_space_clone)
+{
+    space_t space = space_new_grounding_space();
+    space_add(&space, atom_sym("A"));
+    space_add(&space, atom_sym("B"));
+
+    space_t clone = space_clone(&space);
+    ck_assert(!space_eq(&space, &clone));
+    atom_t a = atom_sym("A");
+    ck_assert(space_remove(&clone, &a));
+    space_add(&clone, atom_sym("C"));
+
+    struct output_t result = { "", 0 };
+    space_iterate(&space, atom_string_callback, &result);
+    ck_assert_str_eq(result.str, "A, B, ");
+    reset_output(&result);
+    space_iterate(&clone, atom_string_callback, &result);
+    ck_assert_int_eq(space_atom_count(&clone), 2);
+    ck_assert(strstr(result.str, "A") == NULL);
+    ck_assert(strstr(result.str, "C, ") != NULL);
+
+    atom_free(a);
+    space_free(clone);
+    space_free(space);
+}
+END_TEST
+
 typedef struct _my_observer {
     size_t      atom_count;
 } my_observer_t;
@@ -139,6 +226,12 @@ void observer_notify(void* payload, const space_event_t* event) {
             break;
         case SPACE_EVENT_TYPE_REPLACE:
             break;
+        case SPACE_EVENT_TYPE_BATCH:
+            for (size_t i = 0; i < space_event_batch_len(event); i++) {
+                space_event_t sub_event = space_event_batch_get(event, i);
+                observer_notify(payload, &sub_event);
+            }
+            break;
     }
 }
 
@@ -209,6 +302,52 @@ START_TEST (test_custom_c_space)
     space_free(space);
 }
 
+void record_event(space_event_type_t event_type, const atom_ref_t* atom_a, const atom_ref_t* atom_b, void* context) {
+    struct output_t* out = context;
+    switch (event_type) {
+        case SPACE_EVENT_TYPE_ADD:
+            out->len += snprintf(out->str + out->len, 1024 - out->len, "add ");
+            break;
+        case SPACE_EVENT_TYPE_REMOVE:
+            out->len += snprintf(out->str + out->len, 1024 - out->len, "remove ");
+            break;
+        case SPACE_EVENT_TYPE_REPLACE:
+            out->len += snprintf(out->str + out->len, 1024 - out->len, "replace ");
+            break;
+        default:
+            abort();
+    }
+    out->len += atom_to_str(atom_a, out->str + out->len, 1024 - out->len);
+    if (atom_b != NULL) {
+        out->len += snprintf(out->str + out->len, 1024 - out->len, " ");
+        out->len += atom_to_str(atom_b, out->str + out->len, 1024 - out->len);
+    }
+    out->len += snprintf(out->str + out->len, 1024 - out->len, ", ");
+}
+
+START_TEST (test_space_callback_observer)
+{
+    space_t space = space_new_grounding_space();
+    struct output_t events = { "", 0 };
+    space_callback_observer_t observer = space_register_callback_observer(&space, &record_event, &events);
+
+    atom_t a = atom_sym("A");
+    atom_t b = atom_sym("B");
+    space_add(&space, atom_sym("A"));
+    ck_assert(space_replace(&space, &a, atom_sym("B")));
+    ck_assert(space_remove(&space, &b));
+    ck_assert_str_eq(events.str, "add A, replace A B, remove B, ");
+
+    space_callback_observer_free(observer);
+    space_add(&space, atom_sym("C"));
+    ck_assert_str_eq(events.str, "add A, replace A B, remove B, ");
+
+    atom_free(a);
+    atom_free(b);
+    space_free(space);
+}
+END_TEST
+
 atom_t clone_atom_token_constructor(char const* token, void* context) {
     return atom_clone((atom_t*)context);
 }
@@ -256,10 +395,15 @@ void init_test(TCase* test_case) {
     tcase_set_timeout(test_case, 300); //300s = 5min.  To test for memory leaks
     tcase_add_checked_fixture(test_case, setup, teardown);
     tcase_add_test(test_case, test_grounding_space_query);
+    tcase_add_test(test_case, test_grounding_space_conjunction_query);
     tcase_add_test(test_case, test_grounding_space_add);
     tcase_add_test(test_case, test_grounding_space_remove);
+    tcase_add_test(test_case, test_grounding_space_remove_all);
     tcase_add_test(test_case, test_grounding_space_replace);
+    tcase_add_test(test_case, test_grounding_space_from_atoms);
+    tcase_add_test(test_case, test_grounding_space_clone);
     tcase_add_test(test_case, test_custom_c_space);
+    tcase_add_test(test_case, test_space_callback_observer);
     tcase_add_test(test_case, test_space_nested_in_atom);
 }
 
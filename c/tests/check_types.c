@@ -28,6 +28,56 @@ START_TEST (test_check_type)
 }
 END_TEST
 
+void collect_type_bindings(bindings_t* bindings, void* context) {
+    atom_vec_t* results = context;
+    atom_t value = bindings_resolve(bindings, atom_var("t"));
+    atom_vec_push(results, atom_is_null(&value) ? atom_sym("<none>") : value);
+}
+
+START_TEST (test_check_type_bindings)
+{
+    space_t space = space_new_grounding_space();
+    space_add(&space, expr(atom_sym(":"), atom_sym("a"), expr(atom_sym("List"), atom_sym("A"), atom_ref_null()), atom_ref_null()));
+    space_add(&space, expr(atom_sym(":"), atom_sym("b"), atom_sym("B"), atom_ref_null()));
+
+    atom_t a = atom_sym("a");
+    atom_t b = atom_sym("b");
+    atom_t list_t = expr(atom_sym("List"), atom_var("t"), atom_ref_null());
+    atom_t B = atom_sym("B");
+    atom_t C = atom_sym("C");
+
+    atom_vec_t results = atom_vec_new();
+    check_type_bindings(&space, &a, &list_t, &collect_type_bindings, &results);
+    ck_assert_int_eq(atom_vec_len(&results), 1);
+    atom_ref_t t_value = atom_vec_get(&results, 0);
+    atom_t A = atom_sym("A");
+    ck_assert(atom_eq(&t_value, &A));
+    atom_free(A);
+    atom_vec_free(results);
+
+    results = atom_vec_new();
+    check_type_bindings(&space, &b, &B, &collect_type_bindings, &results);
+    ck_assert_int_eq(atom_vec_len(&results), 1);
+    t_value = atom_vec_get(&results, 0);
+    atom_t none = atom_sym("<none>");
+    ck_assert(atom_eq(&t_value, &none));
+    atom_free(none);
+    atom_vec_free(results);
+
+    results = atom_vec_new();
+    check_type_bindings(&space, &b, &C, &collect_type_bindings, &results);
+    ck_assert_int_eq(atom_vec_len(&results), 0);
+    atom_vec_free(results);
+
+    atom_free(C);
+    atom_free(B);
+    atom_free(list_t);
+    atom_free(b);
+    atom_free(a);
+    space_free(space);
+}
+END_TEST
+
 START_TEST (test_validate_atom)
 {
     space_t space = space_new_grounding_space();
@@ -43,6 +93,28 @@ START_TEST (test_validate_atom)
 }
 END_TEST
 
+START_TEST (test_validate_atom_explain)
+{
+    space_t space = space_new_grounding_space();
+    space_add(&space, expr(atom_sym(":"), atom_sym("a"), atom_sym("A"), atom_ref_null()));
+    space_add(&space, expr(atom_sym(":"), atom_sym("b"), atom_sym("B"), atom_ref_null()));
+    space_add(&space, expr(atom_sym(":"), atom_sym("foo"), expr(atom_sym("->"), atom_sym("A"), atom_sym("B"), atom_ref_null()), atom_ref_null()));
+
+    char err[256] = "";
+    atom_t foo_a = expr(atom_sym("foo"), atom_sym("a"), atom_ref_null());
+    ck_assert(validate_atom_explain(&space, &foo_a, err, sizeof(err)));
+    ck_assert_str_eq(err, "");
+    atom_free(foo_a);
+
+    atom_t foo_b = expr(atom_sym("foo"), atom_sym("b"), atom_ref_null());
+    ck_assert(!validate_atom_explain(&space, &foo_b, err, sizeof(err)));
+    ck_assert_str_eq(err, "Incorrect type of argument 1 of (foo b): expected A, found B");
+    atom_free(foo_b);
+
+    space_free(space);
+}
+END_TEST
+
 typedef struct _atoms_t {
     atom_t** items;
     size_t size;
@@ -98,12 +170,42 @@ START_TEST (test_get_atom_types)
 }
 END_TEST
 
+START_TEST (test_get_atom_type_single)
+{
+    space_t space = space_new_grounding_space();
+    space_add(&space, expr(atom_sym(":"), atom_sym("a"), expr(atom_sym("->"), atom_sym("C"), atom_sym("D"), atom_ref_null()), atom_ref_null()));
+    space_add(&space, expr(atom_sym(":"), atom_sym("b"), atom_sym("B"), atom_ref_null()));
+
+    atom_t b = atom_sym("b");
+    atom_t B = atom_sym("B");
+    atom_t b_type = get_atom_type_single(&space, &b);
+    ck_assert(atom_eq(&b_type, &B));
+
+    atom_t call_a_b = expr(atom_sym("a"), atom_sym("b"), atom_ref_null());
+    atom_t undefined = ATOM_TYPE_UNDEFINED();
+    atom_t call_a_b_type = get_atom_type_single(&space, &call_a_b);
+    ck_assert(atom_eq(&call_a_b_type, &undefined));
+
+    atom_free(call_a_b_type);
+    atom_free(undefined);
+    atom_free(call_a_b);
+    atom_free(b_type);
+    atom_free(B);
+    atom_free(b);
+
+    space_free(space);
+}
+END_TEST
+
 void init_test(TCase* test_case) {
     tcase_set_timeout(test_case, 300); //300s = 5min.  To test for memory leaks
     tcase_add_checked_fixture(test_case, setup, teardown);
     tcase_add_test(test_case, test_check_type);
+    tcase_add_test(test_case, test_check_type_bindings);
     tcase_add_test(test_case, test_validate_atom);
+    tcase_add_test(test_case, test_validate_atom_explain);
     tcase_add_test(test_case, test_get_atom_types);
+    tcase_add_test(test_case, test_get_atom_type_single);
 }
 
 TEST_MAIN(init_test);
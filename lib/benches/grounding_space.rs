@@ -36,3 +36,22 @@ fn query_x100(bencher: &mut Bencher) {
         assert_eq!(res, bind_set![{ X: Atom::sym("arg") }]);
     })
 }
+
+fn facts(size: isize) -> GroundingSpace {
+    let mut space = GroundingSpace::new();
+    for i in (0..size).step_by(1) {
+        space.add(Atom::expr([Atom::sym("edge"),
+            Atom::sym(format!("node-{:X}", i)),
+            Atom::sym(format!("node-{:X}", i + 1))]));
+    }
+    space
+}
+
+#[bench]
+fn ground_query_x100(bencher: &mut Bencher) {
+    let space = facts(100);
+    bencher.iter(|| {
+        let res = space.query(&expr!("edge" "node-2A" "node-2B"));
+        assert_eq!(res, matcher::BindingsSet::single());
+    })
+}
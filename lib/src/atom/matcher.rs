@@ -318,7 +318,7 @@ impl Bindings {
     }
 
     fn match_values(&self, current: &Atom, value: &Atom) -> BindingsSet {
-        match_atoms_recursively(current, value).into_iter()
+        match_atoms_recursively(current, value, GroundedMatchMode::Custom).into_iter()
             .flat_map(|binding| binding.merge_v2(self))
             .collect()
     }
@@ -1117,9 +1117,65 @@ pub type MatchResultIter = Box<dyn Iterator<Item=Bindings>>;
 /// assert_eq!(empty, vec![]);
 /// ```
 pub fn match_atoms<'a>(left: &'a Atom, right: &'a Atom) -> MatchResultIter {
-    Box::new(match_atoms_recursively(left, right).into_iter()
-        .filter(|binding| {
-            if binding.has_loops() {
+    match_atoms_with_mode(left, right, GroundedMatchMode::Custom, true)
+}
+
+/// Matches two atoms like [match_atoms] but doesn't perform the occurs
+/// check: results which contain variable loops are not excluded. For example
+/// matching `$x` with `(f $x)` returns `{ $x = (f $x) }` which describes an
+/// infinite atom. It is faster than [match_atoms] but such results should be
+/// handled with care.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon::*;
+/// use hyperon::atom::matcher::*;
+///
+/// let left  = expr!(a (a));
+/// let right = expr!(x  x );
+///
+/// assert_eq!(match_atoms(&left, &right).count(), 0);
+/// assert_eq!(match_atoms_without_occurs_check(&left, &right).count(), 1);
+/// ```
+pub fn match_atoms_without_occurs_check<'a>(left: &'a Atom, right: &'a Atom) -> MatchResultIter {
+    match_atoms_with_mode(left, right, GroundedMatchMode::Custom, false)
+}
+
+/// Defines how grounded atoms are matched, see [match_atoms_with_mode].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroundedMatchMode {
+    /// Grounded atom which implements [CustomMatch] is matched using
+    /// [CustomMatch::match_], other grounded atoms are compared for equality.
+    #[default]
+    Custom,
+    /// Grounded atoms are compared for equality only, [CustomMatch] is not
+    /// used. It is useful when custom matching is expensive or when the
+    /// caller needs only to find the same grounded atom.
+    Identity,
+}
+
+/// Matches two atoms like [match_atoms] but allows choosing how grounded
+/// atoms are matched using `mode`. When `occurs_check` is false the results
+/// which contain variable loops are not excluded, see
+/// [match_atoms_without_occurs_check].
+///
+/// # Examples
+///
+/// ```
+/// use hyperon::*;
+/// use hyperon::atom::matcher::*;
+/// use hyperon::space::grounding::GroundingSpace;
+///
+/// let space = Atom::gnd(GroundingSpace::from_vec(vec![sym!("A")]));
+///
+/// assert_eq!(match_atoms_with_mode(&space, &sym!("A"), GroundedMatchMode::Custom, true).count(), 1);
+/// assert_eq!(match_atoms_with_mode(&space, &sym!("A"), GroundedMatchMode::Identity, true).count(), 0);
+/// ```
+pub fn match_atoms_with_mode<'a>(left: &'a Atom, right: &'a Atom, mode: GroundedMatchMode, occurs_check: bool) -> MatchResultIter {
+    Box::new(match_atoms_recursively(left, right, mode).into_iter()
+        .filter(move |binding| {
+            if occurs_check && binding.has_loops() {
                 log::trace!("match_atoms: remove bindings which contains a variable loop: {}", binding);
                 false
             } else {
@@ -1128,7 +1184,8 @@ pub fn match_atoms<'a>(left: &'a Atom, right: &'a Atom) -> MatchResultIter {
         }))
 }
 
-fn match_atoms_recursively(left: &Atom, right: &Atom) -> BindingsSet {
+fn match_atoms_recursively(left: &Atom, right: &Atom, mode: GroundedMatchMode) -> BindingsSet {
+    let custom = mode == GroundedMatchMode::Custom;
     let res = match (left, right) {
         (Atom::Symbol(a), Atom::Symbol(b)) if a == b => BindingsSet::single(),
         (Atom::Variable(dv), Atom::Variable(pv)) => BindingsSet::single().add_var_equality(dv, pv),
@@ -1142,13 +1199,13 @@ fn match_atoms_recursively(left: &Atom, right: &Atom) -> BindingsSet {
         if a.len() == b.len() => {
             a.iter().zip(b.iter()).fold(BindingsSet::single(),
             |acc, (a, b)| {
-                acc.merge(&match_atoms_recursively(a, b))
+                acc.merge(&match_atoms_recursively(a, b, mode))
             })
         },
-        (Atom::Grounded(a), _) if a.as_grounded().as_match().is_some() => {
+        (Atom::Grounded(a), _) if custom && a.as_grounded().as_match().is_some() => {
             a.as_grounded().as_match().unwrap().match_(right).collect()
         },
-        (_, Atom::Grounded(b)) if b.as_grounded().as_match().is_some() => {
+        (_, Atom::Grounded(b)) if custom && b.as_grounded().as_match().is_some() => {
             b.as_grounded().as_match().unwrap().match_(left).collect()
         },
         (Atom::Grounded(a), Atom::Grounded(b)) if a.eq_gnd(AsRef::as_ref(b)) => BindingsSet::single(),
@@ -311,13 +311,114 @@ impl Display for VariableAtom {
     }
 }
 
-/// Returns `atom` with all variables replaced by unique instances.
+/// Returns `atom` with all variables replaced by unique instances. Each
+/// distinct variable is renamed using [VariableAtom::make_unique] which takes
+/// the next value of the global variable id counter, so all occurrences of the
+/// same variable inside `atom` are replaced by the same fresh variable. It is
+/// used to prevent variable capture when atoms from different scopes are
+/// combined.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon::{expr, collect_variables, make_variables_unique};
+///
+/// let atom = expr!("foo" x ("bar" x));
+/// let unique = make_variables_unique(atom.clone());
+/// let vars = collect_variables(&unique);
+///
+/// assert_eq!(vars.len(), 1);
+/// assert!(collect_variables(&atom).iter().all(|var| !vars.contains(var)));
+/// ```
 pub fn make_variables_unique(mut atom: Atom) -> Atom {
     let mut mapper = crate::common::CachingMapper::new(VariableAtom::make_unique);
     atom.iter_mut().filter_type::<&mut VariableAtom>().for_each(|var| *var = mapper.replace(var.clone()));
     atom
 }
 
+/// Returns `atom` with variables renamed according to the `mapping`.
+/// Variables which are not keys of the `mapping` are kept as is. Unlike
+/// [make_variables_unique] the new variables are chosen by the caller, so
+/// the result is deterministic.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon::{expr, VariableAtom, rename_variables};
+/// use std::collections::HashMap;
+///
+/// let mapping = HashMap::from([(VariableAtom::new("x"), VariableAtom::new("y"))]);
+///
+/// assert_eq!(rename_variables(&expr!("foo" x z), &mapping), expr!("foo" y z));
+/// ```
+pub fn rename_variables(atom: &Atom, mapping: &std::collections::HashMap<VariableAtom, VariableAtom>) -> Atom {
+    let mut atom = atom.clone();
+    atom.iter_mut().filter_type::<&mut VariableAtom>()
+        .for_each(|var| if let Some(renamed) = mapping.get(var) { *var = renamed.clone() });
+    atom
+}
+
+/// Returns all distinct variables of the `atom` in order of their first
+/// occurrence.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon::{expr, VariableAtom, collect_variables};
+///
+/// let atom = expr!("foo" x ("bar" y x));
+///
+/// assert_eq!(collect_variables(&atom), vec![VariableAtom::new("x"), VariableAtom::new("y")]);
+/// ```
+pub fn collect_variables(atom: &Atom) -> Vec<VariableAtom> {
+    let mut seen = std::collections::HashSet::new();
+    atom.iter().filter_type::<&VariableAtom>()
+        .filter(|var| seen.insert(*var))
+        .cloned().collect()
+}
+
+/// Returns `atom` with variables renamed to `$_0`, `$_1`, ... in order of
+/// their first occurrence. Atoms which differ only by names of variables
+/// (alpha-equivalent atoms) have the same canonical form, thus they can be
+/// deduplicated by hashing the canonical form. Use
+/// [matcher::atoms_are_equivalent] to compare two atoms directly.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon::expr;
+/// use hyperon::atom::alpha_canonical;
+///
+/// assert_eq!(alpha_canonical(&expr!("foo" x ("bar" y x))), expr!("foo" _0 ("bar" _1 _0)));
+/// assert_eq!(alpha_canonical(&expr!("f" x x)), alpha_canonical(&expr!("f" y y)));
+/// ```
+pub fn alpha_canonical(atom: &Atom) -> Atom {
+    let mapping = collect_variables(atom).into_iter().enumerate()
+        .map(|(i, var)| (var, VariableAtom::new(format!("_{}", i))))
+        .collect();
+    rename_variables(atom, &mapping)
+}
+
+/// Returns the number of atoms in the `atom` tree. Symbol, variable and
+/// grounded atom count as 1, expression counts as 1 plus the sum of the
+/// counts of its children. The count can be used to estimate the cost of
+/// processing the atom, for instance to reject too large atoms.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon::{expr, node_count};
+///
+/// assert_eq!(node_count(&expr!("A")), 1);
+/// assert_eq!(node_count(&expr!("foo" x ("bar" y))), 6);
+/// ```
+pub fn node_count(atom: &Atom) -> usize {
+    match atom {
+        Atom::Expression(expr) => 1 + expr.children().iter().map(node_count).sum::<usize>(),
+        _ => 1,
+    }
+}
+
 // Grounded atom
 
 // The main idea is to keep grounded atom behaviour implementation inside
@@ -928,6 +1029,22 @@ impl Atom {
             _ => None,
         }
     }
+
+    /// Returns the wrapper which displays the atom on multiple lines, see
+    /// [PrettyAtom].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::expr;
+    ///
+    /// let atom = expr!("=" ("f" x) ("g" x));
+    ///
+    /// assert_eq!(atom.pretty(2).to_string(), "(=\n  (f $x)\n  (g $x))");
+    /// ```
+    pub fn pretty(&self, indent_width: usize) -> PrettyAtom<'_> {
+        PrettyAtom{ atom: self, indent_width }
+    }
 }
 
 impl PartialEq for Atom {
@@ -1061,6 +1178,23 @@ impl<'a> TryFrom<&'a Atom> for &'a dyn GroundedAtom {
 
 impl Eq for Atom {}
 
+/// Equal atoms have equal hashes. Symbols, variables and expressions are
+/// hashed structurally. [Grounded] trait doesn't require [Hash] to be
+/// implemented, thus grounded atoms are hashed by the Rust type of the
+/// grounded value only: grounded atoms of the same type have equal hashes
+/// even when they are not equal.
+impl std::hash::Hash for Atom {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Atom::Symbol(sym) => sym.hash(state),
+            Atom::Expression(expr) => expr.children().hash(state),
+            Atom::Variable(var) => var.hash(state),
+            Atom::Grounded(gnd) => gnd.as_any_ref().type_id().hash(state),
+        }
+    }
+}
+
 impl Display for Atom {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1078,6 +1212,42 @@ impl Debug for Atom {
     }
 }
 
+/// Displays an atom on multiple lines with indentation. An expression which
+/// contains other expressions is always broken: its first child is written
+/// on the line of the opening parenthesis and each other child is written
+/// on a separate line indented by `indent_width` spaces relative to the
+/// expression. Expressions without nested expressions are written on a
+/// single line as [Display] of [Atom] does. Created by [Atom::pretty].
+pub struct PrettyAtom<'a> {
+    atom: &'a Atom,
+    indent_width: usize,
+}
+
+impl PrettyAtom<'_> {
+    fn fmt_indented(&self, atom: &Atom, indent: usize, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match atom {
+            Atom::Expression(expr) if expr.children().iter().any(|child| matches!(child, Atom::Expression(_))) => {
+                let child_indent = indent + self.indent_width;
+                write!(f, "(")?;
+                for (i, child) in expr.children().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "\n{:1$}", "", child_indent)?;
+                    }
+                    self.fmt_indented(child, child_indent, f)?;
+                }
+                write!(f, ")")
+            },
+            _ => Display::fmt(atom, f),
+        }
+    }
+}
+
+impl Display for PrettyAtom<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_indented(self.atom, 0, f)
+    }
+}
+
 #[cfg(test)]
 mod test {
     #![allow(non_snake_case)]
@@ -1291,4 +1461,68 @@ mod test {
             Err("Atom is not an ExpressionAtom"));
     }
 
+    fn hash(atom: &Atom) -> u64 {
+        use std::hash::{DefaultHasher, Hash, Hasher};
+        let mut hasher = DefaultHasher::new();
+        atom.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_atom_hash() {
+        assert_eq!(hash(&expr!("a" "b")), hash(&expr!("a" "b")));
+        assert_eq!(hash(&expr!("a" x {1})), hash(&expr!("a" x {1})));
+        assert_eq!(hash(&Atom::value(1)), hash(&Atom::value(2)));
+        assert_ne!(hash(&expr!("a" "b")), hash(&expr!("b" "a")));
+        assert_ne!(hash(&expr!("a")), hash(&expr!(("a"))));
+        assert_ne!(hash(&expr!("a")), hash(&expr!(a)));
+
+        let set: std::collections::HashSet<Atom> = [expr!("a" "b"), expr!("a" "b"), expr!("b")].into();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_pretty_atom() {
+        assert_eq!(sym!("A").pretty(2).to_string(), "A");
+        assert_eq!(expr!("A" b).pretty(2).to_string(), "(A $b)");
+        assert_eq!(expr!("A" ("B" ("C" "D") "E") "F").pretty(2).to_string(),
+            "(A\n  (B\n    (C D)\n    E)\n  F)");
+        assert_eq!(expr!(("A") "B").pretty(1).to_string(), "((A)\n B)");
+    }
+
+    #[test]
+    fn test_alpha_canonical() {
+        assert_eq!(alpha_canonical(&expr!("f" x x)), alpha_canonical(&expr!("f" y y)));
+        assert_ne!(alpha_canonical(&expr!("f" x y)), alpha_canonical(&expr!("f" y y)));
+        assert_eq!(alpha_canonical(&expr!("f" x y)), alpha_canonical(&expr!("f" y x)));
+        assert_eq!(alpha_canonical(&expr!("f" x "_0")), expr!("f" _0 "_0"));
+        assert_eq!(alpha_canonical(&expr!(_1 _0)), expr!(_0 _1));
+    }
+
+    #[test]
+    fn test_node_count() {
+        assert_eq!(node_count(&sym!("A")), 1);
+        assert_eq!(node_count(&expr!(x)), 1);
+        assert_eq!(node_count(&expr!({1})), 1);
+        assert_eq!(node_count(&expr!()), 1);
+        assert_eq!(node_count(&expr!("A" "B" x)), 4);
+        assert_eq!(node_count(&expr!("A" ("B" ("C" x)) ())), 8);
+    }
+
+    #[test]
+    fn test_collect_variables() {
+        assert_eq!(collect_variables(&expr!("foo" x ("bar" y x) y)),
+            vec![VariableAtom::new("x"), VariableAtom::new("y")]);
+        assert_eq!(collect_variables(&expr!("foo" "bar")), vec![]);
+        assert_eq!(collect_variables(&expr!(x)), vec![VariableAtom::new("x")]);
+    }
+
+    #[test]
+    fn test_rename_variables() {
+        let mapping = HashMap::from([(VariableAtom::new("x"), VariableAtom::new("y")),
+            (VariableAtom::new("y"), VariableAtom::new("z"))]);
+        assert_eq!(rename_variables(&expr!("foo" x ("bar" y x) w), &mapping), expr!("foo" y ("bar" z y) w));
+        assert_eq!(rename_variables(&expr!("foo"), &mapping), expr!("foo"));
+    }
+
 }
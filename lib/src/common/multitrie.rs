@@ -189,6 +189,17 @@ impl<T: Display> Display for TrieKey<T> {
     }
 }
 
+/// Statistics about the shape of a [MultiTrie], see [MultiTrie::stats].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrieStats {
+    /// Total number of nodes including the root node.
+    pub node_count: usize,
+    /// Maximal number of tokens on the path from the root to a node.
+    pub max_depth: usize,
+    /// Total number of values kept in the trie.
+    pub value_count: usize,
+}
+
 /// Multi-value trie with double side matching. See [crate::common::multitrie]
 /// for the algorithm description.
 #[derive(Clone, Debug)]
@@ -292,6 +303,26 @@ where
         self.0.remove(key, value)
     }
 
+    /// Returns statistics about the shape of the trie.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::common::multitrie::*;
+    ///
+    /// let mut trie = MultiTrie::new();
+    ///
+    /// trie.insert(TrieKey::from([TrieToken::Exact("A"), TrieToken::Exact("B")]), "AB");
+    /// trie.insert(TrieKey::from([TrieToken::Exact("A"), TrieToken::Exact("C")]), "AC");
+    ///
+    /// assert_eq!(trie.stats(), TrieStats{ node_count: 4, max_depth: 2, value_count: 2 });
+    /// ```
+    pub fn stats(&self) -> TrieStats {
+        let mut stats = TrieStats::default();
+        self.0.collect_stats(0, &mut stats);
+        stats
+    }
+
     #[cfg(test)]
     fn size(&self) -> usize {
         self.0.size()
@@ -428,6 +459,15 @@ where
         MultiValueIter::new(self, key.iter()).flat_map(|node| node.values.iter())
     }
 
+    fn collect_stats(&self, depth: usize, stats: &mut TrieStats) {
+        stats.node_count += 1;
+        stats.max_depth = stats.max_depth.max(depth);
+        stats.value_count += self.values.len();
+        for child in self.children.values() {
+            child.borrow().collect_stats(depth + 1, stats);
+        }
+    }
+
     #[cfg(test)]
     fn size(&self) -> usize {
         let mut visited = HashSet::new();
@@ -202,6 +202,28 @@ impl<'a, T: SpaceRef<'a>> InterpreterState<'a, T> {
         !self.plan.is_empty()
     }
 
+    /// Returns the atoms which are going to be evaluated by the next steps,
+    /// one per alternative. The alternative which is evaluated first is returned first.
+    pub fn current_atoms(&self) -> impl Iterator<Item=&Atom> {
+        self.plan.iter().rev().map(|InterpretedAtom(stack, _bindings)| &stack.atom)
+    }
+
+    /// Returns the number of fully evaluated results. While evaluation is not
+    /// finished it is the number of results found so far.
+    pub fn result_count(&self) -> usize {
+        self.finished.len()
+    }
+
+    /// Returns the first error expression (see [atom_is_error]) among the
+    /// results when evaluation is finished. Returns `None` when there are no
+    /// errors or there are still alternatives to be evaluated.
+    pub fn error(&self) -> Option<Atom> {
+        match self.has_next() {
+            true => None,
+            false => self.finished.iter().find(|atom| atom_is_error(atom)).cloned(),
+        }
+    }
+
     /// Returns vector of fully evaluated results or error if there are still
     /// alternatives to be evaluated.
     pub fn into_result(self) -> Result<Vec<Atom>, String> {
@@ -273,13 +295,43 @@ pub fn interpret_step<'a, T: Space + 'a>(mut state: InterpreterState<'a, T>) ->
 /// * `space` - atomspace to query for interpretation
 /// * `expr` - atom to interpret
 pub fn interpret<T: Space>(space: T, expr: &Atom) -> Result<Vec<Atom>, String> {
+    interpret_traced(space, expr, |_| {})
+}
+
+/// Interpret passed atom like [interpret] and call `step_callback` with the
+/// interpreter state after each step of the interpretation.
+/// # Arguments
+/// * `space` - atomspace to query for interpretation
+/// * `expr` - atom to interpret
+/// * `step_callback` - function which is called after each step
+pub fn interpret_traced<'a, T, F>(space: T, expr: &Atom, mut step_callback: F) -> Result<Vec<Atom>, String>
+    where T: Space + 'a, F: FnMut(&InterpreterState<'a, T>)
+{
     let mut state = interpret_init(space, expr);
     while state.has_next() {
         state = interpret_step(state);
+        step_callback(&state);
     }
     state.into_result()
 }
 
+/// Interpret passed atom like [interpret] but stop as soon as `max_results`
+/// results are calculated. Alternatives which are not evaluated yet are
+/// abandoned, thus no more than `max_results` results are returned.
+/// # Arguments
+/// * `space` - atomspace to query for interpretation
+/// * `expr` - atom to interpret
+/// * `max_results` - maximal number of results to calculate
+pub fn interpret_limited<T: Space>(space: T, expr: &Atom, max_results: usize) -> Result<Vec<Atom>, String> {
+    let mut state = interpret_init(space, expr);
+    while state.has_next() && state.result_count() < max_results {
+        state = interpret_step(state);
+    }
+    let mut results = state.finished;
+    results.truncate(max_results);
+    Ok(results)
+}
+
 fn is_embedded_op(atom: &Atom) -> bool {
     let expr = atom_as_slice(&atom);
     match expr {
@@ -1358,6 +1410,32 @@ mod tests {
             vec![expr!("Error" ("eval" "a" "b") "expected: (eval <atom>), found: (eval a b)")]);
     }
 
+    #[test]
+    fn interpreter_state_error() {
+        let mut state = interpret_init(space(""), &metta_atom("(eval)"));
+        while state.has_next() {
+            assert_eq!(state.error(), None);
+            state = interpret_step(state);
+        }
+        assert_eq!(state.error(), Some(expr!("Error" ("eval") "expected: (eval <atom>), found: (eval)")));
+
+        let mut state = interpret_init(space("(= a b)"), &metta_atom("(eval a)"));
+        while state.has_next() {
+            state = interpret_step(state);
+        }
+        assert_eq!(state.error(), None);
+    }
+
+    #[test]
+    fn interpret_limited_stops_after_max_results() {
+        let space = space("(= (foo) a) (= (foo) b) (= (foo) c)");
+        let results = interpret_limited(&space, &metta_atom("(eval (foo))"), 2).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|atom| call_interpret(&space, &metta_atom("(eval (foo))")).contains(atom)));
+        assert_eq!(interpret_limited(&space, &metta_atom("(eval (foo))"), 0), Ok(vec![]));
+        assert_eq!(interpret_limited(&space, &metta_atom("(eval (foo))"), 5).map(|r| r.len()), Ok(3));
+    }
+
     #[test]
     fn interpret_atom_evaluate_atom() {
         let result = call_interpret(&space("(= a b)"), &metta_atom("(eval a)"));
@@ -1748,6 +1826,21 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn interpret_state_current_atoms() {
+        let space = space("(= (color) red) (= (color) green)");
+        let mut state = interpret_init(&space, &metta_atom("(chain (eval (color)) $x (foo $x))"));
+        assert_eq!(state.current_atoms().cloned().collect::<Vec<_>>(),
+            vec![metta_atom("(eval (color))")]);
+        state = interpret_step(state);
+        state = interpret_step(state);
+        assert_eq!(state.current_atoms().count(), 2);
+        while state.has_next() {
+            state = interpret_step(state);
+        }
+        assert_eq!(state.current_atoms().count(), 0);
+    }
+
     fn space(text: &str) -> GroundingSpace {
         metta_space(text)
     }
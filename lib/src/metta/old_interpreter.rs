@@ -99,6 +99,31 @@ impl<'a, T: SpaceRef<'a>> InterpreterState<'a, T> {
     pub fn has_next(&self) -> bool {
         self.step_result.has_next()
     }
+    /// Returns the atoms which are going to be evaluated by the next steps. The
+    /// plan of this interpreter doesn't expose intermediate atoms, so nothing is returned.
+    pub fn current_atoms(&self) -> impl Iterator<Item=&Atom> {
+        std::iter::empty()
+    }
+    /// Returns the number of results. This interpreter returns results only
+    /// when evaluation is finished, so zero is returned before that.
+    pub fn result_count(&self) -> usize {
+        match &self.step_result {
+            StepResult::Return(res) => res.len(),
+            StepResult::Error(_) => 1,
+            StepResult::Execute(_) => 0,
+        }
+    }
+    /// Returns the error expression (see [atom_is_error]) when evaluation is
+    /// finished with an error or one of the results is an error. Returns
+    /// `None` otherwise.
+    pub fn error(&self) -> Option<Atom> {
+        match &self.step_result {
+            StepResult::Return(res) => res.iter().map(|res| res.atom())
+                .find(|atom| atom_is_error(atom)).cloned(),
+            StepResult::Error((atom, err)) => Some(Atom::expr([ERROR_SYMBOL, atom.clone(), err.clone()])),
+            StepResult::Execute(_) => None,
+        }
+    }
     pub fn into_result(self) -> Result<Vec<Atom>, String> {
         match self.step_result {
             StepResult::Return(mut res) => {
@@ -209,9 +234,22 @@ pub fn interpret_step<'a, T: Space + 'a>(step: InterpreterState<'a, T>) -> Inter
 /// * `space` - atomspace to query for interpretation
 /// * `expr` - atom to interpret
 pub fn interpret<T: Space>(space: T, expr: &Atom) -> Result<Vec<Atom>, String> {
+    interpret_traced(space, expr, |_| {})
+}
+
+/// Interpret passed atom like [interpret] and call `step_callback` with the
+/// interpreter state after each step of the interpretation.
+/// # Arguments
+/// * `space` - atomspace to query for interpretation
+/// * `expr` - atom to interpret
+/// * `step_callback` - function which is called after each step
+pub fn interpret_traced<'a, T, F>(space: T, expr: &Atom, mut step_callback: F) -> Result<Vec<Atom>, String>
+    where T: Space + 'a, F: FnMut(&InterpreterState<'a, T>)
+{
     let mut step = interpret_init(space, expr);
     while step.step_result.has_next() {
         step = interpret_step(step);
+        step_callback(&step);
     }
     match step.step_result {
         StepResult::Return(mut result) => Ok(result.drain(0..)
@@ -24,6 +24,9 @@ pub struct Environment {
     is_test: bool,
     #[cfg(feature = "pkg_mgmt")]
     catalogs: Vec<Box<dyn ModuleCatalog>>,
+    /// The directories searched for modules, in the same order as their catalogs in `catalogs`
+    #[cfg(feature = "pkg_mgmt")]
+    include_paths: Vec<PathBuf>,
     #[cfg(feature = "pkg_mgmt")]
     pub(crate) fs_mod_formats: Arc<Vec<Box<dyn FsModuleFormat>>>,
     /// The store for modules cached locally after loading from a specific location, for example, via git.
@@ -80,6 +83,16 @@ impl Environment {
         self.catalogs.iter().map(|catalog| &**catalog as &dyn ModuleCatalog)
     }
 
+    /// Returns the file system directories searched for modules, in search priority order
+    ///
+    /// NOTE: This includes the `working_dir`, if there is one, as well as any include paths added with
+    /// [EnvBuilder::push_include_path] or by the `environment.metta` file.  Non-directory catalogs are not
+    /// included
+    #[cfg(feature = "pkg_mgmt")]
+    pub fn include_paths<'a>(&'a self) -> impl Iterator<Item=&'a Path> + 'a {
+        self.include_paths.iter().map(|path| path.as_path())
+    }
+
     /// Returns the [FsModuleFormat]s from the Environment, in priority order
     #[cfg(feature = "pkg_mgmt")]
     pub fn fs_mod_formats<'a>(&'a self) -> impl Iterator<Item=&dyn FsModuleFormat> + 'a {
@@ -97,6 +110,8 @@ impl Environment {
             #[cfg(feature = "pkg_mgmt")]
             catalogs: vec![],
             #[cfg(feature = "pkg_mgmt")]
+            include_paths: vec![],
+            #[cfg(feature = "pkg_mgmt")]
             fs_mod_formats: Arc::new(vec![]),
             #[cfg(feature = "pkg_mgmt")]
             specified_mods: None,
@@ -254,7 +269,7 @@ impl EnvBuilder {
     /// Returns a newly created Environment from the builder configuration
     ///
     /// NOTE: Creating owned Environments is usually not necessary.  It is usually sufficient to use the [Environment::common_env] method.
-    pub(crate) fn build(self) -> Environment {
+    pub fn build(self) -> Environment {
         let mut env = self.env;
         #[cfg(feature = "pkg_mgmt")]
         let mut proto_catalogs = self.proto_catalogs;
@@ -330,6 +345,7 @@ impl EnvBuilder {
                 match proto {
                     ProtoCatalog::Path(path) => {
                         //Make a DirCatalog for the directory
+                        env.include_paths.push(path.clone());
                         env.catalogs.push(Box::new(DirCatalog::new(path, env.fs_mod_formats.clone())));
                     }
                     ProtoCatalog::Other(catalog) => {
@@ -402,7 +418,10 @@ fn interpret_environment_metta<P: AsRef<Path>>(env_metta_path: P, env: &mut Envi
         match expr.children().get(0) {
             Some(atom_0) if *atom_0 == sym!("#includePath") => {
                 #[cfg(feature = "pkg_mgmt")]
-                env.catalogs.push(include_path_from_cfg_atom(&expr, env)?);
+                {
+                    let catalog = include_path_from_cfg_atom(&expr, env)?;
+                    env.catalogs.push(catalog);
+                }
                 #[cfg(not(feature = "pkg_mgmt"))]
                 {
                     let _ = &env;
@@ -465,7 +484,7 @@ fn git_catalog_from_cfg_atom(atom: &ExpressionAtom, env: &Environment) -> Result
 }
 
 #[cfg(feature = "pkg_mgmt")]
-fn include_path_from_cfg_atom(atom: &ExpressionAtom, env: &Environment) -> Result<Box<dyn ModuleCatalog>, String> {
+fn include_path_from_cfg_atom(atom: &ExpressionAtom, env: &mut Environment) -> Result<Box<dyn ModuleCatalog>, String> {
 
     let mut atom_iter = atom.children().iter();
     let _ = atom_iter.next();
@@ -488,5 +507,6 @@ fn include_path_from_cfg_atom(atom: &ExpressionAtom, env: &Environment) -> Resul
         std::fs::create_dir_all(&path).map_err(|e| e.to_string())?;
     }
 
+    env.include_paths.push(path.clone());
     Ok(Box::new(DirCatalog::new(path, env.fs_mod_formats.clone())))
 }
@@ -87,7 +87,9 @@ pub use environment::{Environment, EnvBuilder};
 
 #[macro_use]
 pub mod stdlib;
-use super::interpreter::{interpret, interpret_init, interpret_step, InterpreterState};
+use super::interpreter::{interpret, interpret_traced, interpret_init, interpret_step, InterpreterState};
+#[cfg(not(feature = "old_interpreter"))]
+use super::interpreter::interpret_limited;
 
 #[cfg(not(feature = "old_interpreter"))]
 pub mod stdlib_minimal;
@@ -161,9 +163,20 @@ impl Metta {
     /// pass `None` for space to create a new [GroundingSpace]
     /// pass `None` for `env_builder` to use the common environment
     pub fn new_with_stdlib_loader(loader: Option<Box<dyn ModuleLoader>>, space: Option<DynSpace>, env_builder: Option<EnvBuilder>) -> Metta {
+        Self::new_with_environment(loader, space, None, environment_from_builder(env_builder))
+    }
+
+    /// Create and initialize a MeTTa runner that uses an existing [Environment]
+    ///
+    /// Unlike the other constructors, this allows several runners to share one non-common environment.
+    ///
+    /// NOTE: pass `None` for `loader` to alias `stdlib` to `corelib`, as in [Metta::new_with_stdlib_loader]
+    /// pass `None` for space to create a new [GroundingSpace]
+    /// pass `None` for tokenizer to create a new [Tokenizer] for the top module
+    pub fn new_with_environment(loader: Option<Box<dyn ModuleLoader>>, space: Option<DynSpace>, tokenizer: Option<Shared<Tokenizer>>, environment: Arc<Environment>) -> Metta {
 
         //Create the raw MeTTa runner
-        let metta = Metta::new_core(space, env_builder);
+        let metta = Metta::new_core_with_environment(space, tokenizer, environment);
 
         //Load the "corelib" module into the runner
         let corelib_mod_id = metta.load_module_direct(Box::new(CoreLibLoader), "corelib").expect("Failed to load corelib");
@@ -206,17 +219,22 @@ impl Metta {
     /// NOTE: If `env_builder` is `None`, the common environment will be used
     /// NOTE: This function does not load any modules, nor run the [Environment]'s 'init.metta'
     pub fn new_core(space: Option<DynSpace>, env_builder: Option<EnvBuilder>) -> Self {
+        Self::new_core_with_environment(space, None, environment_from_builder(env_builder))
+    }
+
+    /// Returns a new core MeTTa interpreter that uses an existing [Environment], without any loaded
+    /// corelib, stdlib, or initialization
+    ///
+    /// NOTE: If `space` is `None`, a [GroundingSpace] will be created
+    /// NOTE: If `tokenizer` is `None`, a new [Tokenizer] will be created for the top module
+    pub fn new_core_with_environment(space: Option<DynSpace>, tokenizer: Option<Shared<Tokenizer>>, environment: Arc<Environment>) -> Self {
         let space = match space {
             Some(space) => space,
             None => DynSpace::new(GroundingSpace::new())
         };
         let settings = Shared::new(HashMap::new());
-        let environment = match env_builder {
-            Some(env_builder) => Arc::new(env_builder.build()),
-            None => Environment::common_env_arc()
-        };
         let top_mod_resource_dir = environment.working_dir().map(|path| path.into());
-        let top_mod_tokenizer = Shared::new(Tokenizer::new());
+        let top_mod_tokenizer = tokenizer.unwrap_or_else(|| Shared::new(Tokenizer::new()));
         let contents = MettaContents{
             modules: Mutex::new(vec![]),
             module_names: Mutex::new(ModNameNode::top()),
@@ -440,6 +458,40 @@ impl Metta {
     }
 
     pub fn evaluate_atom(&self, atom: Atom) -> Result<Vec<Atom>, String> {
+        match self.prepare_evaluation(atom) {
+            Ok(atom) => interpret(self.space(), &atom),
+            Err(error) => Ok(vec![error]),
+        }
+    }
+
+    /// Evaluates `atom` like [Metta::evaluate_atom] but stops the evaluation
+    /// as soon as `max_results` results are found. The remaining alternatives
+    /// of the nondeterministic evaluation are abandoned. Not available with
+    /// the `old_interpreter` feature because the old interpreter calculates
+    /// all results before returning any of them.
+    #[cfg(not(feature = "old_interpreter"))]
+    pub fn evaluate_atom_limited(&self, atom: Atom, max_results: usize) -> Result<Vec<Atom>, String> {
+        match self.prepare_evaluation(atom) {
+            Ok(atom) => interpret_limited(self.space(), &atom, max_results),
+            Err(error) => Ok(vec![error].into_iter().take(max_results).collect()),
+        }
+    }
+
+    /// Evaluates `atom` like [Metta::evaluate_atom] and calls `step_callback`
+    /// with the interpreter state after each step of the evaluation. The
+    /// callback is not called when the atom is rejected by the type checker.
+    pub fn evaluate_atom_traced<F>(&self, atom: Atom, step_callback: F) -> Result<Vec<Atom>, String>
+        where F: FnMut(&InterpreterState<'static, DynSpace>)
+    {
+        match self.prepare_evaluation(atom) {
+            Ok(atom) => interpret_traced(self.space().clone(), &atom, step_callback),
+            Err(error) => Ok(vec![error]),
+        }
+    }
+
+    /// Returns the atom to pass to the interpreter, or the error atom when
+    /// the atom has an incorrect type.
+    fn prepare_evaluation(&self, atom: Atom) -> Result<Atom, Atom> {
         #[cfg(not(feature = "old_interpreter"))]
         let atom = if is_bare_minimal_interpreter(self) {
             atom
@@ -447,9 +499,9 @@ impl Metta {
             wrap_atom_by_metta_interpreter(self.0.top_mod_space.clone(), atom)
         };
         if self.type_check_is_enabled() && !validate_atom(self.0.top_mod_space.borrow().as_space(), &atom) {
-            Ok(vec![Atom::expr([ERROR_SYMBOL, atom, BAD_TYPE_SYMBOL])])
+            Err(Atom::expr([ERROR_SYMBOL, atom, BAD_TYPE_SYMBOL]))
         } else {
-            interpret(self.space(), &atom)
+            Ok(atom)
         }
     }
 
@@ -1087,6 +1139,14 @@ impl<'input> RunContext<'_, '_, 'input> {
 
 }
 
+/// Builds the environment for a runner, falling back to the common environment if `env_builder` is `None`
+fn environment_from_builder(env_builder: Option<EnvBuilder>) -> Arc<Environment> {
+    match env_builder {
+        Some(env_builder) => Arc::new(env_builder.build()),
+        None => Environment::common_env_arc()
+    }
+}
+
 #[cfg(not(feature = "old_interpreter"))]
 fn is_bare_minimal_interpreter(metta: &Metta) -> bool {
     metta.get_setting_string("interpreter") == Some("bare-minimal".into())
@@ -1241,6 +1301,18 @@ mod tests {
         assert_eq!(result, Ok(vec![vec![expr!("Error" ("foo" "b") "BadType")]]));
     }
 
+    #[test]
+    fn metta_evaluate_atom_traced() {
+        let metta = Metta::new_core(None, Some(EnvBuilder::test_env()));
+        metta.run(SExprParser::new("(= (foo) (bar)) (= (bar) ok)")).unwrap();
+
+        let mut steps = 0;
+        let result = metta.evaluate_atom_traced(Atom::expr([sym!("foo")]), |_| steps += 1);
+        assert_eq!(result, Ok(vec![sym!("ok")]));
+        assert!(steps > 0);
+        assert_eq!(metta.evaluate_atom(Atom::expr([sym!("foo")])), result);
+    }
+
     #[derive(Clone, Debug)]
     struct ErrorOp{}
 
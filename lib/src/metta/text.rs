@@ -1,10 +1,12 @@
 //! MeTTa parser implementation.
 
 use crate::*;
+use crate::metta::COMMENT_SYMBOL;
 
 use core::ops::Range;
 use std::str::CharIndices;
 use std::iter::Peekable;
+use std::io::BufRead;
 use regex::Regex;
 use std::rc::Rc;
 
@@ -17,14 +19,18 @@ pub struct Tokenizer {
 struct TokenDescr {
     regex: Regex,
     constr: Rc<AtomConstr>,
+    priority: i32,
 }
 
 impl std::fmt::Debug for TokenDescr {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "TokenDescr{{ regex: {:?}, constr: {:?} }}", self.regex, Rc::as_ptr(&self.constr))
+        write!(f, "TokenDescr{{ regex: {:?}, constr: {:?}, priority: {} }}", self.regex, Rc::as_ptr(&self.constr), self.priority)
     }
 }
 
+/// The priority given to tokens registered without an explicit priority
+pub const DEFAULT_TOKEN_PRIORITY: i32 = 0;
+
 type AtomConstr = dyn Fn(&str) -> Result<Atom, String>;
 
 impl Tokenizer {
@@ -46,6 +52,15 @@ impl Tokenizer {
         self.register_token(regex, constr)
     }
 
+    /// Registers a token with an explicit priority
+    ///
+    /// When several registered regexes match the same token, the one with the highest priority wins.
+    /// Among tokens with equal priority, the most recently registered one wins.  Tokens registered
+    /// with [Self::register_token] have [DEFAULT_TOKEN_PRIORITY]
+    pub fn register_token_with_priority<C: 'static + Fn(&str) -> Atom>(&mut self, regex: Regex, priority: i32, constr: C) {
+        self.register_token_with_func_ptr_and_priority(regex, priority, Rc::new(move |the_str| Ok(constr(the_str))))
+    }
+
     /// Moves all tokenizer entries from `from` into `self`, leaving `from` empty
     ///
     /// NOTE: Tokens are tried in reverse order, so `move_front` actually adds entries that will be tried
@@ -61,20 +76,51 @@ impl Tokenizer {
     /// **first** in the priority order
     pub fn move_back(&mut self, from: &mut Tokenizer) {
         self.tokens.append(&mut from.tokens);
+        //Stable sort, so entries with equal priority keep their relative order
+        self.tokens.sort_by_key(|descr| descr.priority);
+    }
+
+    /// Copies all tokenizer entries from `other` into `self`, `other` is not changed
+    ///
+    /// The copied entries share atom constructors with `other`. Among tokens with equal priority
+    /// the entries of `other` are tried before the entries of `self`, as if they were registered
+    /// in `self` after all its own entries
+    pub fn merge(&mut self, other: &Tokenizer) {
+        self.move_back(&mut other.clone());
     }
 
     pub fn find_token(&self, token: &str) -> Option<&AtomConstr> {
+        self.find_descr(token).map(|descr| &*(descr.constr))
+    }
+
+    /// Returns the regex of the token which is used to parse the `token`
+    /// string, or None if no registered regex matches the whole string. When
+    /// several regexes match the string the one with the highest priority is
+    /// returned, among regexes with equal priority the most recently
+    /// registered is returned.
+    pub fn find_token_regex(&self, token: &str) -> Option<&Regex> {
+        self.find_descr(token).map(|descr| &descr.regex)
+    }
+
+    fn find_descr(&self, token: &str) -> Option<&TokenDescr> {
         self.tokens.iter().rfind(|descr| {
             match descr.regex.find_at(token, 0) {
                 Some(m) => m.start() == 0 && m.end() == token.len(),
                 None => false,
             }
-        }).map(|descr| &*(descr.constr))
+        })
     }
 
     /// Registers the regex-function pair, for a function that's already wrapped in an RC pointer
     pub(crate) fn register_token_with_func_ptr(&mut self, regex: Regex, constr: Rc<AtomConstr>) {
-        self.tokens.push(TokenDescr{ regex, constr: constr })
+        self.register_token_with_func_ptr_and_priority(regex, DEFAULT_TOKEN_PRIORITY, constr)
+    }
+
+    /// Inserts the token after all tokens with a lower or equal priority, so that `tokens` stays
+    /// sorted and the entry found by a reverse search is the highest priority match
+    fn register_token_with_func_ptr_and_priority(&mut self, regex: Regex, priority: i32, constr: Rc<AtomConstr>) {
+        let idx = self.tokens.partition_point(|descr| descr.priority <= priority);
+        self.tokens.insert(idx, TokenDescr{ regex, constr, priority })
     }
 
     /// Returns the constructor function associated with an exact regex string, or None if the Tokenizer
@@ -173,10 +219,21 @@ impl SyntaxNode {
 
     /// Transforms a root SyntaxNode into an [Atom]
     pub fn as_atom(&self, tokenizer: &Tokenizer) -> Result<Option<Atom>, String> {
+        self.as_atom_checked(tokenizer).map_err(|err| err.message)
+    }
+
+    /// Transforms a root SyntaxNode into an [Atom], returning a [ParseError] describing the location
+    /// of any error
+    pub fn as_atom_checked(&self, tokenizer: &Tokenizer) -> Result<Option<Atom>, ParseError> {
 
         //If we have an incomplete node, it's an error
         if !self.is_complete {
-            return Err(self.message.clone().unwrap())
+            let err_node = self.error_node();
+            return Err(ParseError{
+                offset: err_node.src_range.start,
+                message: self.message.clone().unwrap(),
+                node: Some(Box::new(err_node.clone())),
+            })
         }
 
         match self.node_type {
@@ -195,7 +252,11 @@ impl SyntaxNode {
                 let constr = tokenizer.find_token(token_text);
                 if let Some(constr) = constr {
                     let new_atom = constr(token_text)
-                        .map_err(|e| format!("byte range = ({:?}) | {e}", self.src_range))?;
+                        .map_err(|e| ParseError{
+                            offset: self.src_range.start,
+                            message: format!("byte range = ({:?}) | {e}", self.src_range),
+                            node: Some(Box::new(self.clone())),
+                        })?;
                     Ok(Some(new_atom))
                 } else {
                     let new_atom = Atom::sym(token_text);
@@ -205,7 +266,7 @@ impl SyntaxNode {
             SyntaxNodeType::ExpressionGroup => {
                 let mut err_encountered = Ok(());
                 let expr_children: Vec<Atom> = self.sub_nodes.iter().filter_map(|node| {
-                    match node.as_atom(tokenizer) {
+                    match node.as_atom_checked(tokenizer) {
                         Err(err) => {
                             err_encountered = Err(err);
                             None
@@ -226,6 +287,23 @@ impl SyntaxNode {
         }
     }
 
+    /// Returns the innermost incomplete node, which is where an error was detected.  [SyntaxNodeType::LeftoverText]
+    /// only follows an error, so the node containing it is returned instead
+    fn error_node(&self) -> &SyntaxNode {
+        match self.sub_nodes.last() {
+            Some(sub_node) if !sub_node.is_complete && !matches!(sub_node.node_type, SyntaxNodeType::LeftoverText) => sub_node.error_node(),
+            _ => self,
+        }
+    }
+
+    /// Moves the `src_range` of the node and all of its sub-nodes forward by `delta` bytes
+    fn shift_src_range(&mut self, delta: usize) {
+        for sub_node in self.sub_nodes.iter_mut() {
+            sub_node.shift_src_range(delta);
+        }
+        self.src_range = self.src_range.start + delta..self.src_range.end + delta;
+    }
+
     /// Visits all the nodes in a parsed syntax tree in a depth-first order
     pub fn visit_depth_first<C>(&self, mut callback: C)
         where C: FnMut(&SyntaxNode)
@@ -243,6 +321,40 @@ impl SyntaxNode {
     }
 }
 
+/// An error encountered while parsing MeTTa source text
+#[derive(Clone, Debug)]
+pub struct ParseError {
+    /// The byte offset into the source text where the error was detected
+    pub offset: usize,
+    /// A description of the error
+    pub message: String,
+    /// The syntax node in which the error was detected, if there is one
+    pub node: Option<Box<SyntaxNode>>,
+}
+
+impl ParseError {
+    /// Moves the error's location forward by `delta` bytes
+    fn shift_offset(mut self, delta: usize) -> Self {
+        self.offset += delta;
+        if let Some(node) = &mut self.node {
+            node.shift_src_range(delta);
+        }
+        self
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<ParseError> for String {
+    fn from(err: ParseError) -> Self {
+        err.message
+    }
+}
+
 /// Implemented on a type that yields atoms to be interpreted as MeTTa code.  Typically
 /// by parsing source text
 pub trait Parser {
@@ -261,6 +373,18 @@ impl Parser for &mut (dyn Parser + '_) {
     }
 }
 
+/// Controls what [SExprParser::parse] does with comments in the source text
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommentMode {
+    /// Comments are discarded
+    #[default]
+    Skip,
+    /// Each top-level comment is returned as a `(Comment <text>)` atom, where `<text>` is a symbol
+    /// containing the full comment line including the leading `;`.  Comments inside expressions are
+    /// still discarded
+    Keep,
+}
+
 /// Provides a parser for MeTTa code written in S-Expression Syntax
 ///
 /// NOTE: The SExprParser type is short-lived, and can be created cheaply to evaluate a specific block
@@ -269,18 +393,140 @@ impl Parser for &mut (dyn Parser + '_) {
 pub struct SExprParser<'a> {
     text: &'a str,
     it: Peekable<CharIndices<'a>>,
+    comment_mode: CommentMode,
+    max_depth: Option<usize>,
+    depth: usize,
+    variable_sigil: char,
+    extra_separators: Vec<char>,
 }
 
 impl<'a> SExprParser<'a> {
     pub fn new(text: &'a str) -> Self {
-        Self{ text, it: text.char_indices().peekable() }
+        Self{ text, it: text.char_indices().peekable(), comment_mode: CommentMode::Skip, max_depth: None, depth: 0, variable_sigil: '$', extra_separators: vec![] }
+    }
+
+    /// Returns the parser which recognizes variables by the `sigil` char
+    /// instead of `$`. The sigil is detected before tokenization, thus words
+    /// starting with the sigil are always parsed as variables, even when they
+    /// match a token registered in the [Tokenizer]. Words starting with `$`
+    /// are passed to the [Tokenizer] as any other word when another sigil is
+    /// set.
+    ///
+    /// Panics if `sigil` is not allowed, see [Self::is_valid_variable_sigil].
+    pub fn with_variable_sigil(mut self, sigil: char) -> Self {
+        self.set_variable_sigil(sigil);
+        self
+    }
+
+    /// Sets the char which starts variables, see [Self::with_variable_sigil].
+    pub fn set_variable_sigil(&mut self, sigil: char) {
+        assert!(Self::is_valid_variable_sigil(sigil), "Char {:?} cannot be used as a variable sigil", sigil);
+        self.variable_sigil = sigil;
+    }
+
+    /// Returns true if `sigil` can be used to start variables. Whitespace,
+    /// parentheses, `;` and `"` are not allowed because they have special
+    /// meaning for the parser.
+    pub fn is_valid_variable_sigil(sigil: char) -> bool {
+        !(sigil.is_whitespace() || matches!(sigil, '(' | ')' | ';' | '"'))
+    }
+
+    /// Returns the parser which treats `separators` as whitespace between
+    /// atoms, in addition to the standard whitespace. Separators never become
+    /// a part of tokens, thus with `,` as a separator `(a, b, c)` is parsed
+    /// as `(a b c)`. Chars which have special meaning for the parser take
+    /// precedence: `;` always starts a comment, parentheses and `"` keep
+    /// their meaning, thus they are ignored in `separators`. A separator
+    /// equal to the variable sigil is treated as a separator.
+    pub fn with_extra_separators(mut self, separators: &[char]) -> Self {
+        self.set_extra_separators(separators);
+        self
+    }
+
+    /// Sets the additional separator chars, see [Self::with_extra_separators].
+    pub fn set_extra_separators(&mut self, separators: &[char]) {
+        self.extra_separators = filter_separators(separators);
+    }
+
+    fn is_separator(&self, c: char) -> bool {
+        c.is_whitespace() || self.extra_separators.contains(&c)
+    }
+
+    /// Returns the parser which fails with an error when expressions are
+    /// nested deeper than `max_depth` levels, instead of exhausting the stack
+    /// on a deeply nested input. The rest of the text is skipped after the
+    /// error. The depth is not limited by default.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Sets the maximal nesting depth of expressions, see [Self::with_max_depth].
+    /// `None` removes the limit.
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    /// Returns the parser with the specified [CommentMode]
+    pub fn with_comment_handling(mut self, mode: CommentMode) -> Self {
+        self.comment_mode = mode;
+        self
+    }
+
+    /// Sets the [CommentMode] used by subsequent calls to [Self::parse]
+    pub fn set_comment_mode(&mut self, mode: CommentMode) {
+        self.comment_mode = mode;
+    }
+
+    /// Returns a parser that reads the source text incrementally from `reader`
+    ///
+    /// Use this instead of [SExprParser::new] for sources too large to hold in memory at once.
+    /// See [ReaderSExprParser]
+    pub fn from_reader<R: BufRead>(reader: R) -> ReaderSExprParser<R> {
+        ReaderSExprParser::new(reader)
     }
 
     pub fn parse(&mut self, tokenizer: &Tokenizer) -> Result<Option<Atom>, String> {
+        self.parse_checked(tokenizer).map_err(|err| err.message)
+    }
+
+    /// Parses the next atom like [Self::parse], but returns a [ParseError] with the location of any error
+    pub fn parse_checked(&mut self, tokenizer: &Tokenizer) -> Result<Option<Atom>, ParseError> {
+        loop {
+            match self.parse_to_syntax_tree() {
+                Some(node) => {
+                    let atom = node_to_atom(self.text, &node, self.comment_mode, tokenizer)?;
+                    if let Some(atom) = atom {
+                        return Ok(Some(atom))
+                    }
+                },
+                None => {
+                    return Ok(None);
+                },
+            }
+        }
+    }
+
+    /// Parses the next atom like [Self::parse_checked], but doesn't give up
+    /// the rest of the text after a syntax error. The parser skips to the next
+    /// plausible atom boundary and the following call continues parsing from
+    /// there, thus all errors of the text can be collected in one pass.
+    ///
+    /// The boundary is found heuristically: the parser skips the text of the
+    /// failed atom counting parentheses, and stops at the first separator
+    /// outside of any parentheses, or at the `(` which starts a line. String
+    /// literals are not recognized while skipping. Errors of the [Tokenizer]
+    /// don't need recovery, because the failed atom is complete.
+    pub fn parse_recovering(&mut self, tokenizer: &Tokenizer) -> Result<Option<Atom>, ParseError> {
         loop {
+            let start = self.cur_idx();
             match self.parse_to_syntax_tree() {
                 Some(node) => {
-                    if let Some(atom) = node.as_atom(tokenizer)? {
+                    let atom = node_to_atom(self.text, &node, self.comment_mode, tokenizer);
+                    if atom.is_err() && !node.is_complete {
+                        self.skip_to_recovery_point(start);
+                    }
+                    if let Some(atom) = atom? {
                         return Ok(Some(atom))
                     }
                 },
@@ -291,6 +537,26 @@ impl<'a> SExprParser<'a> {
         }
     }
 
+    /// Moves the parser to the first atom boundary after the atom which
+    /// starts at `start`, see [Self::parse_recovering]
+    fn skip_to_recovery_point(&mut self, start: usize) {
+        self.it = self.text.char_indices().peekable();
+        while self.it.next_if(|&(idx, _)| idx <= start).is_some() {}
+        let mut depth: usize = if self.text[start..].starts_with('(') { 1 } else { 0 };
+        let mut prev = None;
+        while let Some(&(_idx, c)) = self.it.peek() {
+            match c {
+                '(' if prev == Some('\n') => break,
+                _ if depth == 0 && self.is_separator(c) => break,
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                _ => {},
+            }
+            prev = Some(c);
+            self.it.next();
+        }
+    }
+
     pub fn parse_to_syntax_tree(&mut self) -> Option<SyntaxNode> {
         if let Some((idx, c)) = self.it.peek().cloned() {
             match c {
@@ -298,12 +564,12 @@ impl<'a> SExprParser<'a> {
                     let comment_node = self.parse_comment().unwrap();
                     return Some(comment_node);
                 },
-                _ if c.is_whitespace() => {
-                    let whispace_node = SyntaxNode::new(SyntaxNodeType::Whitespace, idx..idx+1, vec![]);
+                _ if self.is_separator(c) => {
+                    let whispace_node = SyntaxNode::new(SyntaxNodeType::Whitespace, idx..idx+c.len_utf8(), vec![]);
                     self.it.next();
                     return Some(whispace_node);
                 },
-                '$' => {
+                _ if c == self.variable_sigil => {
                     let var_node = self.parse_variable();
                     return Some(var_node);
                 },
@@ -327,15 +593,30 @@ impl<'a> SExprParser<'a> {
         None
     }
 
+    /// Returns the source text of the parser, `src_range` of the parsed [SyntaxNode]s is relative to it
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns the byte offset into the source text, of the next char the parser will consume
+    ///
     ///WARNING: may be (often is) == to text.len(), and thus can't be used as an index to read a char
-    fn cur_idx(&mut self) -> usize {
-        if let Some((idx, _)) = self.it.peek() {
+    pub fn cur_idx(&self) -> usize {
+        if let Some((idx, _)) = self.it.clone().peek() {
             *idx
         } else {
             self.text.len()
         }
     }
 
+    /// Returns true if the rest of the source text contains nothing to
+    /// parse, i.e. only whitespace and comments which are skipped by the
+    /// current [CommentMode]. In that case the next call of [Self::parse]
+    /// returns `Ok(None)`.
+    pub fn at_end(&self) -> bool {
+        text_at_end(&self.text[self.cur_idx()..], self.comment_mode, &self.extra_separators)
+    }
+
     /// Parse to the next `\n` newline
     fn parse_comment(&mut self) -> Option<SyntaxNode> {
         if let Some((start_idx, _c)) = self.it.peek().cloned() {
@@ -360,6 +641,22 @@ impl<'a> SExprParser<'a> {
     }
 
     fn parse_expr(&mut self) -> SyntaxNode {
+        match self.max_depth {
+            Some(max_depth) if self.depth >= max_depth => {
+                let start_idx = self.cur_idx();
+                let leftover_node = self.parse_leftovers(format!("Expression nesting depth exceeds the limit of {max_depth}"));
+                SyntaxNode::new_error_group(start_idx..self.cur_idx(), vec![leftover_node])
+            },
+            _ => {
+                self.depth += 1;
+                let expr_node = self.parse_expr_internal();
+                self.depth -= 1;
+                expr_node
+            },
+        }
+    }
+
+    fn parse_expr_internal(&mut self) -> SyntaxNode {
         let start_idx = self.cur_idx();
         let mut child_nodes: Vec<SyntaxNode> = Vec::new();
 
@@ -373,8 +670,8 @@ impl<'a> SExprParser<'a> {
                     let comment_node = self.parse_comment().unwrap();
                     child_nodes.push(comment_node);
                 },
-                _ if c.is_whitespace() => {
-                    let whitespace_node = SyntaxNode::new(SyntaxNodeType::Whitespace, idx..idx+1, vec![]);
+                _ if self.is_separator(c) => {
+                    let whitespace_node = SyntaxNode::new(SyntaxNodeType::Whitespace, idx..idx+c.len_utf8(), vec![]);
                     child_nodes.push(whitespace_node);
                     self.it.next();
                 },
@@ -490,11 +787,11 @@ impl<'a> SExprParser<'a> {
         let mut token = String::new();
         let start_idx = self.cur_idx();
 
-        while let Some((_idx, c)) = self.it.peek() {
-            if c.is_whitespace() || *c == '(' || *c == ')' {
+        while let Some(&(_idx, c)) = self.it.peek() {
+            if self.is_separator(c) || c == '(' || c == ')' {
                 break;
             }
-            token.push(*c);
+            token.push(c);
             self.it.next();
         }
 
@@ -509,7 +806,7 @@ impl<'a> SExprParser<'a> {
 
         let mut token = String::new();
         while let Some((_idx, c)) = tmp_it.peek() {
-            if c.is_whitespace() || *c == '(' || *c == ')' {
+            if self.is_separator(*c) || *c == '(' || *c == ')' {
                 break;
             }
             if *c == '#' {
@@ -531,24 +828,289 @@ impl<'a> SExprParser<'a> {
 pub struct OwnedSExprParser {
     text: String,
     last_pos: usize,
+    comment_mode: CommentMode,
+    max_depth: Option<usize>,
+    variable_sigil: char,
+    extra_separators: Vec<char>,
 }
 
 impl OwnedSExprParser {
     pub fn new(text: String) -> Self {
-        Self{text, last_pos: 0}
+        Self{text, last_pos: 0, comment_mode: CommentMode::Skip, max_depth: None, variable_sigil: '$', extra_separators: vec![]}
+    }
+
+    /// Sets the [CommentMode] used by subsequent calls to [Parser::next_atom]
+    pub fn set_comment_mode(&mut self, mode: CommentMode) {
+        self.comment_mode = mode;
+    }
+
+    /// Sets the maximal nesting depth of expressions, see [SExprParser::with_max_depth]
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    /// Sets the char which starts variables, see [SExprParser::with_variable_sigil]
+    pub fn set_variable_sigil(&mut self, sigil: char) {
+        assert!(SExprParser::is_valid_variable_sigil(sigil), "Char {:?} cannot be used as a variable sigil", sigil);
+        self.variable_sigil = sigil;
+    }
+
+    /// Sets the additional separator chars, see [SExprParser::with_extra_separators]
+    pub fn set_extra_separators(&mut self, separators: &[char]) {
+        self.extra_separators = filter_separators(separators);
+    }
+
+    /// Returns the source text of the parser, see [SExprParser::text]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the byte offset into the source text, of the next char the parser will consume
+    pub fn cur_idx(&self) -> usize {
+        self.last_pos
+    }
+
+    /// Returns true if the rest of the source text contains nothing to parse, see [SExprParser::at_end]
+    pub fn at_end(&self) -> bool {
+        text_at_end(&self.text[self.last_pos.min(self.text.len())..], self.comment_mode, &self.extra_separators)
     }
 }
 
-impl Parser for OwnedSExprParser {
-    fn next_atom(&mut self, tokenizer: &Tokenizer) -> Result<Option<Atom>, String> {
+fn filter_separators(separators: &[char]) -> Vec<char> {
+    separators.iter().copied().filter(|c| !matches!(c, '(' | ')' | ';' | '"')).collect()
+}
+
+fn text_at_end(text: &str, comment_mode: CommentMode, separators: &[char]) -> bool {
+    text.lines().all(|line| {
+        let line = line.trim_start_matches(|c: char| c.is_whitespace() || separators.contains(&c));
+        line.is_empty() || (line.starts_with(';') && comment_mode == CommentMode::Skip)
+    })
+}
+
+impl OwnedSExprParser {
+    /// Parses the next atom, and returns a [ParseError] with the location of any error
+    pub fn parse_checked(&mut self, tokenizer: &Tokenizer) -> Result<Option<Atom>, ParseError> {
+        self.parse_rest(|parser| parser.parse_checked(tokenizer))
+    }
+
+    /// Parses the next atom, and continues after a syntax error, see [SExprParser::parse_recovering]
+    pub fn parse_recovering(&mut self, tokenizer: &Tokenizer) -> Result<Option<Atom>, ParseError> {
+        self.parse_rest(|parser| parser.parse_recovering(tokenizer))
+    }
+
+    /// Runs `parse` on the unparsed rest of the text and advances the position
+    fn parse_rest<F>(&mut self, parse: F) -> Result<Option<Atom>, ParseError>
+        where F: FnOnce(&mut SExprParser) -> Result<Option<Atom>, ParseError>
+    {
         if self.last_pos >= self.text.len() {
             return Ok(None);
         }
-        let slice = &self.text[self.last_pos..self.text.len()];
-        let mut parser = SExprParser::new(slice);
-        let result = parser.parse(tokenizer);
-        self.last_pos = self.last_pos + parser.cur_idx();
-        result
+        let start_pos = self.last_pos;
+        let slice = &self.text[start_pos..self.text.len()];
+        let mut parser = SExprParser::new(slice).with_comment_handling(self.comment_mode);
+        parser.set_max_depth(self.max_depth);
+        parser.set_variable_sigil(self.variable_sigil);
+        parser.set_extra_separators(&self.extra_separators);
+        let result = parse(&mut parser);
+        self.last_pos = start_pos + parser.cur_idx();
+        result.map_err(|err| err.shift_offset(start_pos))
+    }
+}
+
+impl Parser for OwnedSExprParser {
+    fn next_atom(&mut self, tokenizer: &Tokenizer) -> Result<Option<Atom>, String> {
+        self.parse_checked(tokenizer).map_err(|err| err.message)
+    }
+}
+
+/// Converts a top-level [SyntaxNode] parsed from `text` into an [Atom], according to `comment_mode`
+fn node_to_atom(text: &str, node: &SyntaxNode, comment_mode: CommentMode, tokenizer: &Tokenizer) -> Result<Option<Atom>, ParseError> {
+    match (node.node_type, comment_mode) {
+        (SyntaxNodeType::Comment, CommentMode::Keep) => {
+            let comment_text = &text[node.src_range.clone()];
+            Ok(Some(Atom::expr([COMMENT_SYMBOL, Atom::sym(comment_text)])))
+        },
+        _ => node.as_atom_checked(tokenizer),
+    }
+}
+
+/// Provides a parser for MeTTa code written in S-Expression Syntax, which reads its source text
+/// incrementally from a [BufRead] object
+///
+/// The source is read one line at a time, and only the text of the atom currently being parsed is
+/// held in memory, so the parser can process files much larger than the available memory.  Atoms
+/// may span any number of lines.  Byte offsets of the errors are relative to the start of the
+/// source.
+pub struct ReaderSExprParser<R: BufRead> {
+    reader: R,
+    buf: String,
+    /// The byte offset into the buffer of the text which is not parsed yet
+    pos: usize,
+    /// The byte offset into the source of the start of the buffer
+    offset: usize,
+    scan: AtomScan,
+    eof: bool,
+    comment_mode: CommentMode,
+    max_depth: Option<usize>,
+    variable_sigil: char,
+    extra_separators: Vec<char>,
+}
+
+/// State of the search for the end of the next atom in the buffer of [ReaderSExprParser], kept
+/// between the lines read from the reader so the text is scanned only once
+#[derive(Default)]
+struct AtomScan {
+    idx: usize,
+    depth: usize,
+    word: bool,
+    string: bool,
+    escape: bool,
+    comment: bool,
+}
+
+impl<R: BufRead> ReaderSExprParser<R> {
+    pub fn new(reader: R) -> Self {
+        Self{ reader, buf: String::new(), pos: 0, offset: 0, scan: AtomScan::default(), eof: false,
+            comment_mode: CommentMode::Skip, max_depth: None, variable_sigil: '$', extra_separators: vec![] }
+    }
+
+    /// Sets the [CommentMode] used by subsequent calls to [Self::next_atom]
+    pub fn set_comment_mode(&mut self, mode: CommentMode) {
+        self.comment_mode = mode;
+    }
+
+    /// Sets the maximal nesting depth of expressions, see [SExprParser::with_max_depth]
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    /// Sets the char which starts variables, see [SExprParser::with_variable_sigil]
+    pub fn set_variable_sigil(&mut self, sigil: char) {
+        assert!(SExprParser::is_valid_variable_sigil(sigil), "Char {:?} cannot be used as a variable sigil", sigil);
+        self.variable_sigil = sigil;
+    }
+
+    /// Sets the additional separator chars, see [SExprParser::with_extra_separators]
+    pub fn set_extra_separators(&mut self, separators: &[char]) {
+        self.extra_separators = filter_separators(separators);
+    }
+
+    /// Parses the next atom, and returns a [ParseError] with the location of any error
+    pub fn next_atom(&mut self, tokenizer: &Tokenizer) -> Result<Option<Atom>, ParseError> {
+        loop {
+            if !self.scan_atom() && !self.eof {
+                self.read_line()?;
+                continue;
+            }
+
+            let text = &self.buf[self.pos..];
+            let mut parser = SExprParser::new(text).with_comment_handling(self.comment_mode);
+            parser.set_max_depth(self.max_depth);
+            parser.set_variable_sigil(self.variable_sigil);
+            parser.set_extra_separators(&self.extra_separators);
+            let node = match parser.parse_to_syntax_tree() {
+                Some(node) => node,
+                None => return Ok(None),
+            };
+            let start = self.offset + self.pos;
+            let result = node_to_atom(text, &node, self.comment_mode, tokenizer)
+                .map_err(|err| err.shift_offset(start));
+            self.pos += parser.cur_idx();
+            self.scan = AtomScan{ idx: self.pos, ..AtomScan::default() };
+            if let Some(atom) = result? {
+                return Ok(Some(atom))
+            }
+        }
+    }
+
+    /// Scans the buffer from the place the previous scan stopped, and returns `true` when the
+    /// buffer contains the whole atom which starts at [Self::pos].  The scan doesn't validate the
+    /// atom, it only finds the place where [SExprParser] stops parsing it
+    fn scan_atom(&mut self) -> bool {
+        let scan = &mut self.scan;
+        let is_separator = |c: char| c.is_whitespace() || self.extra_separators.contains(&c);
+        for c in self.buf[scan.idx..].chars() {
+            scan.idx += c.len_utf8();
+            if scan.comment {
+                if c == '\n' {
+                    scan.comment = false;
+                    if scan.depth == 0 {
+                        return true;
+                    }
+                }
+                continue;
+            }
+            if scan.string {
+                if scan.escape {
+                    scan.escape = false;
+                } else if c == '\\' {
+                    scan.escape = true;
+                } else if c == '"' {
+                    scan.string = false;
+                    if scan.depth == 0 {
+                        return true;
+                    }
+                }
+                continue;
+            }
+            if scan.word {
+                if !(is_separator(c) || c == '(' || c == ')') {
+                    continue;
+                }
+                scan.word = false;
+                if scan.depth == 0 {
+                    return true;
+                }
+            }
+            match c {
+                ';' => scan.comment = true,
+                '"' => scan.string = true,
+                '(' => {
+                    scan.depth += 1;
+                    // The parser skips the rest of the text after the depth error
+                    if self.max_depth.is_some_and(|max_depth| scan.depth > max_depth) {
+                        return true;
+                    }
+                },
+                ')' => {
+                    // An unexpected bracket at the top level is an error as well
+                    if scan.depth <= 1 {
+                        return true;
+                    }
+                    scan.depth -= 1;
+                },
+                _ if is_separator(c) => {
+                    if scan.depth == 0 {
+                        return true;
+                    }
+                },
+                _ => scan.word = true,
+            }
+        }
+        false
+    }
+
+    /// Appends the next line from the reader to the buffer, dropping the text which is parsed
+    /// already
+    fn read_line(&mut self) -> Result<(), ParseError> {
+        if self.pos > 0 {
+            self.buf.drain(..self.pos);
+            self.offset += self.pos;
+            self.scan.idx -= self.pos;
+            self.pos = 0;
+        }
+        let bytes_read = self.reader.read_line(&mut self.buf)
+            .map_err(|err| ParseError{ offset: self.offset + self.buf.len(), message: err.to_string(), node: None })?;
+        if bytes_read == 0 {
+            self.eof = true;
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Parser for ReaderSExprParser<R> {
+    fn next_atom(&mut self, tokenizer: &Tokenizer) -> Result<Option<Atom>, String> {
+        ReaderSExprParser::next_atom(self, tokenizer).map_err(|err| err.message)
     }
 }
 
@@ -610,6 +1172,69 @@ mod tests {
         assert_eq!(Ok(None), parser.parse(&tokenizer));
     }
 
+    #[test]
+    fn test_token_priority() {
+        let mut tokenizer = Tokenizer::new();
+        tokenizer.register_token_with_priority(Regex::new(r"\d+").unwrap(), 1,
+            |token| Atom::value(token.parse::<i32>().unwrap()));
+        tokenizer.register_token(Regex::new(r"\w+").unwrap(), |_| Atom::value("word"));
+
+        let mut parser = SExprParser::new("42 x");
+        assert_eq!(Ok(Some(Atom::value(42))), parser.parse(&tokenizer));
+        assert_eq!(Ok(Some(Atom::value("word"))), parser.parse(&tokenizer));
+
+        tokenizer.register_token_with_priority(Regex::new(r"\w+").unwrap(), 1, |_| Atom::value("word1"));
+        let mut parser = SExprParser::new("42");
+        assert_eq!(Ok(Some(Atom::value("word1"))), parser.parse(&tokenizer));
+    }
+
+    #[test]
+    fn test_parser_at_end() {
+        let tokenizer = Tokenizer::new();
+        let mut parser = SExprParser::new("  (a b) ; comment\n  ");
+        assert!(!parser.at_end());
+        assert_eq!(parser.parse(&tokenizer), Ok(Some(expr!("a" "b"))));
+        assert!(parser.at_end());
+        assert_eq!(parser.parse(&tokenizer), Ok(None));
+
+        let mut parser = SExprParser::new("a ; comment").with_comment_handling(CommentMode::Keep);
+        assert_eq!(parser.parse(&tokenizer), Ok(Some(sym!("a"))));
+        assert!(!parser.at_end());
+
+        let mut parser = OwnedSExprParser::new("a\n".into());
+        assert!(!parser.at_end());
+        assert_eq!(parser.next_atom(&tokenizer), Ok(Some(sym!("a"))));
+        assert!(parser.at_end());
+        assert!(SExprParser::new("").at_end());
+    }
+
+    #[test]
+    fn test_tokenizer_merge() {
+        let mut tokenizer = Tokenizer::new();
+        tokenizer.register_token(Regex::new(r"\d+").unwrap(), |_| Atom::sym("number"));
+        tokenizer.register_token(Regex::new(r"a").unwrap(), |_| Atom::sym("first"));
+        let mut other = Tokenizer::new();
+        other.register_token(Regex::new(r"[a-z]+").unwrap(), |_| Atom::sym("word"));
+        other.register_token_with_priority(Regex::new(r"b").unwrap(), -1, |_| Atom::sym("low"));
+
+        tokenizer.merge(&other);
+
+        let mut parser = SExprParser::new("(42 a b c)");
+        assert_eq!(parser.parse(&tokenizer), Ok(Some(expr!("number" "word" "word" "word"))));
+        assert!(other.find_token("42").is_none());
+    }
+
+    #[test]
+    fn test_find_token_regex() {
+        let mut tokenizer = Tokenizer::new();
+        tokenizer.register_token_with_priority(Regex::new(r"\d+").unwrap(), 1, |_| Atom::value(0));
+        tokenizer.register_token(Regex::new(r"\w+").unwrap(), |_| Atom::value("word"));
+
+        assert_eq!(tokenizer.find_token_regex("123").map(Regex::as_str), Some(r"\d+"));
+        assert_eq!(tokenizer.find_token_regex("x1").map(Regex::as_str), Some(r"\w+"));
+        assert!(tokenizer.find_token_regex("1+").is_none());
+    }
+
     #[test]
     fn test_text_gnd() {
         let mut tokenizer = Tokenizer::new();
@@ -644,6 +1269,36 @@ mod tests {
         assert_eq!(Some((1, ')')), parser.it.next());
     }
 
+    #[test]
+    fn test_cur_idx() {
+        let text = "(a b) c";
+        let tokenizer = Tokenizer::new();
+        let mut parser = SExprParser::new(text);
+        assert_eq!(0, parser.cur_idx());
+        assert_eq!(Ok(Some(expr!("a" "b"))), parser.parse(&tokenizer));
+        assert_eq!(5, parser.cur_idx());
+        assert_eq!(Ok(Some(expr!("c"))), parser.parse(&tokenizer));
+        assert_eq!(text.len(), parser.cur_idx());
+
+        let mut parser = OwnedSExprParser::new(text.to_string());
+        assert_eq!(Ok(Some(expr!("a" "b"))), parser.next_atom(&tokenizer));
+        assert_eq!(5, parser.cur_idx());
+    }
+
+    #[test]
+    fn test_syntax_node_src_text() {
+        let text = "(a \u{a0}\"b\") ; c";
+        let mut parser = SExprParser::new(text);
+        let node = parser.parse_to_syntax_tree().unwrap();
+        assert_eq!("(a \u{a0}\"b\")", &text[node.src_range.clone()]);
+        let leaves: Vec<&str> = node.sub_nodes.iter().map(|node| &text[node.src_range.clone()]).collect();
+        assert_eq!(vec!["(", "a", " ", "\u{a0}", "\"b\"", ")"], leaves);
+
+        let _ = parser.parse_to_syntax_tree().unwrap();
+        let node = parser.parse_to_syntax_tree().unwrap();
+        assert_eq!("; c", &text[node.src_range]);
+    }
+
     #[test]
     fn test_next_string_errors() {
         let mut parser = SExprParser::new("a");
@@ -664,6 +1319,55 @@ mod tests {
         assert_eq!(Err(String::from("Unexpected right bracket")), parser.parse(&Tokenizer::new()));
     }
 
+    #[test]
+    fn test_parser_max_depth() {
+        let tokenizer = Tokenizer::new();
+        let mut parser = SExprParser::new("(a (b (c))) (a (b (c (d)))) (e)").with_max_depth(3);
+        assert_eq!(parser.parse(&tokenizer), Ok(Some(expr!("a" ("b" ("c"))))));
+        assert_eq!(parser.parse(&tokenizer), Err("Expression nesting depth exceeds the limit of 3".into()));
+        assert_eq!(parser.parse(&tokenizer), Ok(None));
+
+        let text = "(".repeat(100000) + &")".repeat(100000);
+        let mut parser = SExprParser::new(&text).with_max_depth(100);
+        assert_eq!(parser.parse(&tokenizer), Err("Expression nesting depth exceeds the limit of 100".into()));
+
+        let mut parser = OwnedSExprParser::new("((a))".into());
+        parser.set_max_depth(Some(1));
+        assert_eq!(parser.next_atom(&tokenizer), Err("Expression nesting depth exceeds the limit of 1".into()));
+    }
+
+    #[test]
+    fn test_parser_variable_sigil() {
+        let mut tokenizer = Tokenizer::new();
+        tokenizer.register_token(Regex::new(r"\?y").unwrap(), |_| Atom::sym("token"));
+        let mut parser = SExprParser::new("(foo ?x $y ?y)").with_variable_sigil('?');
+        assert_eq!(parser.parse(&tokenizer), Ok(Some(expr!("foo" x "$y" y))));
+
+        let mut parser = OwnedSExprParser::new("(foo ?x)".into());
+        parser.set_variable_sigil('?');
+        assert_eq!(parser.next_atom(&tokenizer), Ok(Some(expr!("foo" x))));
+
+        assert!(!SExprParser::is_valid_variable_sigil('('));
+        assert!(!SExprParser::is_valid_variable_sigil(' '));
+    }
+
+    #[test]
+    fn test_parser_extra_separators() {
+        let tokenizer = Tokenizer::new();
+        let mut parser = SExprParser::new("(a, b, c) (d,$x) ,; comment\n,").with_extra_separators(&[',', ';']);
+        assert_eq!(parser.parse(&tokenizer), Ok(Some(expr!("a" "b" "c"))));
+        assert_eq!(parser.parse(&tokenizer), Ok(Some(expr!("d" x))));
+        assert!(parser.at_end());
+        assert_eq!(parser.parse(&tokenizer), Ok(None));
+
+        let mut parser = SExprParser::new("(a, b)");
+        assert_eq!(parser.parse(&tokenizer), Ok(Some(expr!("a," "b"))));
+
+        let mut parser = OwnedSExprParser::new("(a,b)".into());
+        parser.set_extra_separators(&[',']);
+        assert_eq!(parser.next_atom(&tokenizer), Ok(Some(expr!("a" "b"))));
+    }
+
     #[test]
     fn test_error_from_tokenizer() {
         //NOTE: This test relies on an intentional bug in the regex, so that it will accept an invalid
@@ -705,6 +1409,78 @@ mod tests {
         assert_eq!(res, expected);
     }
 
+    #[test]
+    fn test_parse_error_location() {
+        let tokenizer = Tokenizer::new();
+        let text = "(a) (b \"c)";
+        let mut parser = SExprParser::new(text);
+        assert_eq!(Some(expr!(("a"))), parser.parse_checked(&tokenizer).unwrap());
+        let err = parser.parse_checked(&tokenizer).unwrap_err();
+        assert_eq!(7, err.offset);
+        assert_eq!("Unclosed String Literal", err.message);
+        assert_eq!("\"c)", &text[err.node.unwrap().src_range]);
+
+        let mut parser = SExprParser::new("(a)\n  )");
+        assert_eq!(Some(expr!(("a"))), parser.parse_checked(&tokenizer).unwrap());
+        let err = parser.parse_checked(&tokenizer).unwrap_err();
+        assert_eq!(6, err.offset);
+        assert_eq!("Unexpected right bracket", err.message);
+
+        let mut parser = OwnedSExprParser::new("(a) (b".to_string());
+        assert_eq!(Some(expr!(("a"))), parser.parse_checked(&tokenizer).unwrap());
+        let err = parser.parse_checked(&tokenizer).unwrap_err();
+        assert_eq!(4, err.offset);
+        assert_eq!("Unexpected end of expression", err.message);
+        assert_eq!(4..6, err.node.unwrap().src_range);
+    }
+
+    #[test]
+    fn test_parser_recovering() {
+        let tokenizer = Tokenizer::new();
+        let text = "(a $x#1) (b) ) (c)\n(d (e)\n(f)";
+        let mut parser = SExprParser::new(text);
+        let err = parser.parse_recovering(&tokenizer).unwrap_err();
+        assert_eq!(0, err.offset);
+        assert_eq!("'#' char is reserved for internal usage", err.message);
+        assert_eq!(Some(expr!(("b"))), parser.parse_recovering(&tokenizer).unwrap());
+        let err = parser.parse_recovering(&tokenizer).unwrap_err();
+        assert_eq!(13, err.offset);
+        assert_eq!("Unexpected right bracket", err.message);
+        assert_eq!(Some(expr!(("c"))), parser.parse_recovering(&tokenizer).unwrap());
+        let err = parser.parse_recovering(&tokenizer).unwrap_err();
+        assert_eq!("Unexpected end of expression", err.message);
+        assert_eq!(Some(expr!(("f"))), parser.parse_recovering(&tokenizer).unwrap());
+        assert_eq!(None, parser.parse_recovering(&tokenizer).unwrap());
+
+        let mut parser = OwnedSExprParser::new(text.to_string());
+        let mut atoms = vec![];
+        let mut errors = vec![];
+        loop {
+            match parser.parse_recovering(&tokenizer) {
+                Ok(Some(atom)) => atoms.push(atom),
+                Ok(None) => break,
+                Err(err) => errors.push(err.offset),
+            }
+        }
+        assert_eq!(vec![expr!(("b")), expr!(("c")), expr!(("f"))], atoms);
+        assert_eq!(3, errors.len());
+        assert_eq!(&[0, 13], &errors[..2]);
+    }
+
+    #[test]
+    fn test_comment_keep() {
+        let tokenizer = Tokenizer::new();
+        let mut parser = SExprParser::new("; hello\n(foo ; inner\n)").with_comment_handling(CommentMode::Keep);
+        assert_eq!(Ok(Some(expr!("Comment" "; hello"))), parser.parse(&tokenizer));
+        assert_eq!(Ok(Some(expr!(("foo")))), parser.parse(&tokenizer));
+        assert_eq!(Ok(None), parser.parse(&tokenizer));
+
+        let mut parser = OwnedSExprParser::new("; hello\n(foo)".to_string());
+        parser.set_comment_mode(CommentMode::Keep);
+        assert_eq!(Ok(Some(expr!("Comment" "; hello"))), parser.next_atom(&tokenizer));
+        assert_eq!(Ok(Some(expr!(("foo")))), parser.next_atom(&tokenizer));
+    }
+
     #[test]
     fn test_comment_endl() {
         let program = " (a 4);
@@ -714,6 +1490,103 @@ mod tests {
         assert_eq!(res, expected);
     }
 
+    /// A reader that yields its text in tiny chunks, to exercise parsing across buffer refills
+    struct ChunkedReader<'a> {
+        text: &'a [u8],
+        chunk_size: usize,
+    }
+
+    impl std::io::Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.chunk_size.min(buf.len()).min(self.text.len());
+            buf[..len].copy_from_slice(&self.text[..len]);
+            self.text = &self.text[len..];
+            Ok(len)
+        }
+    }
+
+    fn parse_atoms_from_reader(program: &str) -> Vec<Result<Atom, String>> {
+        let tokenizer = Tokenizer::new();
+        let reader = std::io::BufReader::with_capacity(3, ChunkedReader{ text: program.as_bytes(), chunk_size: 2 });
+        let mut parser = SExprParser::from_reader(reader);
+        let mut result = Vec::new();
+        loop {
+            match parser.next_atom(&tokenizer) {
+                Ok(Some(atom)) => result.push(Ok(atom)),
+                Ok(None) => break,
+                Err(err) => {
+                    result.push(Err(err.message));
+                    break;
+                },
+            }
+        }
+        result
+    }
+
+    fn reader_parser(program: &str) -> ReaderSExprParser<impl BufRead + '_> {
+        SExprParser::from_reader(std::io::BufReader::with_capacity(3, ChunkedReader{ text: program.as_bytes(), chunk_size: 2 }))
+    }
+
+    #[test]
+    fn test_reader_parser() {
+        assert_eq!(vec![Ok(expr!("a")), Ok(expr!("b" ("c" d)))], parse_atoms_from_reader("a (b\n  (c $d)) ; x"));
+        assert_eq!(vec![Ok(expr!("long-symbol"))], parse_atoms_from_reader("long-symbol"));
+        assert_eq!(vec![Ok(expr!("f" "\"multi\nline\""))], parse_atoms_from_reader("(f \"multi\nline\")\n"));
+        assert_eq!(parse_atoms("(= (fac $n)\n  (* $n (fac (- $n 1))))\n\n(x)").into_iter().map(Ok).collect::<Vec<_>>(),
+            parse_atoms_from_reader("(= (fac $n)\n  (* $n (fac (- $n 1))))\n\n(x)"));
+        assert_eq!(Vec::<Result<Atom, String>>::new(), parse_atoms_from_reader(""));
+    }
+
+    #[test]
+    fn test_reader_parser_errors() {
+        let result = parse_atoms_from_reader("(a)\n(b\n c");
+        assert_eq!(Ok(expr!(("a"))), result[0]);
+        assert!(result[1].is_err());
+
+        let result = parse_atoms_from_reader("(a)) (b)\n(c)");
+        assert_eq!(Ok(expr!(("a"))), result[0]);
+        assert!(result[1].is_err());
+        assert_eq!(2, result.len());
+    }
+
+    #[test]
+    fn test_reader_parser_error_offset() {
+        let tokenizer = Tokenizer::new();
+        for program in ["(a)\n(b\n c", "(a)\n\n  (b $c#)", "a b)", "(a\n \"b\\q\")"] {
+            let mut expected = SExprParser::new(program);
+            let expected = loop {
+                match expected.parse_checked(&tokenizer) {
+                    Ok(Some(_)) => {},
+                    result => break result,
+                }
+            };
+            let mut parser = reader_parser(program);
+            let actual = loop {
+                match parser.next_atom(&tokenizer) {
+                    Ok(Some(_)) => {},
+                    result => break result,
+                }
+            };
+            assert_eq!(expected.unwrap_err().offset, actual.unwrap_err().offset, "program: {program:?}");
+        }
+    }
+
+    #[test]
+    fn test_reader_parser_options() {
+        let tokenizer = Tokenizer::new();
+        let mut parser = reader_parser("(a, ?b)\n((c))\n(d)");
+        parser.set_variable_sigil('?');
+        parser.set_extra_separators(&[',']);
+        parser.set_max_depth(Some(1));
+
+        assert_eq!(parser.next_atom(&tokenizer).unwrap(), Some(expr!("a" b)));
+        let err = parser.next_atom(&tokenizer).unwrap_err();
+        assert_eq!(err.message, "Expression nesting depth exceeds the limit of 1");
+        assert_eq!(err.offset, 9);
+        assert_eq!(parser.next_atom(&tokenizer).unwrap(), Some(expr!(("d"))));
+        assert_eq!(parser.next_atom(&tokenizer).unwrap(), None);
+    }
+
     fn parse_atoms(program: &str) -> Vec<Atom> {
         let tokenizer = Tokenizer::new();
         let mut parser = SExprParser::new(program);
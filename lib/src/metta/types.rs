@@ -548,6 +548,74 @@ pub fn validate_atom(space: &dyn Space, atom: &Atom) -> bool {
     !get_atom_types(space, atom).is_empty()
 }
 
+/// Checks whether `atom` is correctly typed in context of the given `space`
+/// like [validate_atom] does. Returns `Err` with a message which describes
+/// the type error: the position of the incorrectly typed argument, its
+/// expected and actual types. If a sub-expression of the `atom` is
+/// incorrectly typed then the error in the innermost such sub-expression is
+/// reported.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon::expr;
+/// use hyperon::metta::runner::*;
+/// use hyperon::metta::text::SExprParser;
+/// use hyperon::metta::types::validate_atom_explain;
+///
+/// let metta = Metta::new(None);
+/// metta.run(SExprParser::new("(: foo (-> A B)) (: a A) (: b B)")).unwrap();
+///
+/// let space = metta.space();
+/// assert_eq!(validate_atom_explain(&space, &expr!("foo" "a")), Ok(()));
+/// assert_eq!(validate_atom_explain(&space, &expr!("foo" "b")),
+///     Err("Incorrect type of argument 1 of (foo b): expected A, found B".into()));
+/// ```
+pub fn validate_atom_explain(space: &dyn Space, atom: &Atom) -> Result<(), String> {
+    if validate_atom(space, atom) {
+        Ok(())
+    } else {
+        Err(explain_type_error(space, atom)
+            .unwrap_or_else(|| format!("Incorrect type of {}", atom)))
+    }
+}
+
+fn explain_type_error(space: &dyn Space, atom: &Atom) -> Option<String> {
+    let expr = match atom {
+        Atom::Expression(expr) if !expr.children().is_empty() => expr,
+        _ => return None,
+    };
+    if let Some(child) = expr.children().iter().find(|child| !validate_atom(space, child)) {
+        if let Some(message) = explain_type_error(space, child) {
+            return Some(message);
+        }
+    }
+    let op = get_op(expr);
+    let args = get_args(expr);
+    let mut message = None;
+    for fn_type in get_atom_types(space, op).iter().filter(|typ| is_func(typ)) {
+        let (expected_arg_types, _ret_typ) = get_arg_types(fn_type);
+        if expected_arg_types.len() != args.len() {
+            message.get_or_insert_with(|| format!("Incorrect number of arguments of {}: expected {}, found {}",
+                atom, expected_arg_types.len(), args.len()));
+            continue;
+        }
+        for (i, (arg, expected)) in args.iter().zip(expected_arg_types).enumerate() {
+            if *expected == get_meta_type(arg) || *expected == ATOM_TYPE_ATOM {
+                continue;
+            }
+            let actual = get_atom_types(space, arg);
+            if !actual.iter().any(|typ| match_reducted_types_v2(typ, expected).next().is_some()) {
+                let actual = actual.iter().map(|typ| typ.to_string()).collect::<Vec<String>>().join(" | ");
+                return Some(format!("Incorrect type of argument {} of {}: expected {}, found {}",
+                    i + 1, atom, expected, actual));
+            }
+        }
+        message.get_or_insert_with(|| format!("Types of arguments of {} don't match function type {}", atom, fn_type));
+    }
+    message
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -591,6 +659,26 @@ mod tests {
         space
     }
 
+    #[test]
+    fn validate_atom_explain_incorrect_arguments() {
+        let space = metta_space("
+            (: foo (-> A B B))
+            (: bar (-> $t $t B))
+            (: a A)
+            (: b B)
+        ");
+
+        assert_eq!(validate_atom_explain(&space, &atom("(foo a b)")), Ok(()));
+        assert_eq!(validate_atom_explain(&space, &atom("(foo a a)")),
+            Err("Incorrect type of argument 2 of (foo a a): expected B, found A".into()));
+        assert_eq!(validate_atom_explain(&space, &atom("(foo (foo b b) b)")),
+            Err("Incorrect type of argument 1 of (foo b b): expected A, found B".into()));
+        assert_eq!(validate_atom_explain(&space, &atom("(foo a)")),
+            Err("Incorrect number of arguments of (foo a): expected 2, found 1".into()));
+        let err = validate_atom_explain(&space, &atom("(bar a b)")).unwrap_err();
+        assert!(err.starts_with("Types of arguments of (bar a b) don't match function type (-> $t"), "{}", err);
+    }
+
     #[test]
     fn test_check_type() {
         let mut space = GroundingSpace::new();
@@ -4,14 +4,17 @@
 use crate::*;
 use super::*;
 use crate::atom::*;
-use crate::atom::matcher::match_atoms;
+use crate::atom::matcher::{match_atoms, Bindings, GroundedMatchMode};
 use crate::atom::subexpr::split_expr;
-use crate::common::multitrie::{MultiTrie, TrieKey, TrieToken};
+use crate::common::multitrie::{MultiTrie, TrieKey, TrieToken, TrieStats};
 
 use std::fmt::Debug;
-use std::collections::BTreeSet;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, BTreeMap};
+use std::collections::{HashSet, HashMap};
 use std::hash::{DefaultHasher, Hasher};
+use std::time::{Duration, Instant};
+use std::cell::RefCell;
+use std::rc::Rc;
 use crate::common::collections::ImmutableString;
 
 // Grounding space
@@ -19,36 +22,373 @@ use crate::common::collections::ImmutableString;
 /// Symbol to concatenate queries to space.
 pub const COMMA_SYMBOL : Atom = sym!(",");
 
-struct GroundingSpaceIter<'a> {
-    space: &'a GroundingSpace,
-    i: usize,
+/// Symbol to mark negated sub-queries inside a conjunction of queries, see
+/// [GroundingSpace::query].
+pub const NOT_SYMBOL : Atom = sym!("not");
+
+/// Storage of atoms used by [GroundingSpace]. The space keeps observers,
+/// metadata and the query logic, and delegates keeping atoms to the storage,
+/// thus the default in-memory [VecAtomStorage] can be replaced by a
+/// specialized one, see [GroundingSpace::with_storage].
+///
+/// Each atom is identified by a position which is returned by
+/// [AtomStorage::insert] and stays the same until the atom is removed.
+/// Positions of removed atoms can be reused for new atoms.
+pub trait AtomStorage {
+    /// Adds `atom` into the storage and returns its position.
+    fn insert(&mut self, atom: Atom) -> usize;
+    /// Removes the atom at `pos` and returns it, or returns `None` if there is
+    /// no atom at this position.
+    fn remove(&mut self, pos: usize) -> Option<Atom>;
+    /// Replaces the atom at `pos` by `atom` keeping the position. Returns the
+    /// previous atom, or `None` if there is no atom at this position, in
+    /// that case the storage is not changed.
+    fn replace(&mut self, pos: usize, atom: Atom) -> Option<Atom>;
+    /// Returns the atom at `pos`.
+    fn get(&self, pos: usize) -> Option<&Atom>;
+    /// Returns the number of atoms in the storage.
+    fn atom_count(&self) -> usize;
+    /// Iterates over positions and atoms in order of positions.
+    fn iter(&self) -> Box<dyn Iterator<Item=(usize, &Atom)> + '_>;
+    /// Returns positions of atoms which can match `pattern`. The result may
+    /// contain atoms which don't match `pattern` but it must contain all
+    /// atoms which match it. Returning all positions is always correct.
+    fn candidates(&self, pattern: &Atom) -> Box<dyn Iterator<Item=usize> + '_>;
+    /// Reserves room for at least `additional` more atoms. Does nothing by
+    /// default.
+    fn reserve(&mut self, _additional: usize) {}
 }
 
-impl<'a> GroundingSpaceIter<'a> {
-    fn new(space: &'a GroundingSpace) -> Self {
-        GroundingSpaceIter { space, i: 0 }
-    }
+/// Default [AtomStorage] of the [GroundingSpace]. Atoms are kept in a vector
+/// and indexed by a trie of their symbols, thus [AtomStorage::candidates]
+/// skips atoms which cannot match the pattern.
+pub struct VecAtomStorage {
+    index: MultiTrie<SymbolAtom, usize>,
+    content: Vec<Atom>,
+    free: BTreeSet<usize>,
 }
 
-impl<'a> Iterator for GroundingSpaceIter<'a> {
-    type Item = &'a Atom;
+impl VecAtomStorage {
+    /// Constructs new empty storage.
+    pub fn new() -> Self {
+        Self{ index: MultiTrie::new(), content: Vec::new(), free: BTreeSet::new() }
+    }
+
+    fn from_vec(content: Vec<Atom>) -> Self {
+        let free = BTreeSet::new();
+        Self{ index: Self::build_index(&content, &free), content, free }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let len = self.space.content.len();
-        let mut i = self.i;
-        while i < len && self.space.free.contains(&i) {
-            i += 1;
+    fn build_index(content: &[Atom], free: &BTreeSet<usize>) -> MultiTrie<SymbolAtom, usize> {
+        let mut index = MultiTrie::new();
+        for (i, atom) in content.iter().enumerate() {
+            if !free.contains(&i) {
+                index.insert(atom_to_trie_key(atom), i);
+            }
         }
-        if i >= len {
-            self.i = i;
-            None
+        index
+    }
+
+    fn stats(&self) -> IndexStats {
+        self.index.stats()
+    }
+}
+
+impl Default for VecAtomStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AtomStorage for VecAtomStorage {
+    fn insert(&mut self, atom: Atom) -> usize {
+        if self.free.is_empty() {
+            let pos = self.content.len();
+            self.index.insert(atom_to_trie_key(&atom), pos);
+            self.content.push(atom);
+            pos
         } else {
-            self.i = i + 1;
-            self.space.content.get(i)
+            let pos = *self.free.iter().next().unwrap();
+            self.free.remove(&pos);
+            self.index.insert(atom_to_trie_key(&atom), pos);
+            self.content[pos] = atom;
+            pos
+        }
+    }
+
+    fn remove(&mut self, pos: usize) -> Option<Atom> {
+        let atom = self.get(pos)?.clone();
+        self.index.remove(&atom_to_trie_key(&atom), &pos);
+        self.free.insert(pos);
+        Some(atom)
+    }
+
+    fn replace(&mut self, pos: usize, atom: Atom) -> Option<Atom> {
+        let prev = self.get(pos)?;
+        self.index.remove(&atom_to_trie_key(prev), &pos);
+        self.index.insert(atom_to_trie_key(&atom), pos);
+        Some(std::mem::replace(&mut self.content[pos], atom))
+    }
+
+    fn get(&self, pos: usize) -> Option<&Atom> {
+        match self.free.contains(&pos) {
+            true => None,
+            false => self.content.get(pos),
+        }
+    }
+
+    fn atom_count(&self) -> usize {
+        self.content.len() - self.free.len()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item=(usize, &Atom)> + '_> {
+        Box::new(self.content.iter().enumerate().filter(|(i, _atom)| !self.free.contains(i)))
+    }
+
+    fn candidates(&self, pattern: &Atom) -> Box<dyn Iterator<Item=usize> + '_> {
+        let key = atom_to_trie_key(pattern);
+        let positions: Vec<usize> = self.index.get(&key).cloned().collect();
+        Box::new(positions.into_iter())
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        let additional = additional.saturating_sub(self.free.len());
+        self.content.reserve(additional);
+    }
+}
+
+impl Clone for VecAtomStorage {
+    fn clone(&self) -> Self {
+        // The index is rebuilt because nodes of the MultiTrie are shared
+        // between the clones and modifying one clone would change another.
+        Self {
+            index: Self::build_index(&self.content, &self.free),
+            content: self.content.clone(),
+            free: self.free.clone(),
+        }
+    }
+}
+
+impl PartialEq for VecAtomStorage {
+    fn eq(&self, other: &Self) -> bool {
+        self.content == other.content
+    }
+}
+
+/// Error returned by [GroundingSpace::query_with_guard] when the number of
+/// intermediate results of a conjunction query exceeds the passed limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryLimitError {
+    /// Limit of the intermediate results which was exceeded
+    pub max_intermediate: usize,
+}
+
+impl Display for QueryLimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Number of intermediate query results exceeds the limit of {}", self.max_intermediate)
+    }
+}
+
+/// Error returned by [GroundingSpace::query_timed] when the query is not
+/// finished in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryTimeout {
+    /// Time given to execute the query
+    pub timeout: Duration,
+}
+
+impl Display for QueryTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Query is not finished in {:?}", self.timeout)
+    }
+}
+
+/// Reason of the query execution interruption.
+enum QueryInterrupted {
+    Limit(QueryLimitError),
+    Timeout(QueryTimeout),
+}
+
+/// Limits of the query execution, the query is interrupted when a limit is
+/// exceeded.
+struct QueryBudget {
+    max_intermediate: usize,
+    deadline: Option<(Instant, Duration)>,
+}
+
+impl QueryBudget {
+    fn unlimited() -> Self {
+        Self{ max_intermediate: usize::MAX, deadline: None }
+    }
+
+    fn check_time(&self) -> Result<(), QueryInterrupted> {
+        match self.deadline {
+            Some((deadline, timeout)) if Instant::now() > deadline =>
+                Err(QueryInterrupted::Timeout(QueryTimeout{ timeout })),
+            _ => Ok(()),
         }
     }
 }
 
+/// Options of the [GroundingSpace::query_with_options] method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryOptions {
+    /// When true results which bind a variable to an atom containing this
+    /// variable (for instance `$x` to `(f $x)`) are excluded, see
+    /// [matcher::match_atoms_without_occurs_check]. True by default.
+    pub occurs_check: bool,
+    /// Defines how the grounded atoms are matched. [GroundedMatchMode::Custom]
+    /// by default.
+    pub grounded_match: GroundedMatchMode,
+    /// When true symbols of the query match symbols of the space which have
+    /// the same name ignoring case, for instance `human` matches `Human`.
+    /// It affects only symbols, variables and grounded atoms are matched as
+    /// usual. The index of the space cannot be used to find candidates in
+    /// this mode, thus all atoms of the space are checked. False by default.
+    pub case_insensitive_symbols: bool,
+}
+
+impl Default for QueryOptions {
+    fn default() -> Self {
+        Self{ occurs_check: true, grounded_match: GroundedMatchMode::Custom, case_insensitive_symbols: false }
+    }
+}
+
+/// Returns true if `atom` contains only symbols and expressions.
+fn is_symbolic(atom: &Atom) -> bool {
+    atom.iter().all(|atom| matches!(atom, Atom::Symbol(_) | Atom::Expression(_)))
+}
+
+/// Returns `query` with each symbol replaced by the symbol at the same
+/// position in `source` when their names are equal ignoring case.
+fn align_symbols_case(query: &Atom, source: &Atom) -> Atom {
+    match (query, source) {
+        (Atom::Symbol(q), Atom::Symbol(s)) if q.name().to_lowercase() == s.name().to_lowercase() => source.clone(),
+        (Atom::Expression(q), Atom::Expression(s)) if q.children().len() == s.children().len() =>
+            Atom::expr(q.children().iter().zip(s.children())
+                .map(|(q, s)| align_symbols_case(q, s)).collect::<Vec<_>>()),
+        _ => query.clone(),
+    }
+}
+
+/// Matches the atom of the space `source` with the `query`, the results keep
+/// only the `query_vars` of the query.
+fn match_source(source: &Atom, query: &Atom, query_vars: &HashSet<&VariableAtom>, is_ground_query: bool, options: &QueryOptions) -> Vec<Bindings> {
+    if is_ground_query && is_symbolic(source) {
+        return if source == query { vec![Bindings::new()] } else { vec![] };
+    }
+    let next = make_variables_unique(source.clone());
+    log::trace!("single_query: match next: {}", next);
+    matcher::match_atoms_with_mode(&next, query, options.grounded_match, options.occurs_check)
+        .map(|bindings| bindings.narrow_vars(query_vars))
+        .inspect(|bindings| log::trace!("single_query: push result: {}", bindings))
+        .collect()
+}
+
+fn negated_query(query: &Atom) -> Option<&Atom> {
+    match query {
+        Atom::Expression(expr) => match expr.children().as_slice() {
+            [op, negated] if *op == NOT_SYMBOL => Some(negated),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Result of a conjunction clause which can be merged with the results of
+/// the next clauses.
+trait ConjunctionResult: Sized {
+    fn bindings(&self) -> &Bindings;
+    fn merge(&self, next: Self) -> Vec<Self>;
+}
+
+impl ConjunctionResult for Bindings {
+    fn bindings(&self) -> &Bindings {
+        self
+    }
+
+    fn merge(&self, next: Self) -> Vec<Self> {
+        next.merge_v2(self).into_iter().collect()
+    }
+}
+
+/// Bindings paired with the atoms of the space which produced them.
+impl ConjunctionResult for (Bindings, Vec<Atom>) {
+    fn bindings(&self) -> &Bindings {
+        &self.0
+    }
+
+    fn merge(&self, (next, next_sources): Self) -> Vec<Self> {
+        next.merge_v2(&self.0).into_iter()
+            .map(|bindings| {
+                let mut sources = self.1.clone();
+                sources.extend(next_sources.iter().cloned());
+                (bindings, sources)
+            }).collect()
+    }
+}
+
+type ConjunctionIter<'a, R, E> = Box<dyn Iterator<Item=Result<R, E>> + 'a>;
+
+/// Executes the conjunction `clauses` one by one starting from the `start`
+/// result. Bindings of the previous result are applied to each clause before
+/// it is executed. A negated clause keeps the previous result when
+/// `is_empty` returns `true` for the negated query. Other clauses are
+/// executed by `query` and their results are merged with the previous
+/// result. Results of each clause are passed through `next_clause` which
+/// can collect them eagerly, for instance to limit the number of
+/// intermediate results. Otherwise results are calculated lazily.
+fn fold_conjunction<'a, R, E, N, Q, I>(clauses: Vec<Atom>, start: R, is_empty: N, query: Q,
+    next_clause: impl Fn(ConjunctionIter<'a, R, E>) -> ConjunctionIter<'a, R, E>) -> ConjunctionIter<'a, R, E>
+where
+    R: ConjunctionResult + 'a,
+    E: 'a,
+    N: Fn(&Atom) -> Result<bool, E> + Clone + 'a,
+    Q: Fn(Atom) -> Result<I, E> + Clone + 'a,
+    I: Iterator<Item=R> + 'a,
+{
+    let start: ConjunctionIter<'a, R, E> = Box::new(std::iter::once(Ok(start)));
+    clauses.into_iter().fold(start, |acc, clause| {
+        let is_empty = is_empty.clone();
+        let query = query.clone();
+        next_clause(Box::new(acc.flat_map(move |prev| -> ConjunctionIter<'a, R, E> {
+            let prev = match prev {
+                Ok(prev) => prev,
+                Err(err) => return Box::new(std::iter::once(Err(err))),
+            };
+            let clause = matcher::apply_bindings_to_atom_move(clause.clone(), prev.bindings());
+            let next: Result<ConjunctionIter<'a, R, E>, E> = match negated_query(&clause) {
+                Some(negated) => is_empty(negated)
+                    .map(|empty| Box::new(empty.then_some(prev).into_iter().map(Ok)) as ConjunctionIter<'a, R, E>),
+                None => query(clause)
+                    .map(|results| Box::new(results.flat_map(move |next| prev.merge(next)).map(Ok)) as ConjunctionIter<'a, R, E>),
+            };
+            next.unwrap_or_else(|err| Box::new(std::iter::once(Err(err))))
+        })))
+    })
+}
+
+/// Returns clauses of the conjunction `query` glued by [COMMA_SYMBOL] or
+/// `None` if `query` is not a conjunction. Allows analyzing and rewriting
+/// queries before passing them to [GroundingSpace::query].
+///
+/// # Examples
+///
+/// ```
+/// use hyperon::expr;
+/// use hyperon::space::grounding::split_conjunction;
+///
+/// assert_eq!(split_conjunction(&expr!("," ("a" x) ("b" x))),
+///     Some(vec![expr!("a" x), expr!("b" x)]));
+/// assert_eq!(split_conjunction(&expr!("a" x)), None);
+/// ```
+pub fn split_conjunction(query: &Atom) -> Option<Vec<Atom>> {
+    match split_expr(query) {
+        Some((sym @ Atom::Symbol(_), args)) if *sym == COMMA_SYMBOL => Some(args.cloned().collect()),
+        _ => None,
+    }
+}
+
 pub(crate) fn atom_to_trie_key(atom: &Atom) -> TrieKey<SymbolAtom> {
     fn fill_key(atom: &Atom, tokens: &mut Vec<TrieToken<SymbolAtom>>) {
         match atom {
@@ -77,42 +417,227 @@ pub(crate) fn atom_to_trie_key(atom: &Atom) -> TrieKey<SymbolAtom> {
     TrieKey::from(tokens)
 }
 
-/// In-memory space which can contain grounded atoms.
-// TODO: Clone is required by C API
+/// Statistics about the shape of the [GroundingSpace] index, see
+/// [GroundingSpace::stats].
+pub type IndexStats = TrieStats;
+
+/// Maximal number of atoms listed by the [Debug] output of [GroundingSpace].
+/// Use [GroundingSpace::dump] to list more.
+pub const DEBUG_ATOM_LIMIT: usize = 32;
+
+/// Saved state of the [GroundingSpace] content, see
+/// [GroundingSpace::snapshot].
 #[derive(Clone)]
-pub struct GroundingSpace {
-    index: MultiTrie<SymbolAtom, usize>,
-    content: Vec<Atom>,
-    free: BTreeSet<usize>,
+pub struct SpaceSnapshot<S: AtomStorage = VecAtomStorage> {
+    storage: S,
+    metadata: HashMap<usize, AtomMetadata>,
+}
+
+/// Key-value attributes attached to an atom of the [GroundingSpace], see
+/// [GroundingSpace::add_with_metadata]. Metadata is kept outside of the atom,
+/// thus it doesn't affect matching. It can be used to keep the provenance of
+/// the atom like the source file and line or a confidence value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AtomMetadata {
+    values: BTreeMap<String, String>,
+}
+
+impl AtomMetadata {
+    /// Constructs metadata without attributes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns metadata with the `key` attribute set to `value`.
+    pub fn with<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    /// Sets the `key` attribute to `value`. Returns the previous value of the
+    /// attribute if any.
+    pub fn insert<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) -> Option<String> {
+        self.values.insert(key.into(), value.into())
+    }
+
+    /// Returns the value of the `key` attribute.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Returns the iterator over attributes ordered by key.
+    pub fn iter(&self) -> impl Iterator<Item=(&str, &str)> {
+        self.values.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+/// Node of an atom added by [GroundingSpace::add_interned]. Equal
+/// sub-expressions of the interned atoms are represented by the same node.
+#[derive(Debug, PartialEq, Eq)]
+pub enum InternedAtom {
+    /// Symbol, variable or grounded atom
+    Leaf(Atom),
+    /// Expression which shares its children with other interned atoms
+    Expression(Vec<Rc<InternedAtom>>),
+}
+
+impl InternedAtom {
+    /// Returns the owned [Atom] represented by the node.
+    pub fn to_atom(&self) -> Atom {
+        match self {
+            Self::Leaf(atom) => atom.clone(),
+            Self::Expression(children) => Atom::expr(children.iter()
+                .map(|child| child.to_atom()).collect::<Vec<_>>()),
+        }
+    }
+
+    fn is_equal(&self, atom: &Atom) -> bool {
+        match (self, atom) {
+            (Self::Leaf(leaf), _) => leaf == atom,
+            (Self::Expression(children), Atom::Expression(expr)) =>
+                children.len() == expr.children().len()
+                    && children.iter().zip(expr.children()).all(|(node, child)| node.is_equal(child)),
+            _ => false,
+        }
+    }
+}
+
+/// Key of the node in the [InternPool]. Children are interned before the
+/// expression, thus the expression is identified by addresses of its
+/// children nodes which are kept alive by the pool.
+#[derive(PartialEq, Eq, Hash)]
+enum InternKey {
+    Leaf(Atom),
+    Expression(Vec<*const InternedAtom>),
+}
+
+/// Atoms added by [GroundingSpace::add_interned] and the pool of their nodes.
+#[derive(Default)]
+struct InternPool {
+    nodes: HashMap<InternKey, Rc<InternedAtom>>,
+    atoms: Vec<Rc<InternedAtom>>,
+}
+
+impl Clone for InternPool {
+    fn clone(&self) -> Self {
+        // Nodes are interned again because the pool relies on the number of
+        // references to a node to find out whether it is still used.
+        let mut pool = Self::default();
+        for node in &self.atoms {
+            pool.insert(&node.to_atom());
+        }
+        pool
+    }
+}
+
+impl InternPool {
+    fn insert(&mut self, atom: &Atom) {
+        let node = self.intern(atom);
+        self.atoms.push(node);
+    }
+
+    fn intern(&mut self, atom: &Atom) -> Rc<InternedAtom> {
+        let (key, node) = match atom {
+            Atom::Expression(expr) => {
+                let children: Vec<Rc<InternedAtom>> = expr.children().iter()
+                    .map(|child| self.intern(child)).collect();
+                (InternKey::Expression(children.iter().map(Rc::as_ptr).collect()), InternedAtom::Expression(children))
+            },
+            _ => (InternKey::Leaf(atom.clone()), InternedAtom::Leaf(atom.clone())),
+        };
+        self.nodes.entry(key).or_insert_with(|| Rc::new(node)).clone()
+    }
+
+    /// Removes all atoms equal to `atom` and the nodes which are not used
+    /// anymore. Returns true if any atom was removed.
+    fn remove(&mut self, atom: &Atom) -> bool {
+        let count = self.atoms.len();
+        self.atoms.retain(|node| !node.is_equal(atom));
+        if self.atoms.len() == count {
+            return false;
+        }
+        // Node which is referenced by the pool only is not used. Parents are
+        // dropped before their children, thus it is repeated until no node
+        // is dropped.
+        loop {
+            let count = self.nodes.len();
+            self.nodes.retain(|_key, node| Rc::strong_count(node) > 1);
+            if self.nodes.len() == count {
+                return true;
+            }
+        }
+    }
+}
+
+/// Counters of the [GroundingSpace] query cache, see
+/// [GroundingSpace::enable_query_cache].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryCacheStats {
+    /// Number of queries answered from the cache
+    pub hits: usize,
+    /// Number of queries executed on the content of the space
+    pub misses: usize,
+}
+
+#[derive(Default)]
+struct QueryCache {
+    results: HashMap<Atom, BindingsSet>,
+    stats: QueryCacheStats,
+}
+
+/// In-memory space which can contain grounded atoms. Atoms are kept by the
+/// [AtomStorage] `S`, which is [VecAtomStorage] by default.
+pub struct GroundingSpace<S: AtomStorage = VecAtomStorage> {
+    storage: S,
+    metadata: HashMap<usize, AtomMetadata>,
     common: SpaceCommon,
     name: Option<String>,
+    batch: Option<Vec<SpaceEvent>>,
+    query_cache: Option<RefCell<QueryCache>>,
+    interned: InternPool,
 }
 
 impl GroundingSpace {
 
     /// Constructs new empty space.
     pub fn new() -> Self {
-        Self {
-            index: MultiTrie::new(),
-            content: Vec::new(),
-            free: BTreeSet::new(),
-            common: SpaceCommon::default(),
-            name: None,
-        }
+        Self::with_storage(VecAtomStorage::new())
+    }
+
+    /// Constructs new empty space with room for at least `capacity` atoms
+    /// before reallocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut space = Self::new();
+        space.reserve(capacity);
+        space
     }
 
     /// Constructs space from vector of atoms.
     pub fn from_vec(atoms: Vec<Atom>) -> Self {
-        let mut index = MultiTrie::new();
-        for (i, atom) in atoms.iter().enumerate() {
-            index.insert(atom_to_trie_key(atom), i);
-        }
-        Self{
-            index,
-            content: atoms,
-            free: BTreeSet::new(),
+        Self::with_storage(VecAtomStorage::from_vec(atoms))
+    }
+
+    /// Returns statistics about the shape of the space index. A large
+    /// `max_depth` relative to `node_count` means the atoms produce a deep
+    /// degenerate chain, while a small one means a shallow wide index.
+    pub fn stats(&self) -> IndexStats {
+        self.storage.stats()
+    }
+}
+
+impl<S: AtomStorage> GroundingSpace<S> {
+
+    /// Constructs new space which keeps atoms in the `storage`. Atoms which
+    /// are already in the `storage` become the content of the space.
+    pub fn with_storage(storage: S) -> Self {
+        Self {
+            storage,
+            metadata: HashMap::new(),
             common: SpaceCommon::default(),
             name: None,
+            batch: None,
+            query_cache: None,
+            interned: InternPool::default(),
         }
     }
 
@@ -136,354 +661,2219 @@ impl GroundingSpace {
     pub fn add(&mut self, atom: Atom) {
         //log::debug!("GroundingSpace::add(): self: {:?}, atom: {:?}", self as *const GroundingSpace, atom);
         self.add_internal(atom.clone());
-        self.common.notify_all_observers(&SpaceEvent::Add(atom));
+        self.notify(SpaceEvent::Add(atom));
     }
 
-    fn add_internal(&mut self, atom: Atom) {
-        if self.free.is_empty() {
-            let pos = self.content.len();
-            self.index.insert(atom_to_trie_key(&atom), pos);
-            self.content.push(atom);
+    /// Adds `atom` into space keeping its sub-expressions in a pool shared
+    /// by all interned atoms, thus a sub-expression repeated in many atoms
+    /// is kept in memory once. It is a tradeoff: adding is slower because
+    /// each sub-expression is looked up in the pool, and queries are slower
+    /// because interned atoms are not indexed and each of them is turned
+    /// back into an owned [Atom] before matching. Use it for large spaces
+    /// with many repeated sub-expressions when the memory matters more
+    /// than the speed.
+    ///
+    /// Interned atoms are returned by queries and
+    /// [GroundingSpace::iter_interned], and removed by
+    /// [GroundingSpace::remove]. They are kept outside of the [AtomStorage],
+    /// thus they are not returned by [GroundingSpace::iter], cannot be
+    /// replaced and are not saved by [GroundingSpace::snapshot]. Observers
+    /// are notified by [SpaceEvent::Add] as for [GroundingSpace::add].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind_set, sym};
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let mut space = GroundingSpace::new();
+    /// space.add_interned(expr!("parent" ("person" "Tom") ("person" "Bob")));
+    /// space.add_interned(expr!("male" ("person" "Tom")));
+    ///
+    /// assert_eq!(space.query(&expr!("male" x)), bind_set![{x: expr!("person" "Tom")}]);
+    /// assert_eq!(space.iter_interned().count(), 2);
+    /// ```
+    pub fn add_interned(&mut self, atom: Atom) {
+        self.interned.insert(&atom);
+        self.notify(SpaceEvent::Add(atom));
+    }
+
+    /// Returns owned copies of the atoms added by
+    /// [GroundingSpace::add_interned], in the order they were added.
+    pub fn iter_interned(&self) -> impl Iterator<Item=Atom> + '_ {
+        self.interned.atoms.iter().map(|node| node.to_atom())
+    }
+
+    /// Adds `atom` into space and attaches `metadata` to it. Observers are
+    /// notified by the same [SpaceEvent::Add] event as for
+    /// [GroundingSpace::add], metadata is not delivered to them. Metadata is
+    /// dropped when the atom is removed or replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::sym;
+    /// use hyperon::space::grounding::{GroundingSpace, AtomMetadata};
+    ///
+    /// let mut space = GroundingSpace::new();
+    /// space.add_with_metadata(sym!("A"), AtomMetadata::new().with("source", "a.metta:1"));
+    ///
+    /// assert_eq!(space.metadata_of(&sym!("A")).and_then(|meta| meta.get("source")), Some("a.metta:1"));
+    /// ```
+    pub fn add_with_metadata(&mut self, atom: Atom, metadata: AtomMetadata) {
+        let pos = self.add_internal(atom.clone());
+        self.metadata.insert(pos, metadata);
+        self.notify(SpaceEvent::Add(atom));
+    }
+
+    /// Returns metadata attached to `atom` by [GroundingSpace::add_with_metadata].
+    /// If the space contains several copies of the atom then metadata of the
+    /// first copy which has it is returned.
+    pub fn metadata_of(&self, atom: &Atom) -> Option<&AtomMetadata> {
+        let mut indexes: Vec<usize> = self.positions_of(atom).collect();
+        indexes.sort();
+        indexes.iter().find_map(|i| self.metadata.get(i))
+    }
+
+    /// Reserves room for at least `additional` more atoms to be added into
+    /// space without reallocating the atom storage. Useful before adding a
+    /// known number of atoms. The index isn't pre-sized because its shape
+    /// depends on the atoms added.
+    pub fn reserve(&mut self, additional: usize) {
+        self.storage.reserve(additional);
+    }
+
+    /// Adds `atom` into space, unless the space already contains an equal atom.
+    /// Returns true if atom was added, and false otherwise. Observers are
+    /// notified only when the atom is added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::sym;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let mut space = GroundingSpace::from_vec(vec![sym!("A")]);
+    ///
+    /// assert!(!space.add_unique(sym!("A")));
+    /// assert!(space.add_unique(sym!("B")));
+    ///
+    /// assert_eq!(space.iter().count(), 2);
+    /// ```
+    pub fn add_unique(&mut self, atom: Atom) -> bool {
+        if self.contains(&atom) {
+            false
         } else {
-            let pos = *self.free.iter().next().unwrap();
-            self.free.remove(&pos);
-            self.index.insert(atom_to_trie_key(&atom), pos);
-            self.content[pos] = atom;
+            self.add(atom);
+            true
         }
     }
 
-    /// Removes `atom` from space. Returns true if atom was found and removed,
-    /// and false otherwise.
+    /// Saves the current content of the space to be restored later by
+    /// [GroundingSpace::restore]. It allows rolling back speculative
+    /// modifications of the space. The snapshot is a copy of the atom storage
+    /// made by its [Clone] implementation, thus the cost depends on the
+    /// storage `S`. For [VecAtomStorage] it takes time and memory
+    /// proportional to the size of the space, because the index is rebuilt
+    /// when the snapshot is taken. [GroundingSpace::restore] only swaps the
+    /// saved storage in.
     ///
     /// # Examples
     ///
     /// ```
     /// use hyperon::sym;
-    /// use hyperon::matcher::BindingsSet;
     /// use hyperon::space::grounding::GroundingSpace;
     ///
     /// let mut space = GroundingSpace::from_vec(vec![sym!("A")]);
+    /// let snapshot = space.snapshot();
+    /// space.add(sym!("B"));
+    /// space.restore(snapshot);
     ///
-    /// space.remove(&sym!("A"));
-    ///
-    /// assert_eq!(space.query(&sym!("A")), BindingsSet::empty());
+    /// assert_eq!(space.iter().cloned().collect::<Vec<_>>(), vec![sym!("A")]);
     /// ```
-    pub fn remove(&mut self, atom: &Atom) -> bool {
-        //log::debug!("GroundingSpace::remove(): self: {:?}, atom: {:?}", self as *const GroundingSpace, atom);
-        let is_removed = self.remove_internal(atom);
-        if is_removed {
-            self.common.notify_all_observers(&SpaceEvent::Remove(atom.clone()));
+    pub fn snapshot(&self) -> SpaceSnapshot<S> where S: Clone {
+        SpaceSnapshot {
+            storage: self.storage.clone(),
+            metadata: self.metadata.clone(),
         }
-        is_removed
     }
 
-    fn remove_internal(&mut self, atom: &Atom) -> bool {
-        let index_key = atom_to_trie_key(atom);
-        let indexes: Vec<usize> = self.index.get(&index_key).map(|i| *i).collect();
-        let mut indexes: Vec<usize> = indexes.into_iter()
-            .filter(|i| self.content[*i] == *atom).collect();
-        indexes.sort_by(|a, b| b.partial_cmp(a).unwrap());
-        let is_removed = indexes.len() > 0;
-        for i in indexes {
-            self.index.remove(&index_key, &i);
-            self.free.insert(i);
+    /// Resets the content of the space to the state saved by
+    /// [GroundingSpace::snapshot]. Observers are notified by
+    /// [SpaceEvent::Remove] about each atom which is not in the snapshot and
+    /// by [SpaceEvent::Add] about each atom which is returned back.
+    pub fn restore(&mut self, snapshot: SpaceSnapshot<S>) {
+        let positions: BTreeSet<usize> = self.storage.iter().map(|(i, _atom)| i)
+            .chain(snapshot.storage.iter().map(|(i, _atom)| i))
+            .collect();
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        for i in positions {
+            let current = self.storage.get(i);
+            let saved = snapshot.storage.get(i);
+            if current != saved {
+                removed.extend(current.cloned());
+                added.extend(saved.cloned());
+            }
+        }
+        self.invalidate_query_cache();
+        self.storage = snapshot.storage;
+        self.metadata = snapshot.metadata;
+        for atom in removed {
+            self.notify(SpaceEvent::Remove(atom));
+        }
+        for atom in added {
+            self.notify(SpaceEvent::Add(atom));
         }
-        is_removed
     }
 
-    /// Replaces `from` atom to `to` atom inside space. Doesn't add `to` when
-    /// `from` is not found. Returns true if atom was found and replaced, and
-    /// false otherwise.
+    /// Adds all atoms of the `other` space into this space. Observers of this
+    /// space are notified about each added atom, observers of the `other`
+    /// space are not notified because it is not modified.
     ///
     /// # Examples
     ///
     /// ```
     /// use hyperon::sym;
     /// use hyperon::space::grounding::GroundingSpace;
-    /// use hyperon::atom::matcher::BindingsSet;
     ///
     /// let mut space = GroundingSpace::from_vec(vec![sym!("A")]);
+    /// space.merge(&GroundingSpace::from_vec(vec![sym!("A"), sym!("B")]));
     ///
-    /// space.replace(&sym!("A"), sym!("B"));
-    ///
-    /// assert_eq!(space.query(&sym!("A")), BindingsSet::empty());
-    /// assert_eq!(space.query(&sym!("B")), BindingsSet::single());
+    /// assert_eq!(space.iter().count(), 3);
     /// ```
-    pub fn replace(&mut self, from: &Atom, to: Atom) -> bool {
-        let is_replaced = self.replace_internal(from, to.clone());
-        if is_replaced {
-            self.common.notify_all_observers(&SpaceEvent::Replace(from.clone(), to));
+    pub fn merge(&mut self, other: &Self) {
+        self.reserve(other.storage.atom_count());
+        for atom in other.iter() {
+            self.add(atom.clone());
         }
-        is_replaced
     }
 
-    fn replace_internal(&mut self, from: &Atom, to: Atom) -> bool {
-        let is_replaced = self.remove_internal(from);
-        if is_replaced {
-            self.add_internal(to);
-        }
-        is_replaced
+    /// Adds atoms of the `other` space into this space skipping atoms which
+    /// are already in this space, see [GroundingSpace::add_unique].
+    /// Observers of this space are notified about each added atom, observers
+    /// of the `other` space are not notified because it is not modified.
+    pub fn merge_unique(&mut self, other: &Self) {
+        for atom in other.iter() {
+            self.add_unique(atom.clone());
+        }
+    }
+
+    /// Returns atoms of this space which are not present in the `other`
+    /// space. Atoms are compared structurally and set semantics is used: each
+    /// atom is returned once even if it is added into this space several
+    /// times, and it is not returned if the `other` space contains at least
+    /// one equal atom.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::sym;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![sym!("A"), sym!("B"), sym!("B")]);
+    /// let other = GroundingSpace::from_vec(vec![sym!("A"), sym!("C")]);
+    ///
+    /// assert_eq!(space.difference(&other), vec![sym!("B")]);
+    /// ```
+    pub fn difference(&self, other: &Self) -> Vec<Atom> {
+        self.distinct_atoms().filter(|atom| !other.contains(atom)).cloned().collect()
+    }
+
+    /// Returns atoms which are present in both this space and the `other`
+    /// space. Atoms are compared structurally and set semantics is used:
+    /// each atom is returned once regardless of how many times it is added
+    /// into each space.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::sym;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![sym!("A"), sym!("A"), sym!("B")]);
+    /// let other = GroundingSpace::from_vec(vec![sym!("A"), sym!("C")]);
+    ///
+    /// assert_eq!(space.intersection(&other), vec![sym!("A")]);
+    /// ```
+    pub fn intersection(&self, other: &Self) -> Vec<Atom> {
+        self.distinct_atoms().filter(|atom| other.contains(atom)).cloned().collect()
+    }
+
+    /// Iterates over atoms of the space skipping atoms equal to some atom
+    /// with a lower position in the space content.
+    fn distinct_atoms(&self) -> impl Iterator<Item=&Atom> {
+        self.storage.iter()
+            .filter(|(i, atom)| !self.positions_of(atom).any(|j| j < *i))
+            .map(|(_i, atom)| atom)
+    }
+
+    /// Returns true if the space contains an atom equal to `atom`.
+    fn contains(&self, atom: &Atom) -> bool {
+        self.positions_of(atom).next().is_some()
+    }
+
+    /// Iterates over positions of atoms equal to `atom`.
+    fn positions_of<'a>(&'a self, atom: &'a Atom) -> impl Iterator<Item=usize> + 'a {
+        self.storage.candidates(atom)
+            .filter(move |i| self.storage.get(*i) == Some(atom))
+    }
+
+    fn add_internal(&mut self, atom: Atom) -> usize {
+        self.storage.insert(atom)
+    }
+
+    /// Removes `atom` from space. Returns true if atom was found and removed,
+    /// and false otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::sym;
+    /// use hyperon::matcher::BindingsSet;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let mut space = GroundingSpace::from_vec(vec![sym!("A")]);
+    ///
+    /// space.remove(&sym!("A"));
+    ///
+    /// assert_eq!(space.query(&sym!("A")), BindingsSet::empty());
+    /// ```
+    pub fn remove(&mut self, atom: &Atom) -> bool {
+        //log::debug!("GroundingSpace::remove(): self: {:?}, atom: {:?}", self as *const GroundingSpace, atom);
+        let is_removed = self.remove_internal(atom);
+        if is_removed {
+            self.notify(SpaceEvent::Remove(atom.clone()));
+        }
+        is_removed
+    }
+
+    /// Removes each of `atoms` from space like [GroundingSpace::remove] does.
+    /// Returns the number of passed atoms which were found and removed.
+    /// Observers are notified by a separate [SpaceEvent::Remove] event per
+    /// removed atom.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::sym;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let mut space = GroundingSpace::from_vec(vec![sym!("A"), sym!("B"), sym!("C")]);
+    ///
+    /// assert_eq!(space.remove_all(&[sym!("A"), sym!("C"), sym!("D")]), 2);
+    /// assert_eq!(space.iter().cloned().collect::<Vec<_>>(), vec![sym!("B")]);
+    /// ```
+    pub fn remove_all<'a, I: IntoIterator<Item=&'a Atom>>(&mut self, atoms: I) -> usize {
+        atoms.into_iter().filter(|atom| self.remove(atom)).count()
+    }
+
+    fn remove_internal(&mut self, atom: &Atom) -> bool {
+        let mut indexes: Vec<usize> = self.positions_of(atom).collect();
+        indexes.sort_by(|a, b| b.partial_cmp(a).unwrap());
+        let is_removed = self.interned.remove(atom) | (indexes.len() > 0);
+        for i in indexes {
+            self.storage.remove(i);
+            self.metadata.remove(&i);
+        }
+        is_removed
+    }
+
+    /// Replaces `from` atom to `to` atom inside space. Doesn't add `to` when
+    /// `from` is not found. Returns true if atom was found and replaced, and
+    /// false otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::sym;
+    /// use hyperon::space::grounding::GroundingSpace;
+    /// use hyperon::atom::matcher::BindingsSet;
+    ///
+    /// let mut space = GroundingSpace::from_vec(vec![sym!("A")]);
+    ///
+    /// space.replace(&sym!("A"), sym!("B"));
+    ///
+    /// assert_eq!(space.query(&sym!("A")), BindingsSet::empty());
+    /// assert_eq!(space.query(&sym!("B")), BindingsSet::single());
+    /// ```
+    pub fn replace(&mut self, from: &Atom, to: Atom) -> bool {
+        let is_replaced = self.replace_internal(from, to.clone());
+        if is_replaced {
+            self.notify(SpaceEvent::Replace(from.clone(), to));
+        }
+        is_replaced
+    }
+
+    fn replace_internal(&mut self, from: &Atom, to: Atom) -> bool {
+        let is_replaced = self.remove_internal(from);
+        if is_replaced {
+            self.add_internal(to);
+        }
+        is_replaced
+    }
+
+    /// Replaces each atom of the space by the result of `f` applied to it.
+    /// The closure is called once per atom in the order of the space content.
+    /// Observers are notified by [SpaceEvent::Replace] only about atoms which
+    /// are changed by `f`. Changed atoms keep their positions and metadata.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, sym, Atom};
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let mut space = GroundingSpace::from_vec(vec![expr!("color" "red"), expr!("size" "big")]);
+    ///
+    /// space.map_atoms(|atom| match atom {
+    ///     Atom::Expression(expr) => Atom::expr(expr.children().iter()
+    ///         .map(|child| if *child == sym!("red") { sym!("green") } else { child.clone() })
+    ///         .collect::<Vec<_>>()),
+    ///     _ => atom.clone(),
+    /// });
+    ///
+    /// assert_eq!(space.iter().cloned().collect::<Vec<_>>(), vec![expr!("color" "green"), expr!("size" "big")]);
+    /// ```
+    pub fn map_atoms(&mut self, mut f: impl FnMut(&Atom) -> Atom) {
+        let positions: Vec<usize> = self.storage.iter().map(|(i, _atom)| i).collect();
+        for i in positions {
+            let Some(prev) = self.storage.get(i) else { continue };
+            let atom = f(prev);
+            if atom != *prev {
+                if let Some(prev) = self.storage.replace(i, atom.clone()) {
+                    self.notify(SpaceEvent::Replace(prev, atom));
+                }
+            }
+        }
+    }
+
+    /// Calls `f` on the space as a single transaction. Events caused by
+    /// modifications inside `f` are not delivered to observers immediately.
+    /// Instead they are delivered together as one [SpaceEvent::Batch] after
+    /// `f` returns, so observers don't see the intermediate states of the
+    /// space. No event is delivered if `f` doesn't modify the space. A
+    /// transaction started inside `f` becomes part of the outer transaction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::sym;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let mut space = GroundingSpace::new();
+    ///
+    /// let added = space.transaction(|space| {
+    ///     space.add(sym!("A"));
+    ///     space.add_unique(sym!("A"))
+    /// });
+    ///
+    /// assert!(!added);
+    /// assert_eq!(space.iter().count(), 1);
+    /// ```
+    pub fn transaction<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        if self.batch.is_some() {
+            return f(self);
+        }
+        self.batch = Some(Vec::new());
+        let result = f(self);
+        let events = self.batch.take().unwrap();
+        if !events.is_empty() {
+            self.common.notify_all_observers(&SpaceEvent::Batch(events));
+        }
+        result
+    }
+
+    fn notify(&mut self, event: SpaceEvent) {
+        self.invalidate_query_cache();
+        match &mut self.batch {
+            Some(batch) => batch.push(event),
+            None => self.common.notify_all_observers(&event),
+        }
+    }
+
+    /// Executes `query` on the space and returns variable bindings found.
+    /// Query may include sub-queries glued by [COMMA_SYMBOL] symbol.
+    /// Each [Bindings](matcher::Bindings) instance in the returned [BindingsSet]
+    /// represents single result.
+    ///
+    /// A sub-query of the conjunction can be negated as `(not <sub-query>)`
+    /// (see [NOT_SYMBOL]). Negated sub-query keeps the current bindings when
+    /// the sub-query with these bindings applied has no results, and removes
+    /// them otherwise. This is negation as failure: it relies on the
+    /// closed-world assumption that anything which cannot be found in the space
+    /// is false. Variables which are not bound by the previous sub-queries are
+    /// not bound by the negated sub-query.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind_set, sym};
+    /// use hyperon::matcher::BindingsSet;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("B" "C")]);
+    /// let query = expr!("," ("A" x) (x "C"));
+    ///
+    /// let result = space.query(&query);
+    ///
+    /// assert_eq!(result, bind_set![{x: sym!("B")}]);
+    /// ```
+    pub fn query(&self, query: &Atom) -> BindingsSet {
+        let result = match &self.query_cache {
+            Some(cache) => self.query_cached(cache, query, |query| self.query_unlimited(query)),
+            None => self.query_unlimited(query),
+        };
+        self.common.notify_all_observers_on_query(query, result.len());
+        result
+    }
+
+    fn query_unlimited(&self, query: &Atom) -> BindingsSet {
+        self.query_with_budget(query, &QueryBudget::unlimited(), &QueryOptions::default())
+            .unwrap_or_else(|_| panic!("Query without limits cannot be interrupted"))
+    }
+
+    /// Returns results of the `query` from the `cache`, or executes the query
+    /// by `execute` and caches its results. Results are cached by the
+    /// canonical form of the query, thus alpha-equivalent queries share them.
+    fn query_cached<F>(&self, cache: &RefCell<QueryCache>, query: &Atom, execute: F) -> BindingsSet
+        where F: FnOnce(&Atom) -> BindingsSet
+    {
+        let canonical = alpha_canonical(query);
+        let cached = cache.borrow().results.get(&canonical).cloned();
+        let result = match cached {
+            Some(result) => {
+                cache.borrow_mut().stats.hits += 1;
+                result
+            },
+            None => {
+                let result = execute(&canonical);
+                let mut cache = cache.borrow_mut();
+                cache.stats.misses += 1;
+                cache.results.insert(canonical.clone(), result.clone());
+                result
+            },
+        };
+        let original: HashMap<VariableAtom, VariableAtom> = collect_variables(&canonical).into_iter()
+            .zip(collect_variables(query)).collect();
+        // Variables which came from the atoms of the space are renamed on
+        // each call, thus results of different queries don't share them
+        // the same way results of uncached queries don't.
+        result.into_iter()
+            .map(|bindings| {
+                let mut fresh = crate::common::CachingMapper::new(VariableAtom::make_unique);
+                bindings.rename_vars(|var| match original.get(&var) {
+                    Some(original) => original.clone(),
+                    None => fresh.replace(var),
+                })
+            })
+            .collect()
+    }
+
+    /// Enables caching of the [GroundingSpace::query] results. Results are
+    /// kept per query, queries which differ only by names of variables share
+    /// the same cache entry, see [alpha_canonical]. Repeated query is answered
+    /// from the cache without scanning the space. The whole cache is dropped
+    /// on any modification of the space, thus it is correct but useful only
+    /// when the space is read much more often than it is modified. Other query
+    /// methods don't use the cache.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind_set, sym};
+    /// use hyperon::space::grounding::{GroundingSpace, QueryCacheStats};
+    ///
+    /// let mut space = GroundingSpace::from_vec(vec![expr!("A" "B")]);
+    /// space.enable_query_cache();
+    ///
+    /// assert_eq!(space.query(&expr!("A" x)), bind_set![{x: sym!("B")}]);
+    /// assert_eq!(space.query(&expr!("A" y)), bind_set![{y: sym!("B")}]);
+    /// assert_eq!(space.query_cache_stats(), Some(QueryCacheStats{ hits: 1, misses: 1 }));
+    /// ```
+    pub fn enable_query_cache(&mut self) {
+        if self.query_cache.is_none() {
+            self.query_cache = Some(RefCell::default());
+        }
+    }
+
+    /// Returns the counters of the query cache, or `None` when the cache is
+    /// not enabled, see [GroundingSpace::enable_query_cache].
+    pub fn query_cache_stats(&self) -> Option<QueryCacheStats> {
+        self.query_cache.as_ref().map(|cache| cache.borrow().stats)
+    }
+
+    fn invalidate_query_cache(&mut self) {
+        if let Some(cache) = &mut self.query_cache {
+            cache.get_mut().results.clear();
+        }
+    }
+
+    /// Executes `query` on the space like [GroundingSpace::query] but
+    /// returns [QueryLimitError] when the number of intermediate results of
+    /// the conjunction of sub-queries exceeds `max_intermediate`. It protects
+    /// the caller from queries which produce a huge cross product of results
+    /// when first sub-queries are not selective enough.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind};
+    /// use hyperon::space::grounding::{GroundingSpace, QueryLimitError};
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A"), expr!("B")]);
+    ///
+    /// assert_eq!(space.query_with_guard(&expr!("," x y), 3),
+    ///     Err(QueryLimitError{ max_intermediate: 3 }));
+    /// assert_eq!(space.query_with_guard(&expr!("," x y), 4).map(|r| r.len()), Ok(4));
+    /// ```
+    pub fn query_with_guard(&self, query: &Atom, max_intermediate: usize) -> Result<Vec<Bindings>, QueryLimitError> {
+        let budget = QueryBudget{ max_intermediate, deadline: None };
+        let result = match self.query_with_budget(query, &budget, &QueryOptions::default()) {
+            Ok(result) => result,
+            Err(QueryInterrupted::Limit(err)) => return Err(err),
+            Err(QueryInterrupted::Timeout(_)) => unreachable!("Query without deadline cannot be timed out"),
+        };
+        self.common.notify_all_observers_on_query(query, result.len());
+        Ok(result.into_iter().collect())
+    }
+
+    /// Executes `query` on the space like [GroundingSpace::query] but
+    /// reorders sub-queries of the conjunction before execution. Sub-queries
+    /// which have less candidate atoms in the space index are executed first
+    /// which keeps the number of intermediate results low. Negated
+    /// sub-queries are kept in place and only sub-queries between them are
+    /// reordered, because the result of a negation depends on the variables
+    /// bound by the sub-queries executed before it. The returned
+    /// results are the same as results of [GroundingSpace::query] but the
+    /// order of results can be different.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind, sym, assert_eq_no_order};
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("B" "C"), expr!("B" "D")]);
+    ///
+    /// let result = space.query_planned(&expr!("," (x y) ("A" x)));
+    ///
+    /// assert_eq_no_order!(result, vec![bind!{x: sym!("B"), y: sym!("C")}, bind!{x: sym!("B"), y: sym!("D")}]);
+    /// ```
+    pub fn query_planned(&self, query: &Atom) -> Vec<Bindings> {
+        let execute = |query: &Atom| self.query_unlimited(&self.plan_query(query));
+        let result = match &self.query_cache {
+            Some(cache) => self.query_cached(cache, query, execute),
+            None => execute(query),
+        };
+        self.common.notify_all_observers_on_query(query, result.len());
+        result.into_iter().collect()
+    }
+
+    fn plan_query(&self, query: &Atom) -> Atom {
+        match split_conjunction(query) {
+            Some(mut clauses) => {
+                for run in clauses.split_mut(|query| negated_query(query).is_some()) {
+                    run.sort_by_cached_key(|query| self.storage.candidates(query).count());
+                }
+                let mut children = vec![COMMA_SYMBOL];
+                children.extend(clauses);
+                Atom::expr(children)
+            },
+            None => query.clone(),
+        }
+    }
+
+    /// Executes `query` on the space like [GroundingSpace::query] using the
+    /// passed `options`. [QueryOptions::default] gives the same results as
+    /// [GroundingSpace::query].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind};
+    /// use hyperon::space::grounding::{GroundingSpace, QueryOptions};
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("eq" y y)]);
+    /// let query = expr!("eq" x ("f" x));
+    ///
+    /// assert!(space.query_with_options(&query, QueryOptions::default()).is_empty());
+    /// let options = QueryOptions{ occurs_check: false, ..Default::default() };
+    /// assert_eq!(space.query_with_options(&query, options).len(), 1);
+    /// ```
+    pub fn query_with_options(&self, query: &Atom, options: QueryOptions) -> Vec<Bindings> {
+        let result = self.query_with_budget(query, &QueryBudget::unlimited(), &options)
+            .unwrap_or_else(|_| panic!("Query without limits cannot be interrupted"));
+        self.common.notify_all_observers_on_query(query, result.len());
+        result.into_iter().collect()
+    }
+
+    /// Executes `query` on the space like [GroundingSpace::query] but
+    /// returns [QueryTimeout] when the query is not finished in `timeout`.
+    /// The time is checked after each candidate atom is matched and after
+    /// each intermediate result of a conjunction is processed, thus the
+    /// query can run a bit longer than `timeout`. The results found before
+    /// the timeout are discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, sym, bind};
+    /// use hyperon::space::grounding::GroundingSpace;
+    /// use std::time::Duration;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A" "B")]);
+    ///
+    /// assert_eq!(space.query_timed(&expr!("A" x), Duration::from_secs(10)),
+    ///     Ok(vec![bind!{x: sym!("B")}]));
+    /// ```
+    pub fn query_timed(&self, query: &Atom, timeout: Duration) -> Result<Vec<Bindings>, QueryTimeout> {
+        let budget = QueryBudget{ max_intermediate: usize::MAX, deadline: Some((Instant::now() + timeout, timeout)) };
+        let result = match self.query_with_budget(query, &budget, &QueryOptions::default()) {
+            Ok(result) => result,
+            Err(QueryInterrupted::Timeout(err)) => return Err(err),
+            Err(QueryInterrupted::Limit(_)) => unreachable!("Number of results cannot exceed usize::MAX"),
+        };
+        self.common.notify_all_observers_on_query(query, result.len());
+        Ok(result.into_iter().collect())
+    }
+
+    fn query_with_budget(&self, query: &Atom, budget: &QueryBudget, options: &QueryOptions) -> Result<BindingsSet, QueryInterrupted> {
+        match split_conjunction(query) {
+            Some(clauses) => fold_conjunction(clauses, Bindings::new(),
+                |negated| Ok(self.query_with_budget(negated, budget, options)?.is_empty()),
+                |clause| Ok(self.query_with_budget(&clause, budget, options)?.into_iter()),
+                |results| {
+                    let mut collected = BindingsSet::empty();
+                    for next in results {
+                        let next = match next {
+                            Ok(next) => next,
+                            Err(err) => return Box::new(std::iter::once(Err(err))),
+                        };
+                        collected.push(next);
+                        if collected.len() > budget.max_intermediate {
+                            return Box::new(std::iter::once(Err(QueryInterrupted::Limit(QueryLimitError{ max_intermediate: budget.max_intermediate }))));
+                        }
+                        if let Err(err) = budget.check_time() {
+                            return Box::new(std::iter::once(Err(err)));
+                        }
+                    }
+                    log::debug!("query: current result: {:?}", collected);
+                    Box::new(collected.into_iter().map(Ok))
+                }).collect(),
+            None => self.single_query(query, budget, options),
+        }
+    }
+
+    /// Renames variables of the `query` according to the `mapping` using
+    /// [rename_variables], executes the renamed query on the space and
+    /// renames variables of the results back. Thus results refer to the
+    /// variables of the original `query`. The `mapping` should not map
+    /// different variables to the same one, otherwise variables of the
+    /// results cannot be renamed back unambiguously.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind, sym, VariableAtom};
+    /// use hyperon::space::grounding::GroundingSpace;
+    /// use std::collections::HashMap;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A" "B")]);
+    /// let mapping = HashMap::from([(VariableAtom::new("x"), VariableAtom::new("y"))]);
+    ///
+    /// assert_eq!(space.query_renamed(&expr!("A" x), &mapping), vec![bind!{x: sym!("B")}]);
+    /// ```
+    pub fn query_renamed(&self, query: &Atom, mapping: &HashMap<VariableAtom, VariableAtom>) -> Vec<Bindings> {
+        let reverse: HashMap<&VariableAtom, &VariableAtom> = mapping.iter()
+            .map(|(from, to)| (to, from)).collect();
+        self.query(&rename_variables(query, mapping)).into_iter()
+            .map(|bindings| bindings.rename_vars(|var| match reverse.get(&var) {
+                Some(original) => (*original).clone(),
+                None => var,
+            }))
+            .collect()
+    }
+
+    /// Executes `query` on the space like [GroundingSpace::query] and returns
+    /// results in a canonical order which doesn't depend on the order atoms
+    /// were added into the space. Results are sorted by the textual form of
+    /// the variables and their values. Sorting requires formatting each
+    /// result, so this method is slower than [GroundingSpace::query] and
+    /// should be used only when the deterministic order is needed, for
+    /// instance in tests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind, sym};
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A" "C"), expr!("A" "B")]);
+    ///
+    /// let result = space.query_sorted(&expr!("A" x));
+    ///
+    /// assert_eq!(result, vec![bind!{x: sym!("B")}, bind!{x: sym!("C")}]);
+    /// ```
+    pub fn query_sorted(&self, query: &Atom) -> Vec<Bindings> {
+        let mut results: Vec<(Vec<(String, String)>, Bindings)> = self.query(query).into_iter()
+            .map(|bindings| {
+                let mut key: Vec<(String, String)> = bindings.iter()
+                    .map(|(var, value)| (var.to_string(), value.to_string()))
+                    .collect();
+                key.sort();
+                (key, bindings)
+            }).collect();
+        results.sort_by(|(a, _), (b, _)| a.cmp(b));
+        results.into_iter().map(|(_key, bindings)| bindings).collect()
+    }
+
+    /// Executes `query` on the space like [GroundingSpace::query] and keeps
+    /// only results where the value bound to `var` has the type `typ`.
+    /// Types are checked using [check_type](crate::metta::types::check_type)
+    /// in `type_space`, which can be either this space or a separate space
+    /// containing the type declarations. Results which don't bind `var` are
+    /// dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind, sym, VariableAtom};
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![
+    ///     expr!(":" "Socrates" "Human"), expr!(":" "Zeus" "God"),
+    ///     expr!("mortal" "Socrates"), expr!("mortal" "Zeus")]);
+    ///
+    /// let result = space.query_typed(&expr!("mortal" x), &VariableAtom::new("x"), &sym!("Human"), &space);
+    ///
+    /// assert_eq!(result, vec![bind!{x: sym!("Socrates")}]);
+    /// ```
+    pub fn query_typed(&self, query: &Atom, var: &VariableAtom, typ: &Atom, type_space: &dyn Space) -> Vec<Bindings> {
+        self.query(query).into_iter()
+            .filter(|bindings| {
+                match bindings.resolve(var) {
+                    Some(value) => crate::metta::types::check_type(type_space, &value, typ),
+                    None => false,
+                }
+            }).collect()
+    }
+
+    /// Returns all atoms of the space which have the type `typ` among their
+    /// types returned by [get_atom_types](crate::metta::types::get_atom_types)
+    /// in `type_space`. Types are compared by equality, thus atoms without
+    /// type declarations which have `%Undefined%` type are not returned. The
+    /// type of each atom is inferred separately, which requires querying
+    /// `type_space` for each atom of the space, so the method is slow on
+    /// large spaces.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, sym};
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![
+    ///     expr!(":" "Socrates" "Human"), expr!(":" "Zeus" "God"), sym!("Socrates"), sym!("Zeus")]);
+    ///
+    /// assert_eq!(space.atoms_of_type(&sym!("Human"), &space), vec![sym!("Socrates")]);
+    /// ```
+    pub fn atoms_of_type(&self, typ: &Atom, type_space: &dyn Space) -> Vec<Atom> {
+        self.iter()
+            .filter(|atom| crate::metta::types::get_atom_types(type_space, atom).contains(typ))
+            .cloned().collect()
+    }
+
+    /// Executes simple `query` without sub-queries on the space.
+    fn single_query(&self, query: &Atom, budget: &QueryBudget, options: &QueryOptions) -> Result<BindingsSet, QueryInterrupted> {
+        let mut result: BindingsSet = self.single_query_explain(query, budget, options)?.into_iter()
+            .map(|(bindings, _source)| bindings).collect();
+        result.extend(self.single_query_interned(query, budget, options)?);
+        log::debug!("single_query: result: {:?}", result);
+        Ok(result)
+    }
+
+    fn single_query_explain(&self, query: &Atom, budget: &QueryBudget, options: &QueryOptions) -> Result<Vec<(Bindings, &Atom)>, QueryInterrupted> {
+        log::debug!("single_query: query: {}", query);
+        let mut result = Vec::new();
+        let query_vars: HashSet<&VariableAtom> = query.iter().filter_type::<&VariableAtom>().collect();
+        // Symbols and expressions of symbols match each other only when they
+        // are equal, thus unification can be skipped for them.
+        let is_ground_query = is_symbolic(query);
+        let candidates: Box<dyn Iterator<Item=usize>> = if options.case_insensitive_symbols {
+            Box::new(self.storage.iter().map(|(i, _atom)| i))
+        } else {
+            self.storage.candidates(query)
+        };
+        for i in candidates {
+            budget.check_time()?;
+            let source = self.storage.get(i)
+                .unwrap_or_else(|| panic!("Storage returns absent atom: query: {:?}, position: {}", query, i));
+            let aligned;
+            let matched_query = if options.case_insensitive_symbols {
+                aligned = align_symbols_case(query, source);
+                &aligned
+            } else {
+                query
+            };
+            for bindings in match_source(source, matched_query, &query_vars, is_ground_query, options) {
+                result.push((bindings, source));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Executes simple `query` on the atoms added by
+    /// [GroundingSpace::add_interned].
+    fn single_query_interned(&self, query: &Atom, budget: &QueryBudget, options: &QueryOptions) -> Result<Vec<Bindings>, QueryInterrupted> {
+        let mut result = Vec::new();
+        let query_vars: HashSet<&VariableAtom> = query.iter().filter_type::<&VariableAtom>().collect();
+        let is_ground_query = is_symbolic(query);
+        for node in &self.interned.atoms {
+            budget.check_time()?;
+            let source = node.to_atom();
+            let matched_query = if options.case_insensitive_symbols {
+                align_symbols_case(query, &source)
+            } else {
+                query.clone()
+            };
+            result.extend(match_source(&source, &matched_query, &query_vars, is_ground_query, options));
+        }
+        Ok(result)
+    }
+
+    /// Executes `pattern` query on the space and lazily substitutes variables
+    /// in `template` by the values from each result, like [Space::subst].
+    /// Results are found one by one while the iterator is advanced, thus
+    /// taking only the first results doesn't spend time on the rest. Query
+    /// observers are not notified about the query.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::expr;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("A" "C")]);
+    ///
+    /// let first: Vec<_> = space.subst_iter(&expr!("A" x), &expr!("D" x)).take(1).collect();
+    ///
+    /// assert_eq!(first.len(), 1);
+    /// ```
+    pub fn subst_iter<'a>(&'a self, pattern: &'a Atom, template: &'a Atom) -> impl Iterator<Item=Atom> + 'a {
+        self.query_iter(pattern.clone())
+            .map(move |bindings| matcher::apply_bindings_to_atom_move(template.clone(), &bindings))
+    }
+
+    fn query_iter<'a>(&'a self, query: Atom) -> Box<dyn Iterator<Item=Bindings> + 'a> {
+        match split_conjunction(&query) {
+            Some(clauses) => Box::new(fold_conjunction(clauses, Bindings::new(),
+                |negated| Ok::<_, std::convert::Infallible>(self.query_iter(negated.clone()).next().is_none()),
+                |clause| Ok(self.query_iter(clause)),
+                |results| results)
+                .map(|result| result.unwrap_or_else(|never| match never {}))),
+            None => self.single_query_iter(query),
+        }
+    }
+
+    fn single_query_iter<'a>(&'a self, query: Atom) -> Box<dyn Iterator<Item=Bindings> + 'a> {
+        let positions: Vec<usize> = self.storage.candidates(&query).collect();
+        let query_vars: HashSet<VariableAtom> = query.iter().filter_type::<&VariableAtom>().cloned().collect();
+        let is_ground_query = is_symbolic(&query);
+        let stored = positions.into_iter().filter_map(|i| self.storage.get(i)).map(std::borrow::Cow::Borrowed);
+        let interned = self.interned.atoms.iter().map(|node| std::borrow::Cow::Owned(node.to_atom()));
+        Box::new(stored.chain(interned).flat_map(move |source| {
+            let query_vars: HashSet<&VariableAtom> = query_vars.iter().collect();
+            match_source(&source, &query, &query_vars, is_ground_query, &QueryOptions::default())
+        }))
+    }
+
+    /// Executes `query` on the space like [GroundingSpace::query] and returns
+    /// each result paired with the atoms of the space which produced it. The
+    /// vector of atoms contains one atom per sub-query of the conjunction
+    /// (negated sub-queries have no atom), or a single atom when `query` is
+    /// not a conjunction. Atoms are returned as they are stored in the space:
+    /// variables of the atoms are renamed before matching, thus bindings can
+    /// refer to the renamed variables which are not present in the returned
+    /// atoms.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind, sym};
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("B" "C")]);
+    ///
+    /// let result = space.query_explain(&expr!("," ("A" x) (x "C")));
+    ///
+    /// assert_eq!(result, vec![(bind!{x: sym!("B")}, vec![expr!("A" "B"), expr!("B" "C")])]);
+    /// ```
+    pub fn query_explain(&self, query: &Atom) -> Vec<(Bindings, Vec<Atom>)> {
+        let result = self.query_explain_internal(query);
+        self.common.notify_all_observers_on_query(query, result.len());
+        result
+    }
+
+    fn query_explain_internal(&self, query: &Atom) -> Vec<(Bindings, Vec<Atom>)> {
+        match split_conjunction(query) {
+            Some(clauses) => fold_conjunction(clauses, (Bindings::new(), Vec::new()),
+                |negated| Ok::<_, std::convert::Infallible>(self.query_iter(negated.clone()).next().is_none()),
+                |clause| Ok(self.query_explain_internal(&clause).into_iter()),
+                |results| results)
+                .map(|result| result.unwrap_or_else(|never| match never {}))
+                .collect(),
+            None => self.single_query_explain(query, &QueryBudget::unlimited(), &QueryOptions::default())
+                .unwrap_or_else(|_| panic!("Query without limits cannot be interrupted")).into_iter()
+                .map(|(bindings, source)| (bindings, vec![source.clone()]))
+                .collect(),
+        }
+    }
+
+    /// Returns atoms of the space which match the `pattern`, in the order
+    /// they are stored in the space. Unlike [GroundingSpace::query] which
+    /// returns only bindings of the variables, this method returns the
+    /// matched atoms themselves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::expr;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("B" "C"), expr!("A" "C")]);
+    ///
+    /// assert_eq!(space.match_atoms_in_space(&expr!("A" x)), vec![expr!("A" "B"), expr!("A" "C")]);
+    /// ```
+    pub fn match_atoms_in_space(&self, pattern: &Atom) -> Vec<Atom> {
+        let mut positions: Vec<usize> = self.storage.candidates(pattern).collect();
+        positions.sort_unstable();
+        let result: Vec<Atom> = positions.into_iter()
+            .filter_map(|i| self.storage.get(i))
+            .filter(|atom| match_atoms(&make_variables_unique((*atom).clone()), pattern).next().is_some())
+            .cloned()
+            .collect();
+        self.common.notify_all_observers_on_query(pattern, result.len());
+        result
+    }
+
+    /// Returns the atom stored at the `index` position of the space, or None
+    /// if there is no atom at this position. Atoms are stored in the order
+    /// they are added, so the n-th added atom has index n until atoms are
+    /// removed. Removing an atom doesn't move other atoms but leaves a free
+    /// position which is filled by the next added atom. [GroundingSpace::replace]
+    /// puts the new atom into the lowest free position, which is the
+    /// position of the replaced atom unless other atoms were removed before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::sym;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let mut space = GroundingSpace::from_vec(vec![sym!("A"), sym!("B"), sym!("C")]);
+    /// space.remove(&sym!("B"));
+    ///
+    /// assert_eq!(space.get(0), Some(&sym!("A")));
+    /// assert_eq!(space.get(1), None);
+    /// assert_eq!(space.get(2), Some(&sym!("C")));
+    /// ```
+    pub fn get(&self, index: usize) -> Option<&Atom> {
+        self.storage.get(index)
+    }
+
+    /// Returns the iterator over content of the space.
+    pub fn iter(&self) -> SpaceIter {
+        SpaceIter::new(self.storage.iter().map(|(_i, atom)| atom))
+    }
+
+    /// Executes each of `patterns` on the space and returns the results of
+    /// each query in the same order as `patterns`. Queries are independent,
+    /// variables of different patterns are not connected to each other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind};
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("C" "D")]);
+    ///
+    /// let results = space.query_multi(&[expr!("A" x), expr!("C" x)]);
+    ///
+    /// assert_eq!(results, vec![vec![bind!{x: expr!("B")}], vec![bind!{x: expr!("D")}]]);
+    /// ```
+    pub fn query_multi(&self, patterns: &[Atom]) -> Vec<Vec<Bindings>> {
+        patterns.iter()
+            .map(|pattern| self.query(pattern).into_iter().collect())
+            .collect()
+    }
+
+    /// Executes `query` on the space and returns the resulting bindings
+    /// narrowed to the `vars` variables only. Values of other variables are
+    /// resolved before they are removed, thus the values of `vars` are the
+    /// same as in the results of [GroundingSpace::query]. Results which
+    /// differ only by the values of other variables are not merged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind};
+    /// use hyperon::atom::VariableAtom;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A" "B")]);
+    ///
+    /// let result = space.query_project(&expr!(x y), &[VariableAtom::new("x")]);
+    ///
+    /// assert_eq!(result, vec![bind!{x: expr!("A")}]);
+    /// ```
+    pub fn query_project(&self, query: &Atom, vars: &[VariableAtom]) -> Vec<Bindings> {
+        let vars: HashSet<&VariableAtom> = vars.iter().collect();
+        self.query(query).into_iter()
+            .map(|bindings| bindings.narrow_vars(&vars))
+            .collect()
+    }
+
+    /// Executes `query` on the space and groups the resulting bindings by
+    /// the value of `group_var`. Bindings in which `group_var` is not bound
+    /// or is bound to a variable are put under the catch-all key which is
+    /// `group_var` itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, sym};
+    /// use hyperon::atom::VariableAtom;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("A" "C"), expr!("D" "B")]);
+    ///
+    /// let groups = space.query_grouped(&expr!(x y), &VariableAtom::new("x"));
+    ///
+    /// assert_eq!(groups[&sym!("A")].len(), 2);
+    /// assert_eq!(groups[&sym!("D")].len(), 1);
+    /// ```
+    pub fn query_grouped(&self, query: &Atom, group_var: &VariableAtom) -> HashMap<Atom, Vec<Bindings>> {
+        let mut groups: HashMap<Atom, Vec<Bindings>> = HashMap::new();
+        for bindings in self.query(query) {
+            let key = match bindings.resolve(group_var) {
+                Some(value) if !matches!(value, Atom::Variable(_)) => value,
+                _ => Atom::Variable(group_var.clone()),
+            };
+            groups.entry(key).or_default().push(bindings);
+        }
+        groups
+    }
+
+    /// Returns atoms which were added into the space more than once along
+    /// with the number of their copies. Atoms are returned in order of their
+    /// first occurrence in the space. Duplicates explain why a query returns
+    /// the same bindings several times.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::sym;
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![sym!("A"), sym!("B"), sym!("A")]);
+    ///
+    /// assert_eq!(space.find_duplicates(), vec![(sym!("A"), 2)]);
+    /// ```
+    pub fn find_duplicates(&self) -> Vec<(Atom, usize)> {
+        let mut counts: HashMap<&Atom, usize> = HashMap::new();
+        let mut order = Vec::new();
+        for atom in self.iter() {
+            let count = counts.entry(atom).or_insert(0);
+            if *count == 0 {
+                order.push(atom);
+            }
+            *count += 1;
+        }
+        order.into_iter()
+            .filter_map(|atom| match counts[atom] {
+                1 => None,
+                count => Some((atom.clone(), count)),
+            }).collect()
+    }
+
+    /// Sets the name property for the `GroundingSpace` which can be useful for debugging
+    pub fn set_name(&mut self, name: String) {
+        self.name = Some(name);
+    }
+
+    /// Returns the name property for the `GroundingSpace`, if one has been set
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(|s| s.as_str())
+    }
+
+    /// Returns the space content as a string, one atom per line, listing at
+    /// most `max_atoms` atoms. Useful for debugging.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, sym};
+    /// use hyperon::space::grounding::GroundingSpace;
+    ///
+    /// let space = GroundingSpace::from_vec(vec![sym!("A"), expr!("B" "C"), sym!("D")]);
+    ///
+    /// assert_eq!(space.dump(2), "A\n(B C)\n... 1 more\n");
+    /// ```
+    pub fn dump(&self, max_atoms: usize) -> String {
+        let mut out = String::new();
+        let mut iter = self.iter();
+        for atom in iter.by_ref().take(max_atoms) {
+            out.push_str(&format!("{}\n", atom));
+        }
+        let rest = iter.count();
+        if rest > 0 {
+            out.push_str(&format!("... {} more\n", rest));
+        }
+        out
+    }
+}
+
+/// Space observer which counts copies of each atom in the space. Counts are
+/// updated by the [SpaceEvent]s thus atoms which were added before the
+/// observer is registered are not counted. [SpaceEvent::Remove] drops all
+/// copies of the atom, because [GroundingSpace::remove] removes all equal
+/// atoms.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon::sym;
+/// use hyperon::space::Space;
+/// use hyperon::space::grounding::{GroundingSpace, CountingObserver};
+///
+/// let mut space = GroundingSpace::new();
+/// let counts = space.common().register_observer(CountingObserver::new());
+///
+/// space.add(sym!("A"));
+/// space.add(sym!("A"));
+///
+/// assert_eq!(counts.borrow().count_of(&sym!("A")), 2);
+/// assert_eq!(counts.borrow().count_of(&sym!("B")), 0);
+/// ```
+#[derive(Debug, Default)]
+pub struct CountingObserver {
+    counts: HashMap<Atom, usize>,
+}
+
+impl CountingObserver {
+    /// Constructs observer without any atoms counted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of copies of `atom` in the observed space.
+    pub fn count_of(&self, atom: &Atom) -> usize {
+        self.counts.get(atom).cloned().unwrap_or(0)
+    }
+}
+
+impl SpaceObserver for CountingObserver {
+    fn notify(&mut self, event: &SpaceEvent) {
+        for event in event.flatten() {
+            match event {
+                SpaceEvent::Add(atom) => *self.counts.entry(atom.clone()).or_insert(0) += 1,
+                SpaceEvent::Remove(atom) => { self.counts.remove(atom); },
+                SpaceEvent::Replace(from, to) => {
+                    self.counts.remove(from);
+                    *self.counts.entry(to.clone()).or_insert(0) += 1;
+                },
+                SpaceEvent::Batch(_) => unreachable!("Batch events are flattened"),
+            }
+        }
+    }
+}
+
+/// Union of several [GroundingSpace] instances which can be queried as one
+/// space without merging their content.
+///
+/// # Examples
+///
+/// ```
+/// use hyperon::{expr, bind, sym};
+/// use hyperon::space::grounding::{GroundingSpace, SpaceUnion};
+///
+/// let core = GroundingSpace::from_vec(vec![expr!("isa" "cat" "animal")]);
+/// let user = GroundingSpace::from_vec(vec![expr!("isa" "tom" "cat")]);
+/// let union = SpaceUnion(vec![&core, &user]);
+///
+/// assert_eq!(union.query(&expr!("isa" x "cat")), vec![bind!{x: sym!("tom")}]);
+/// assert_eq!(union.query(&expr!("isa" x y)).len(), 2);
+/// ```
+pub struct SpaceUnion<'a>(pub Vec<&'a GroundingSpace>);
+
+impl<'a> SpaceUnion<'a> {
+    /// Executes `pattern` on each member space in order and concatenates the
+    /// results, results of each space keep the order returned by
+    /// [GroundingSpace::query]. Results are not deduplicated: when several
+    /// spaces contain the same atom the same bindings are returned once per
+    /// space. A conjunction query is executed on each space separately, thus
+    /// its sub-queries are not joined across the spaces.
+    pub fn query(&self, pattern: &Atom) -> Vec<Bindings> {
+        self.0.iter().flat_map(|space| space.query(pattern)).collect()
+    }
+}
+
+// TODO: Clone is required by C API
+impl<S: AtomStorage + Clone> Clone for GroundingSpace<S> {
+    fn clone(&self) -> Self {
+        Self {
+            storage: self.storage.clone(),
+            metadata: self.metadata.clone(),
+            common: self.common.clone(),
+            name: self.name.clone(),
+            batch: self.batch.clone(),
+            query_cache: self.query_cache.as_ref().map(|_| RefCell::default()),
+            interned: self.interned.clone(),
+        }
+    }
+}
+
+impl<S: AtomStorage + 'static> Space for GroundingSpace<S> {
+    fn common(&self) -> FlexRef<SpaceCommon> {
+        FlexRef::from_simple(&self.common)
+    }
+    fn query(&self, query: &Atom) -> BindingsSet {
+        GroundingSpace::<S>::query(self, query)
+    }
+    fn subst(&self, pattern: &Atom, template: &Atom) -> Vec<Atom> {
+        let result: Vec<Atom> = self.subst_iter(pattern, template).collect();
+        self.common.notify_all_observers_on_query(pattern, result.len());
+        result
+    }
+    fn atom_count(&self) -> Option<usize> {
+        Some(self.storage.atom_count())
+    }
+    fn atom_iter(&self) -> Option<SpaceIter> {
+        Some(self.iter())
+    }
+    fn as_any(&self) -> Option<&dyn std::any::Any> {
+        Some(self)
+    }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+}
+
+impl<S: AtomStorage + 'static> SpaceMut for GroundingSpace<S> {
+    fn add(&mut self, atom: Atom) {
+        GroundingSpace::<S>::add(self, atom)
+    }
+    fn remove(&mut self, atom: &Atom) -> bool {
+        GroundingSpace::<S>::remove(self, atom)
+    }
+    fn replace(&mut self, from: &Atom, to: Atom) -> bool {
+        GroundingSpace::<S>::replace(self, from, to)
+    }
+    fn as_space(&self) -> &dyn Space {
+        self
+    }
+}
+
+impl<S: AtomStorage + PartialEq> PartialEq for GroundingSpace<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.storage == other.storage
+    }
+}
+
+impl<S: AtomStorage> Debug for GroundingSpace<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "GroundingSpace-{name} ({self:p})")?,
+            None => write!(f, "GroundingSpace-{self:p}")?
+        }
+        write!(f, " {{ content: [")?;
+        let mut iter = self.iter();
+        for (i, atom) in iter.by_ref().take(DEBUG_ATOM_LIMIT).enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", atom)?;
+        }
+        let rest = iter.count();
+        if rest > 0 {
+            write!(f, ", ... {} more", rest)?;
+        }
+        write!(f, "] }}")
+    }
+}
+
+impl<S: AtomStorage> Display for GroundingSpace<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "GroundingSpace-{name}"),
+            None => write!(f, "GroundingSpace-{self:p}")
+        }
+    }
+}
+
+impl Grounded for GroundingSpace {
+    fn type_(&self) -> Atom {
+        rust_type_atom::<GroundingSpace>()
+    }
+
+    fn as_match(&self) -> Option<&dyn CustomMatch> {
+        Some(self)
+    }
+}
+
+impl CustomMatch for GroundingSpace {
+    fn match_(&self, other: &Atom) -> matcher::MatchResultIter {
+        Box::new(self.query(other).into_iter())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    struct SpaceEventCollector {
+        events: Vec<SpaceEvent>,
+    }
+
+    impl SpaceEventCollector {
+        fn new() -> Self {
+            Self{ events: Vec::new() }
+        }
+    }
+
+    impl SpaceObserver for SpaceEventCollector {
+        fn notify(&mut self, event: &SpaceEvent) {
+            self.events.push(event.clone());
+        }
+    }
+
+    #[test]
+    fn add_atom() {
+        let mut space = GroundingSpace::new();
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        space.add(expr!("a"));
+        space.add(expr!("b"));
+        space.add(expr!("c"));
+
+        assert_eq_no_order!(space, vec![expr!("a"), expr!("b"), expr!("c")]);
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a")),
+            SpaceEvent::Add(sym!("b")), SpaceEvent::Add(sym!("c"))]);
+    }
+
+    #[test]
+    fn remove_atom() {
+        let mut space = GroundingSpace::new();
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        space.add(expr!("a"));
+        space.add(expr!("b"));
+        space.add(expr!("c"));
+        assert_eq!(space.remove(&expr!("b")), true);
+
+        assert_eq_no_order!(space, vec![expr!("a"), expr!("c")]);
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a")),
+            SpaceEvent::Add(sym!("b")), SpaceEvent::Add(sym!("c")),
+            SpaceEvent::Remove(sym!("b"))]);
+    }
+
+    #[test]
+    fn subst_iter_is_lazy() {
+        let mut space = GroundingSpace::new();
+        space.add(expr!("A" "B"));
+        space.add(expr!("A" "C"));
+        space.add(expr!("A" "D"));
+        space.add(expr!("B" "E"));
+
+        let first: Vec<Atom> = space.subst_iter(&expr!("A" x), &expr!("R" x)).take(1).collect();
+        assert_eq!(first.len(), 1);
+        assert!(space.subst(&expr!("A" x), &expr!("R" x)).contains(&first[0]));
+
+        assert_eq_no_order!(space.subst_iter(&expr!("," ("A" x) (x y)), &expr!("R" x y)).collect::<Vec<_>>(),
+            vec![expr!("R" "B" "E")]);
+        assert_eq_no_order!(space.subst_iter(&expr!("," ("A" x) ("not" (x y))), &expr!("R" x)).collect::<Vec<_>>(),
+            vec![expr!("R" "C"), expr!("R" "D")]);
+        assert_eq_no_order!(Space::subst(&space, &expr!("A" x), &expr!("R" x)),
+            vec![expr!("R" "B"), expr!("R" "C"), expr!("R" "D")]);
+    }
+
+    #[test]
+    fn query_multi_returns_result_per_pattern() {
+        let space = GroundingSpace::from_vec(vec![
+            expr!("parent" "Tom" "Bob"), expr!("parent" "Tom" "Liz"), expr!("age" "Bob" "10"),
+        ]);
+
+        let results = space.query_multi(&[expr!("parent" "Tom" x), expr!("age" x y), expr!("age" "Liz" x)]);
+
+        assert_eq!(results.len(), 3);
+        assert_eq_no_order!(results[0], vec![bind!{x: sym!("Bob")}, bind!{x: sym!("Liz")}]);
+        assert_eq!(results[1], vec![bind!{x: sym!("Bob"), y: sym!("10")}]);
+        assert_eq!(results[2], Vec::<Bindings>::new());
+        assert_eq!(space.query_multi(&[]), Vec::<Vec<Bindings>>::new());
+    }
+
+    #[test]
+    fn query_project_keeps_requested_vars() {
+        let space = GroundingSpace::from_vec(vec![
+            expr!("parent" "Tom" "Bob"), expr!("parent" "Tom" "Liz"), expr!("parent" "Bob" "Ann"),
+        ]);
+
+        let result = space.query_project(&expr!("parent" x y), &[VariableAtom::new("x")]);
+        assert_eq_no_order!(result, vec![bind!{x: sym!("Tom")}, bind!{x: sym!("Tom")}, bind!{x: sym!("Bob")}]);
+
+        let result = space.query_project(&expr!("," ("parent" x y) ("parent" y z)), &[VariableAtom::new("z")]);
+        assert_eq!(result, vec![bind!{z: sym!("Ann")}]);
+    }
+
+    #[test]
+    fn query_grouped_by_variable() {
+        let space = GroundingSpace::from_vec(vec![
+            expr!("age" "Bob" {1}), expr!("age" "Bob" {2}), expr!("age" "Ann" {3}),
+            expr!("age" w {4}),
+        ]);
+
+        let groups = space.query_grouped(&expr!("age" who n), &VariableAtom::new("who"));
+
+        assert_eq!(groups.len(), 3);
+        assert_eq_no_order!(groups[&sym!("Bob")], vec![
+            bind!{who: sym!("Bob"), n: expr!({1})}, bind!{who: sym!("Bob"), n: expr!({2})}]);
+        assert_eq!(groups[&sym!("Ann")], vec![bind!{who: sym!("Ann"), n: expr!({3})}]);
+        assert_eq!(groups[&expr!(who)].len(), 1);
+    }
+
+    #[test]
+    fn add_interned_shares_sub_expressions() {
+        let mut space = GroundingSpace::new();
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+        space.add_interned(expr!("parent" ("person" "Tom") ("person" "Bob")));
+        space.add_interned(expr!("male" ("person" "Tom")));
+
+        let tom = |node: &InternedAtom, i: usize| match node {
+            InternedAtom::Expression(children) => children[i].clone(),
+            InternedAtom::Leaf(_) => panic!("Expression is expected"),
+        };
+        let atoms = &space.interned.atoms;
+        assert!(Rc::ptr_eq(&tom(&atoms[0], 1), &tom(&atoms[1], 1)));
+        assert!(!Rc::ptr_eq(&tom(&atoms[0], 1), &tom(&atoms[0], 2)));
+
+        assert_eq!(space.iter_interned().collect::<Vec<_>>(),
+            vec![expr!("parent" ("person" "Tom") ("person" "Bob")), expr!("male" ("person" "Tom"))]);
+        assert_eq!(space.iter().count(), 0);
+        assert_eq!(space.query(&expr!("parent" ("person" x) y)),
+            bind_set![{x: sym!("Tom"), y: expr!("person" "Bob")}]);
+        assert_eq!(space.query(&expr!("," ("male" x) ("parent" x y))),
+            bind_set![{x: expr!("person" "Tom"), y: expr!("person" "Bob")}]);
+        assert_eq!(space.subst(&expr!("male" x), &expr!(x)), vec![expr!("person" "Tom")]);
+
+        assert!(space.remove(&expr!("parent" ("person" "Tom") ("person" "Bob"))));
+        assert_eq!(space.iter_interned().collect::<Vec<_>>(), vec![expr!("male" ("person" "Tom"))]);
+        assert_eq!(space.interned.nodes.len(), 5);
+        assert_eq!(observer.borrow().events, vec![
+            SpaceEvent::Add(expr!("parent" ("person" "Tom") ("person" "Bob"))),
+            SpaceEvent::Add(expr!("male" ("person" "Tom"))),
+            SpaceEvent::Remove(expr!("parent" ("person" "Tom") ("person" "Bob")))]);
+    }
+
+    #[test]
+    fn query_cache_invalidated_on_modification() {
+        let mut space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("A" "C")]);
+        assert_eq!(space.query_cache_stats(), None);
+        space.enable_query_cache();
+
+        let first = space.query(&expr!("A" x));
+        assert_eq_no_order!(first, vec![bind!{x: sym!("B")}, bind!{x: sym!("C")}]);
+        assert_eq!(space.query(&expr!("A" x)), first);
+        assert_eq_no_order!(space.query(&expr!("A" y)), vec![bind!{y: sym!("B")}, bind!{y: sym!("C")}]);
+        assert_eq!(space.query_cache_stats(), Some(QueryCacheStats{ hits: 2, misses: 1 }));
+
+        space.add(expr!("A" "D"));
+        assert_eq_no_order!(space.query(&expr!("A" x)),
+            vec![bind!{x: sym!("B")}, bind!{x: sym!("C")}, bind!{x: sym!("D")}]);
+        assert_eq!(space.query_cache_stats(), Some(QueryCacheStats{ hits: 2, misses: 2 }));
+
+        space.remove(&expr!("A" "B"));
+        assert_eq_no_order!(space.query(&expr!("A" x)), vec![bind!{x: sym!("C")}, bind!{x: sym!("D")}]);
+        assert_eq!(space.query_cache_stats(), Some(QueryCacheStats{ hits: 2, misses: 3 }));
+    }
+
+    #[test]
+    fn query_cache_renames_space_variables() {
+        let mut space = GroundingSpace::from_vec(vec![expr!("A" ("f" y y z))]);
+        let uncached = space.query(&expr!("A" x));
+        space.enable_query_cache();
+
+        let first = space.query(&expr!("A" x));
+        let second = space.query(&expr!("A" x));
+        assert_eq!(space.query_cache_stats(), Some(QueryCacheStats{ hits: 1, misses: 1 }));
+
+        let value = |result: &BindingsSet| result.iter().next()
+            .and_then(|bindings| bindings.resolve(&VariableAtom::new("x"))).unwrap();
+        let first = value(&first);
+        let second = value(&second);
+        assert!(collect_variables(&first).into_iter().all(|var| !collect_variables(&second).contains(&var)));
+        assert!(matcher::atoms_are_equivalent(&first, &value(&uncached)));
+        assert!(matcher::atoms_are_equivalent(&second, &value(&uncached)));
+    }
+
+    #[test]
+    fn counting_observer_counts() {
+        let mut space = GroundingSpace::new();
+        let counts = space.common.register_observer(CountingObserver::new());
+
+        space.add(expr!("a"));
+        space.add(expr!("a"));
+        space.add(expr!("b" "c"));
+        assert_eq!(counts.borrow().count_of(&expr!("a")), 2);
+        assert_eq!(counts.borrow().count_of(&expr!("b" "c")), 1);
+
+        space.replace(&expr!("a"), expr!("b" "c"));
+        assert_eq!(counts.borrow().count_of(&expr!("a")), 0);
+        assert_eq!(counts.borrow().count_of(&expr!("b" "c")), 2);
+
+        space.transaction(|space| {
+            space.add(expr!("d"));
+            space.remove(&expr!("b" "c"));
+        });
+        assert_eq!(counts.borrow().count_of(&expr!("d")), 1);
+        assert_eq!(counts.borrow().count_of(&expr!("b" "c")), 0);
+        assert_eq!(space.iter().count(), 1);
+    }
+
+    #[test]
+    fn space_union_query() {
+        let core = GroundingSpace::from_vec(vec![expr!("isa" "cat" "animal"), expr!("isa" "dog" "animal")]);
+        let user = GroundingSpace::from_vec(vec![expr!("isa" "tom" "cat"), expr!("isa" "cat" "animal")]);
+        let union = SpaceUnion(vec![&core, &user]);
+
+        assert_eq_no_order!(union.query(&expr!("isa" x "animal")), vec![bind!{x: sym!("cat")},
+            bind!{x: sym!("dog")}, bind!{x: sym!("cat")}]);
+        assert_eq!(union.query(&expr!("isa" "tom" x)), vec![bind!{x: sym!("cat")}]);
+        assert_eq!(SpaceUnion(vec![]).query(&expr!("isa" x y)), vec![]);
+    }
+
+    #[test]
+    fn metadata_of_queried_atom() {
+        let mut space = GroundingSpace::new();
+        space.add(expr!("parent" "Tom" "Bob"));
+        space.add_with_metadata(expr!("parent" "Bob" "Ann"),
+            AtomMetadata::new().with("file", "family.metta").with("line", "2"));
+
+        let found = space.match_atoms_in_space(&expr!("parent" "Bob" x));
+        assert_eq!(found, vec![expr!("parent" "Bob" "Ann")]);
+        let metadata = space.metadata_of(&found[0]).unwrap();
+        assert_eq!(metadata.iter().collect::<Vec<_>>(), vec![("file", "family.metta"), ("line", "2")]);
+        assert_eq!(space.metadata_of(&expr!("parent" "Tom" "Bob")), None);
+
+        space.replace(&expr!("parent" "Bob" "Ann"), expr!("parent" "Bob" "Kim"));
+        assert_eq!(space.metadata_of(&expr!("parent" "Bob" "Kim")), None);
+    }
+
+    #[test]
+    fn find_duplicates_counts_copies() {
+        let mut space = GroundingSpace::new();
+        space.add(expr!("a" "b"));
+        space.add(expr!("c"));
+        space.add(expr!("a" "b"));
+        space.add(expr!("a" "b"));
+
+        assert_eq!(space.find_duplicates(), vec![(expr!("a" "b"), 3)]);
+        space.remove(&expr!("a" "b"));
+        assert_eq!(space.find_duplicates(), vec![]);
+    }
+
+    #[test]
+    fn get_atom_by_index() {
+        let mut space = GroundingSpace::new();
+        space.add(expr!("a"));
+        space.add(expr!("b" "c"));
+        space.add(expr!("d"));
+
+        assert_eq!(space.get(0), Some(&expr!("a")));
+        assert_eq!(space.get(1), Some(&expr!("b" "c")));
+        assert_eq!(space.get(2), Some(&expr!("d")));
+        assert_eq!(space.get(3), None);
+
+        space.replace(&expr!("b" "c"), expr!("e"));
+        assert_eq!(space.get(1), Some(&expr!("e")));
+        space.remove(&expr!("a"));
+        assert_eq!(space.get(0), None);
+        space.add(expr!("f"));
+        assert_eq!(space.get(0), Some(&expr!("f")));
+    }
+
+    #[test]
+    fn remove_atom_not_found() {
+        let mut space = GroundingSpace::new();
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        space.add(expr!("a"));
+        assert_eq!(space.remove(&expr!("b")), false);
+
+        assert_eq_no_order!(space, vec![expr!("a")]);
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a"))]);
+    }
+
+    #[test]
+    fn remove_all_atoms() {
+        let mut space = GroundingSpace::new();
+        space.add(expr!("a"));
+        space.add(expr!("b"));
+        space.add(expr!("c"));
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        assert_eq!(space.remove_all(&[expr!("a"), expr!("d"), expr!("c")]), 2);
+
+        assert_eq_no_order!(space, vec![expr!("b")]);
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Remove(sym!("a")),
+            SpaceEvent::Remove(sym!("c"))]);
+    }
+
+    #[test]
+    fn replace_atom() {
+        let mut space = GroundingSpace::new();
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        space.add(expr!("a"));
+        space.add(expr!("b"));
+        space.add(expr!("c"));
+        assert_eq!(space.replace(&expr!("b"), expr!("d")), true);
+
+        assert_eq_no_order!(space, vec![expr!("a"), expr!("d"), expr!("c")]);
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a")),
+            SpaceEvent::Add(sym!("b")), SpaceEvent::Add(sym!("c")),
+            SpaceEvent::Replace(sym!("b"), sym!("d"))]);
+    }
+
+    #[test]
+    fn replace_atom_split_replace_observer() {
+        let mut space = GroundingSpace::new();
+        space.add(expr!("a"));
+        let observer = space.common.register_observer_with_options(SpaceEventCollector::new(),
+            ObserverOptions{ split_replace: true });
+        let default_observer = space.common.register_observer(SpaceEventCollector::new());
+
+        assert_eq!(space.replace(&expr!("a"), expr!("b")), true);
+
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Remove(sym!("a")),
+            SpaceEvent::Add(sym!("b"))]);
+        assert_eq!(default_observer.borrow().events, vec![SpaceEvent::Replace(sym!("a"), sym!("b"))]);
+    }
+
+    #[test]
+    fn map_atoms_renames_symbol() {
+        fn rename(atom: &Atom) -> Atom {
+            match atom {
+                Atom::Symbol(sym) if sym.name() == "bob" => sym!("BOB"),
+                Atom::Expression(expr) => Atom::expr(expr.children().iter().map(rename).collect::<Vec<_>>()),
+                _ => atom.clone(),
+            }
+        }
+        let mut space = GroundingSpace::new();
+        space.add(expr!("friend" "bob" "ann"));
+        space.add(expr!("age" "ann" "42"));
+        space.add(expr!("bob"));
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        let mut calls = 0;
+        space.map_atoms(|atom| { calls += 1; rename(atom) });
+
+        assert_eq!(calls, 3);
+        assert_eq!(space.iter().cloned().collect::<Vec<_>>(), vec![expr!("friend" "BOB" "ann"),
+            expr!("age" "ann" "42"), expr!("BOB")]);
+        assert_eq!(observer.borrow().events, vec![
+            SpaceEvent::Replace(expr!("friend" "bob" "ann"), expr!("friend" "BOB" "ann")),
+            SpaceEvent::Replace(expr!("bob"), expr!("BOB"))]);
+        assert_eq!(space.query(&expr!("friend" x "ann")), bind_set![{x: sym!("BOB")}]);
+        assert_eq!(space.query(&expr!("friend" "bob" x)), BindingsSet::empty());
+    }
+
+    #[test]
+    fn replace_atom_not_found() {
+        let mut space = GroundingSpace::new();
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        space.add(expr!("a"));
+        assert_eq!(space.replace(&expr!("b"), expr!("d")), false);
+
+        assert_eq_no_order!(space, vec![expr!("a")]);
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a"))]);
+    }
+
+    #[test]
+    fn remove_replaced_atom() {
+        let mut space = GroundingSpace::new();
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        space.add(expr!("a"));
+        space.replace(&expr!("a"), expr!("b"));
+        assert_eq!(space.remove(&expr!("b")), true);
+
+        assert_eq_no_order!(space, Vec::<Atom>::new());
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a")),
+            SpaceEvent::Replace(expr!("a"), expr!("b")),
+            SpaceEvent::Remove(expr!("b"))]);
     }
 
-    /// Executes `query` on the space and returns variable bindings found.
-    /// Query may include sub-queries glued by [COMMA_SYMBOL] symbol.
-    /// Each [Bindings](matcher::Bindings) instance in the returned [BindingsSet]
-    /// represents single result.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use hyperon::{expr, bind_set, sym};
-    /// use hyperon::matcher::BindingsSet;
-    /// use hyperon::space::grounding::GroundingSpace;
-    ///
-    /// let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("B" "C")]);
-    /// let query = expr!("," ("A" x) (x "C"));
-    ///
-    /// let result = space.query(&query);
-    ///
-    /// assert_eq!(result, bind_set![{x: sym!("B")}]);
-    /// ```
-    pub fn query(&self, query: &Atom) -> BindingsSet {
-        match split_expr(query) {
-            // Cannot match with COMMA_SYMBOL here, because Rust allows
-            // it only when Atom has PartialEq and Eq derived.
-            Some((sym @ Atom::Symbol(_), args)) if *sym == COMMA_SYMBOL => {
-                args.fold(BindingsSet::single(),
-                    |mut acc, query| {
-                        let result = if acc.is_empty() {
-                            acc
-                        } else {
-                            acc.drain(0..).flat_map(|prev| -> BindingsSet {
-                                let query = matcher::apply_bindings_to_atom_move(query.clone(), &prev);
-                                let mut res = self.query(&query);
-                                res.drain(0..)
-                                    .flat_map(|next| next.merge_v2(&prev))
-                                    .collect()
-                            }).collect()
-                        };
-                        log::debug!("query: current result: {:?}", result);
-                        result
-                    })
-            },
-            _ => self.single_query(query),
-        }
+    #[test]
+    fn add_unique_atom() {
+        let mut space = GroundingSpace::new();
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        assert_eq!(space.add_unique(expr!("a")), true);
+        assert_eq!(space.add_unique(expr!("a")), false);
+
+        assert_eq_no_order!(space, vec![expr!("a")]);
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a"))]);
     }
 
-    /// Executes simple `query` without sub-queries on the space.
-    fn single_query(&self, query: &Atom) -> BindingsSet {
-        log::debug!("single_query: query: {}", query);
-        let mut result = BindingsSet::empty();
-        let query_vars: HashSet<&VariableAtom> = query.iter().filter_type::<&VariableAtom>().collect();
-        for i in self.index.get(&atom_to_trie_key(query)) {
-            let next = self.content.get(*i).expect(format!("Index contains absent atom: key: {:?}, position: {}", query, i).as_str());
-            let next = make_variables_unique(next.clone());
-            log::trace!("single_query: match next: {}", next);
-            for bindings in match_atoms(&next, query) {
-                let bindings = bindings.narrow_vars(&query_vars);
-                log::trace!("single_query: push result: {}", bindings);
-                result.push(bindings);
-            }
-        }
-        log::debug!("single_query: result: {:?}", result);
-        result
+    #[test]
+    fn add_unique_after_removed() {
+        let mut space = GroundingSpace::from_vec(vec![expr!("a" b)]);
+        space.remove(&expr!("a" b));
+
+        assert_eq!(space.add_unique(expr!("a" b)), true);
+        assert_eq_no_order!(space, vec![expr!("a" b)]);
     }
 
-    /// Returns the iterator over content of the space.
-    pub fn iter(&self) -> SpaceIter {
-        SpaceIter::new(GroundingSpaceIter::new(self))
+    #[test]
+    fn reserve_keeps_content() {
+        let mut space = GroundingSpace::from_vec(vec![expr!("a"), expr!("b")]);
+        space.reserve(100);
+        assert_eq_no_order!(space, vec![expr!("a"), expr!("b")]);
+        assert_eq!(space.query(&expr!("a")), BindingsSet::single());
+
+        let mut space = GroundingSpace::with_capacity(10);
+        space.add(expr!("c"));
+        assert_eq_no_order!(space, vec![expr!("c")]);
     }
 
-    /// Sets the name property for the `GroundingSpace` which can be useful for debugging
-    pub fn set_name(&mut self, name: String) {
-        self.name = Some(name);
+    #[test]
+    fn merge_spaces() {
+        let mut space = GroundingSpace::from_vec(vec![expr!("a"), expr!("b"), expr!("c")]);
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+        let other = GroundingSpace::from_vec(vec![expr!("c"), expr!("d"), expr!("e")]);
+
+        space.merge(&other);
+
+        assert_eq_no_order!(space, vec![expr!("a"), expr!("b"), expr!("c"),
+            expr!("c"), expr!("d"), expr!("e")]);
+        assert_eq_no_order!(other, vec![expr!("c"), expr!("d"), expr!("e")]);
+        assert_eq!(observer.borrow().events.len(), 3);
     }
 
-    /// Returns the name property for the `GroundingSpace`, if one has been set
-    pub fn name(&self) -> Option<&str> {
-        self.name.as_ref().map(|s| s.as_str())
+    #[test]
+    fn merge_unique_spaces() {
+        let mut space = GroundingSpace::from_vec(vec![expr!("a"), expr!("b"), expr!("c")]);
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+        let other = GroundingSpace::from_vec(vec![expr!("c"), expr!("d"), expr!("e")]);
+
+        space.merge_unique(&other);
+
+        assert_eq_no_order!(space, vec![expr!("a"), expr!("b"), expr!("c"),
+            expr!("d"), expr!("e")]);
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("d")),
+            SpaceEvent::Add(sym!("e"))]);
     }
-}
 
-impl Space for GroundingSpace {
-    fn common(&self) -> FlexRef<SpaceCommon> {
-        FlexRef::from_simple(&self.common)
+    #[test]
+    fn difference_and_intersection_overlapping() {
+        let space = GroundingSpace::from_vec(vec![expr!("a"), expr!("b" x), expr!("c"), expr!("c")]);
+        let other = GroundingSpace::from_vec(vec![expr!("b" x), expr!("c"), expr!("d")]);
+
+        assert_eq!(space.difference(&other), vec![expr!("a")]);
+        assert_eq!(space.intersection(&other), vec![expr!("b" x), expr!("c")]);
+        assert_eq!(other.difference(&space), vec![expr!("d")]);
     }
-    fn query(&self, query: &Atom) -> BindingsSet {
-        GroundingSpace::query(self, query)
+
+    #[test]
+    fn difference_and_intersection_disjoint() {
+        let mut space = GroundingSpace::from_vec(vec![expr!("a"), expr!("b"), expr!("a")]);
+        space.remove(&expr!("a"));
+        space.add(expr!("d"));
+        let other = GroundingSpace::from_vec(vec![expr!("c"), expr!("b" x)]);
+
+        assert_eq!(space.difference(&other), vec![expr!("d"), expr!("b")]);
+        assert_eq!(space.intersection(&other), Vec::<Atom>::new());
     }
-    fn atom_count(&self) -> Option<usize> {
-        Some(self.iter().count())
+
+    #[test]
+    fn query_with_negation() {
+        let space = GroundingSpace::from_vec(vec![expr!("p" "1"), expr!("q" "1"), expr!("q" "2")]);
+
+        let result = space.query(&expr!("," ("q" x) ("not" ("p" x))));
+        assert_eq!(result, bind_set![{x: sym!("2")}]);
+
+        let result = space.query(&expr!("," ("q" x) ("not" ("r" x))));
+        assert_eq_no_order!(result, vec![bind!{x: sym!("1")}, bind!{x: sym!("2")}]);
+
+        let result = space.query(&expr!("," ("q" x) ("not" ("p" y))));
+        assert!(result.is_empty());
     }
-    fn atom_iter(&self) -> Option<SpaceIter> {
-        Some(self.iter())
+
+    #[test]
+    fn query_sorted_ignores_insertion_order() {
+        let atoms = vec![expr!("p" "c" "1"), expr!("p" "a" "2"), expr!("p" "b" "1"), expr!("p" "a" "1")];
+        let a = GroundingSpace::from_vec(atoms.clone());
+        let mut b = GroundingSpace::new();
+        b.add(expr!("junk"));
+        atoms.into_iter().rev().for_each(|atom| b.add(atom));
+        b.remove(&expr!("junk"));
+
+        let query = expr!("p" x y);
+        assert_eq!(a.query_sorted(&query), b.query_sorted(&query));
+        assert_eq!(a.query_sorted(&query), vec![
+            bind!{x: sym!("a"), y: sym!("1")}, bind!{x: sym!("a"), y: sym!("2")},
+            bind!{x: sym!("b"), y: sym!("1")}, bind!{x: sym!("c"), y: sym!("1")}]);
     }
-    fn as_any(&self) -> Option<&dyn std::any::Any> {
-        Some(self)
+
+    #[test]
+    fn query_with_guard_cross_product() {
+        let space = GroundingSpace::from_vec((0..10).map(|i| expr!("n" {i})).collect());
+        let query = expr!("," ("n" x) ("n" y) ("n" z));
+
+        assert_eq!(space.query_with_guard(&query, 999),
+            Err(QueryLimitError{ max_intermediate: 999 }));
+        assert_eq!(space.query_with_guard(&query, 1000).map(|r| r.len()), Ok(1000));
+        assert_eq!(space.query_with_guard(&expr!("," ("n" x) ("n" x)), 10).map(|r| r.len()), Ok(10));
     }
-    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
-        Some(self)
+
+    struct QueryCollector {
+        queries: Vec<(Atom, usize)>,
     }
-}
 
-impl SpaceMut for GroundingSpace {
-    fn add(&mut self, atom: Atom) {
-        GroundingSpace::add(self, atom)
+    impl SpaceObserver for QueryCollector {
+        fn notify(&mut self, _event: &SpaceEvent) {}
+        fn on_query(&mut self, query: &Atom, result_count: usize) {
+            self.queries.push((query.clone(), result_count));
+        }
     }
-    fn remove(&mut self, atom: &Atom) -> bool {
-        GroundingSpace::remove(self, atom)
+
+    #[test]
+    fn observer_on_query() {
+        let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("A" "C"), expr!("B" "C")]);
+        let observer = space.common.register_observer(QueryCollector{ queries: vec![] });
+        let events = space.common.register_observer(SpaceEventCollector::new());
+
+        space.query(&expr!("A" x));
+        space.query(&expr!("," ("A" x) (x "C")));
+        space.query(&expr!("D" x));
+        space.query_with_options(&expr!("a" x), QueryOptions{ case_insensitive_symbols: true, ..Default::default() });
+        space.match_atoms_in_space(&expr!(x "C"));
+
+        assert_eq!(observer.borrow().queries, vec![(expr!("A" x), 2),
+            (expr!("," ("A" x) (x "C")), 1), (expr!("D" x), 0), (expr!("a" x), 2), (expr!(x "C"), 2)]);
+        assert!(events.borrow().events.is_empty());
     }
-    fn replace(&mut self, from: &Atom, to: Atom) -> bool {
-        GroundingSpace::replace(self, from, to)
+
+    #[test]
+    fn subscribe_query_reports_deltas() {
+        let mut space = GroundingSpace::new();
+        let deltas = Rc::new(RefCell::new(Vec::new()));
+        let deltas_ref = deltas.clone();
+        let subscription = space.common.subscribe_query(expr!("tick" n),
+            move |delta| deltas_ref.borrow_mut().push(delta));
+
+        space.add(expr!("tick" "1"));
+        space.add(expr!("tock" "1"));
+        space.add(expr!("tick" "2"));
+        space.remove(&expr!("tick" "1"));
+        space.replace(&expr!("tick" "2"), expr!("tick" "3"));
+        space.transaction(|space| {
+            space.add(expr!("tick" "4"));
+            space.remove(&expr!("tock" "1"));
+        });
+        drop(subscription);
+        space.add(expr!("tick" "5"));
+
+        assert_eq!(*deltas.borrow(), vec![
+            QueryDelta::Added(bind!{n: sym!("1")}),
+            QueryDelta::Added(bind!{n: sym!("2")}),
+            QueryDelta::Removed(bind!{n: sym!("1")}),
+            QueryDelta::Removed(bind!{n: sym!("2")}),
+            QueryDelta::Added(bind!{n: sym!("3")}),
+            QueryDelta::Added(bind!{n: sym!("4")}),
+        ]);
     }
-    fn as_space(&self) -> &dyn Space {
-        self
+
+    #[test]
+    fn restore_snapshot() {
+        let mut space = GroundingSpace::from_vec(vec![expr!("a"), expr!("b"), expr!("c")]);
+        let snapshot = space.snapshot();
+        let observer = space.common.register_observer(SpaceEventCollector::new());
+
+        space.remove(&expr!("b"));
+        space.add(expr!("d"));
+        space.add(expr!("e"));
+        space.restore(snapshot.clone());
+
+        assert_eq_no_order!(space, vec![expr!("a"), expr!("b"), expr!("c")]);
+        assert_eq!(space.query(&expr!("d")), BindingsSet::empty());
+        assert_eq!(space.query(&expr!("b")), BindingsSet::single());
+        assert_eq!(observer.borrow().events[3..], vec![SpaceEvent::Remove(expr!("d")),
+            SpaceEvent::Remove(expr!("e")), SpaceEvent::Add(expr!("b"))]);
+
+        space.restore(snapshot);
+        assert_eq!(observer.borrow().events.len(), 6);
     }
-}
 
-impl PartialEq for GroundingSpace {
-    fn eq(&self, other: &Self) -> bool {
-        self.content == other.content
+    #[test]
+    fn match_atoms_in_space_edges() {
+        let space = GroundingSpace::from_vec(vec![expr!("edge" "1" "2"), expr!("node" "1"),
+            expr!("edge" "2" "3"), expr!("edge" x x)]);
+
+        assert_eq!(space.match_atoms_in_space(&expr!("edge" a b)),
+            vec![expr!("edge" "1" "2"), expr!("edge" "2" "3"), expr!("edge" x x)]);
+        assert_eq!(space.match_atoms_in_space(&expr!("edge" "2" b)),
+            vec![expr!("edge" "2" "3"), expr!("edge" x x)]);
+        assert_eq!(space.match_atoms_in_space(&expr!("edge" "3" b)), vec![expr!("edge" x x)]);
     }
-}
 
-impl Debug for GroundingSpace {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.name {
-            Some(name) => write!(f, "GroundingSpace-{name} ({self:p})"),
-            None => write!(f, "GroundingSpace-{self:p}")
+    #[test]
+    fn query_explain_returns_source_atoms() {
+        let space = GroundingSpace::from_vec(vec![expr!("parent" "Tom" "Bob"),
+            expr!("parent" "Bob" "Ann"), expr!("parent" "Ann" x), expr!("female" "Ann")]);
+
+        let result = space.query_explain(&expr!("parent" "Tom" y));
+        assert_eq!(result, vec![(bind!{y: sym!("Bob")}, vec![expr!("parent" "Tom" "Bob")])]);
+
+        let result = space.query_explain(&expr!("," ("parent" "Tom" y) ("parent" y z) ("female" z)));
+        assert_eq!(result, vec![(bind!{y: sym!("Bob"), z: sym!("Ann")}, vec![expr!("parent" "Tom" "Bob"),
+            expr!("parent" "Bob" "Ann"), expr!("female" "Ann")])]);
+        for (_bindings, sources) in result {
+            for source in sources {
+                assert!(space.iter().any(|atom| *atom == source));
+            }
         }
     }
-}
 
-impl Display for GroundingSpace {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.name {
-            Some(name) => write!(f, "GroundingSpace-{name}"),
-            None => write!(f, "GroundingSpace-{self:p}")
+    #[test]
+    fn query_explain_notifies_once() {
+        let mut space = GroundingSpace::from_vec(vec![expr!("parent" "Tom" "Bob"),
+            expr!("parent" "Bob" "Ann"), expr!("female" "Ann")]);
+        space.enable_query_cache();
+        let observer = space.common.register_observer(QueryCollector{ queries: vec![] });
+
+        let query = expr!("," ("parent" x y) ("not" ("female" y)));
+        let result = space.query_explain(&query);
+
+        assert_eq!(result, vec![(bind!{x: sym!("Tom"), y: sym!("Bob")}, vec![expr!("parent" "Tom" "Bob")])]);
+        assert_eq!(observer.borrow().queries, vec![(query, 1)]);
+        assert_eq!(space.query_cache_stats(), Some(QueryCacheStats{ hits: 0, misses: 0 }));
+    }
+
+    #[test]
+    fn ground_query_same_as_unification() {
+        let space = GroundingSpace::from_vec(vec![expr!("edge" "1" "2"), expr!("edge" "1" "2"),
+            expr!("edge" x x), expr!("edge" "1" {2}), expr!("edge" ("1") "2")]);
+        let slow = |query: &Atom| -> BindingsSet {
+            let query_vars: HashSet<&VariableAtom> = query.iter().filter_type::<&VariableAtom>().collect();
+            space.iter().flat_map(|atom| match_atoms(&make_variables_unique(atom.clone()), query))
+                .map(|bindings| bindings.narrow_vars(&query_vars)).collect()
+        };
+
+        for query in [expr!("edge" "1" "2"), expr!("edge" "2" "2"), expr!("edge" "1" "3"),
+                expr!("edge" ("1") "2"), expr!("edge" "1" {2})] {
+            assert_eq!(space.query(&query), slow(&query), "query: {}", query);
         }
+        assert_eq!(space.query(&expr!("edge" "1" "2")).len(), 2);
+        assert_eq!(space.query(&expr!("edge" "2" "2")), BindingsSet::single());
+        assert_eq!(space.query(&expr!("edge" "1" "3")), BindingsSet::empty());
     }
-}
 
-impl Grounded for GroundingSpace {
-    fn type_(&self) -> Atom {
-        rust_type_atom::<GroundingSpace>()
+    #[test]
+    fn query_with_options_occurs_check() {
+        let space = GroundingSpace::from_vec(vec![expr!("eq" y y), expr!("eq" "a" "a")]);
+        let query = expr!("eq" x ("f" x));
+
+        let checked = space.query_with_options(&query, QueryOptions{ occurs_check: true, ..Default::default() });
+        assert_eq!(checked, vec![]);
+        assert_eq!(checked, space.query(&query).into_iter().collect::<Vec<_>>());
+
+        let unchecked = space.query_with_options(&query, QueryOptions{ occurs_check: false, ..Default::default() });
+        assert_eq!(unchecked.len(), 1);
+        assert!(unchecked[0].has_loops());
     }
 
-    fn as_match(&self) -> Option<&dyn CustomMatch> {
-        Some(self)
+    #[test]
+    fn query_with_options_case_insensitive_symbols() {
+        let space = GroundingSpace::from_vec(vec![expr!("isa" "Socrates" "Human"), expr!("isa" "Fido" "dog")]);
+        let options = QueryOptions{ case_insensitive_symbols: true, ..Default::default() };
+
+        assert_eq!(space.query_with_options(&expr!("isa" x "human"), QueryOptions::default()), vec![]);
+        assert_eq!(space.query_with_options(&expr!("isa" x "human"), options.clone()),
+            vec![bind!{x: sym!("Socrates")}]);
+        assert_eq!(space.query_with_options(&expr!("ISA" "socrates" "HUMAN"), options.clone()),
+            vec![Bindings::new()]);
+        assert_eq!(space.query_with_options(&expr!("," ("isa" x "DOG") ("isa" x y)), options),
+            vec![bind!{x: sym!("Fido"), y: sym!("dog")}]);
     }
-}
 
-impl CustomMatch for GroundingSpace {
-    fn match_(&self, other: &Atom) -> matcher::MatchResultIter {
-        Box::new(self.query(other).into_iter())
+    #[test]
+    fn query_with_options_grounded_match() {
+        let nested = Atom::gnd(GroundingSpace::from_vec(vec![expr!("A")]));
+        let space = GroundingSpace::from_vec(vec![Atom::expr([sym!("space"), nested.clone()])]);
+        let custom = QueryOptions{ grounded_match: GroundedMatchMode::Custom, ..Default::default() };
+        let identity = QueryOptions{ grounded_match: GroundedMatchMode::Identity, ..Default::default() };
+
+        assert_eq!(space.query_with_options(&expr!("space" "A"), custom.clone()), vec![bind!{}]);
+        assert_eq!(space.query_with_options(&expr!("space" "A"), identity.clone()), vec![]);
+        assert_eq!(space.query_with_options(&Atom::expr([sym!("space"), nested.clone()]), identity.clone()), vec![bind!{}]);
+        assert_eq!(space.query_with_options(&expr!("space" x), identity), vec![bind!{x: nested}]);
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    #[test]
+    fn query_renamed_restores_variables() {
+        let space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("B" "C")]);
+        let mapping = HashMap::from([(VariableAtom::new("x"), VariableAtom::new("y")),
+            (VariableAtom::new("y"), VariableAtom::new("z"))]);
 
-    struct SpaceEventCollector {
-        events: Vec<SpaceEvent>,
+        let result = space.query_renamed(&expr!("," ("A" x) (x y)), &mapping);
+
+        assert_eq!(result, vec![bind!{x: sym!("B"), y: sym!("C")}]);
     }
 
-    impl SpaceEventCollector {
-        fn new() -> Self {
-            Self{ events: Vec::new() }
-        }
+    #[test]
+    fn query_timed_cross_product() {
+        let space = GroundingSpace::from_vec((0..100).map(|i| expr!("n" {i})).collect());
+        let query = expr!("," ("n" x) ("n" y) ("n" z));
+
+        assert_eq!(space.query_timed(&query, Duration::from_nanos(1)),
+            Err(QueryTimeout{ timeout: Duration::from_nanos(1) }));
+        assert_eq!(space.query_timed(&expr!("n" x), Duration::from_secs(60)).map(|r| r.len()), Ok(100));
     }
 
-    impl SpaceObserver for SpaceEventCollector {
-        fn notify(&mut self, event: &SpaceEvent) {
-            self.events.push(event.clone());
-        }
+    #[test]
+    fn split_conjunction_returns_clauses() {
+        assert_eq!(split_conjunction(&expr!("," ("a" x) ("b" x))),
+            Some(vec![expr!("a" x), expr!("b" x)]));
+        assert_eq!(split_conjunction(&Atom::expr([COMMA_SYMBOL])), Some(vec![]));
+        assert_eq!(split_conjunction(&expr!("a" ("," x))), None);
+        assert_eq!(split_conjunction(&sym!(",")), None);
     }
 
     #[test]
-    fn add_atom() {
+    fn query_planned_reorders_conjunction() {
         let mut space = GroundingSpace::new();
-        let observer = space.common.register_observer(SpaceEventCollector::new());
+        for i in 0..20 {
+            space.add(expr!("person" {i}));
+        }
+        space.add(expr!("admin" {7}));
+        let query = expr!("," ("person" x) ("person" y) ("admin" x) ("not" ("admin" y)));
 
-        space.add(expr!("a"));
-        space.add(expr!("b"));
-        space.add(expr!("c"));
+        assert_eq!(space.query_with_guard(&query, 100), Err(QueryLimitError{ max_intermediate: 100 }));
+        let plan = space.plan_query(&query);
+        assert_eq!(plan, expr!("," ("admin" x) ("person" x) ("person" y) ("not" ("admin" y))));
+        assert_eq!(space.query_with_guard(&plan, 100).map(|r| r.len()), Ok(19));
 
-        assert_eq_no_order!(space, vec![expr!("a"), expr!("b"), expr!("c")]);
-        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a")),
-            SpaceEvent::Add(sym!("b")), SpaceEvent::Add(sym!("c"))]);
+        let planned = space.query_planned(&query);
+        assert_eq_no_order!(planned, space.query(&query));
+        assert_eq!(planned.len(), 19);
     }
 
     #[test]
-    fn remove_atom() {
-        let mut space = GroundingSpace::new();
-        let observer = space.common.register_observer(SpaceEventCollector::new());
+    fn query_planned_keeps_negation_in_place() {
+        let space = GroundingSpace::from_vec(vec![expr!("p" "a"), expr!("q" "a"), expr!("q" "b")]);
+        let query = expr!("," ("not" ("p" x)) ("q" x));
 
-        space.add(expr!("a"));
-        space.add(expr!("b"));
-        space.add(expr!("c"));
-        assert_eq!(space.remove(&expr!("b")), true);
+        assert_eq!(space.plan_query(&query), query);
+        assert_eq!(space.query(&query), BindingsSet::empty());
+        assert_eq!(space.query_planned(&query), Vec::<Bindings>::new());
+    }
 
-        assert_eq_no_order!(space, vec![expr!("a"), expr!("c")]);
-        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a")),
-            SpaceEvent::Add(sym!("b")), SpaceEvent::Add(sym!("c")),
-            SpaceEvent::Remove(sym!("b"))]);
+    #[test]
+    fn query_planned_reports_original_query() {
+        let mut space = GroundingSpace::from_vec(vec![expr!("A" "B"), expr!("B" "C"), expr!("B" "D")]);
+        space.enable_query_cache();
+        let observer = space.common.register_observer(QueryCollector{ queries: vec![] });
+        let query = expr!("," (x y) ("A" x));
+        assert_ne!(space.plan_query(&query), query);
+
+        let planned = space.query_planned(&query);
+        assert_eq_no_order!(space.query(&query), planned);
+
+        assert_eq!(observer.borrow().queries, vec![(query.clone(), 2), (query, 2)]);
+        assert_eq!(space.query_cache_stats(), Some(QueryCacheStats{ hits: 1, misses: 1 }));
     }
 
     #[test]
-    fn remove_atom_not_found() {
+    fn transaction_batches_events() {
         let mut space = GroundingSpace::new();
         let observer = space.common.register_observer(SpaceEventCollector::new());
 
-        space.add(expr!("a"));
-        assert_eq!(space.remove(&expr!("b")), false);
+        space.transaction(|space| {
+            space.add(expr!("a"));
+            space.add(expr!("b"));
+            space.transaction(|space| space.add(expr!("c")));
+            assert!(observer.borrow().events.is_empty());
+        });
 
-        assert_eq_no_order!(space, vec![expr!("a")]);
-        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a"))]);
+        assert_eq_no_order!(space, vec![expr!("a"), expr!("b"), expr!("c")]);
+        assert_eq!(observer.borrow().events, vec![SpaceEvent::Batch(vec![
+            SpaceEvent::Add(sym!("a")), SpaceEvent::Add(sym!("b")), SpaceEvent::Add(sym!("c"))])]);
+        assert_eq!(observer.borrow().events[0].flatten(), vec![&SpaceEvent::Add(sym!("a")),
+            &SpaceEvent::Add(sym!("b")), &SpaceEvent::Add(sym!("c"))]);
     }
 
     #[test]
-    fn replace_atom() {
+    fn transaction_without_changes() {
         let mut space = GroundingSpace::new();
         let observer = space.common.register_observer(SpaceEventCollector::new());
 
-        space.add(expr!("a"));
-        space.add(expr!("b"));
-        space.add(expr!("c"));
-        assert_eq!(space.replace(&expr!("b"), expr!("d")), true);
+        space.transaction(|space| space.remove(&expr!("a")));
 
-        assert_eq_no_order!(space, vec![expr!("a"), expr!("d"), expr!("c")]);
-        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a")),
-            SpaceEvent::Add(sym!("b")), SpaceEvent::Add(sym!("c")),
-            SpaceEvent::Replace(sym!("b"), sym!("d"))]);
+        assert!(observer.borrow().events.is_empty());
     }
 
     #[test]
-    fn replace_atom_not_found() {
-        let mut space = GroundingSpace::new();
-        let observer = space.common.register_observer(SpaceEventCollector::new());
+    fn debug_lists_content() {
+        let mut space = GroundingSpace::from_vec(vec![expr!("A"), expr!("B" "C")]);
+        space.remove(&expr!("A"));
+        let debug = format!("{:?}", space);
+        assert!(debug.ends_with(" { content: [(B C)] }"), "unexpected debug output: {}", debug);
+
+        let space = GroundingSpace::from_vec((0..DEBUG_ATOM_LIMIT + 2).map(|i| Atom::sym(i.to_string())).collect());
+        let debug = format!("{:?}", space);
+        assert!(debug.ends_with(&format!("{}, ... 2 more] }}", DEBUG_ATOM_LIMIT - 1)), "unexpected debug output: {}", debug);
+    }
 
-        space.add(expr!("a"));
-        assert_eq!(space.replace(&expr!("b"), expr!("d")), false);
+    #[test]
+    fn index_stats() {
+        let mut space = GroundingSpace::from_vec(vec![expr!("A"), expr!("B" "C"), expr!("B" "D")]);
+        // root, A, (, B, C, ), D, )
+        assert_eq!(space.stats(), IndexStats{ node_count: 8, max_depth: 4, value_count: 3 });
 
-        assert_eq_no_order!(space, vec![expr!("a")]);
-        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a"))]);
+        space.remove(&expr!("B" "D"));
+        assert_eq!(space.stats(), IndexStats{ node_count: 6, max_depth: 4, value_count: 2 });
     }
 
     #[test]
-    fn remove_replaced_atom() {
-        let mut space = GroundingSpace::new();
-        let observer = space.common.register_observer(SpaceEventCollector::new());
+    fn query_typed_with_separate_type_space() {
+        let types = GroundingSpace::from_vec(vec![expr!(":" "Socrates" "Human"),
+            expr!(":" "Plato" "Human"), expr!(":" "Fido" "Dog")]);
+        let space = GroundingSpace::from_vec(vec![expr!("mortal" "Socrates"),
+            expr!("mortal" "Fido"), expr!("mortal" "Plato")]);
 
-        space.add(expr!("a"));
-        space.replace(&expr!("a"), expr!("b"));
-        assert_eq!(space.remove(&expr!("b")), true);
+        let result = space.query_typed(&expr!("mortal" x), &VariableAtom::new("x"), &sym!("Human"), &types);
+        assert_eq_no_order!(result, vec![bind!{x: sym!("Socrates")}, bind!{x: sym!("Plato")}]);
 
-        assert_eq_no_order!(space, Vec::<Atom>::new());
-        assert_eq!(observer.borrow().events, vec![SpaceEvent::Add(sym!("a")),
-            SpaceEvent::Replace(expr!("a"), expr!("b")),
-            SpaceEvent::Remove(expr!("b"))]);
+        let result = space.query_typed(&expr!("mortal" x), &VariableAtom::new("y"), &sym!("Human"), &types);
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn atoms_of_type_filters_by_type() {
+        let types = GroundingSpace::from_vec(vec![expr!(":" "a" "Number"),
+            expr!(":" "b" "String"), expr!(":" "c" "Number")]);
+        let space = GroundingSpace::from_vec(vec![sym!("a"), sym!("b"), sym!("c"),
+            sym!("d"), expr!("a" "b")]);
+
+        assert_eq_no_order!(space.atoms_of_type(&sym!("Number"), &types), vec![sym!("a"), sym!("c")]);
+        assert_eq!(space.atoms_of_type(&sym!("String"), &types), vec![sym!("b")]);
+        assert!(space.atoms_of_type(&sym!("Bool"), &types).is_empty());
     }
 
     #[test]
@@ -528,6 +2918,19 @@ mod test {
         assert_eq_no_order!(second, vec![expr!("d")]);
     }
 
+    #[test]
+    fn mut_cloned_atomspace_query() {
+        let mut first = GroundingSpace::from_vec(vec![expr!("a" "b")]);
+        let mut second = first.clone();
+
+        first.add(expr!("a" "c"));
+        second.add(expr!("a" "d"));
+        second.remove(&expr!("a" "b"));
+
+        assert_eq_no_order!(first.query(&expr!("a" x)), vec![bind!{x: sym!("b")}, bind!{x: sym!("c")}]);
+        assert_eq!(second.query(&expr!("a" x)), bind_set![{x: sym!("d")}]);
+    }
+
     #[test]
     fn test_match_symbol() {
         let mut space = GroundingSpace::new();
@@ -680,6 +3083,85 @@ mod test {
         assert_eq!(result, bind_set![{x: sym!("a")}]);
     }
 
+    /// Storage without index which checks all atoms on each query
+    #[derive(Default, Clone, PartialEq)]
+    struct ScanStorage(Vec<Option<Atom>>);
+
+    impl AtomStorage for ScanStorage {
+        fn insert(&mut self, atom: Atom) -> usize {
+            self.0.push(Some(atom));
+            self.0.len() - 1
+        }
+        fn remove(&mut self, pos: usize) -> Option<Atom> {
+            self.0.get_mut(pos)?.take()
+        }
+        fn replace(&mut self, pos: usize, atom: Atom) -> Option<Atom> {
+            let slot = self.0.get_mut(pos)?;
+            slot.as_ref()?;
+            slot.replace(atom)
+        }
+        fn get(&self, pos: usize) -> Option<&Atom> {
+            self.0.get(pos)?.as_ref()
+        }
+        fn atom_count(&self) -> usize {
+            self.0.iter().flatten().count()
+        }
+        fn iter(&self) -> Box<dyn Iterator<Item=(usize, &Atom)> + '_> {
+            Box::new(self.0.iter().enumerate().filter_map(|(i, atom)| atom.as_ref().map(|atom| (i, atom))))
+        }
+        fn candidates(&self, _pattern: &Atom) -> Box<dyn Iterator<Item=usize> + '_> {
+            Box::new(self.iter().map(|(i, _atom)| i))
+        }
+    }
+
+    fn scan_space(atoms: Vec<Atom>) -> GroundingSpace<ScanStorage> {
+        let mut space = GroundingSpace::with_storage(ScanStorage::default());
+        atoms.into_iter().for_each(|atom| space.add(atom));
+        space
+    }
+
+    #[test]
+    fn alternative_storage_query() {
+        let cases = vec![
+            (vec![expr!("foo")], expr!("foo")),
+            (vec![expr!("foo")], expr!(x)),
+            (vec![expr!("+" "A" ("*" "B" "C"))], expr!("+" a ("*" b c))),
+            (vec![expr!("+" "A" ("*" "B" "C"))], expr!("+" a ("*" a c))),
+            (vec![expr!("equals" x x)], expr!("equals" y z)),
+            (vec![expr!(x x)], expr!(y (z))),
+            (vec![expr!("=" ("if" "True" then) then)], expr!("=" ("if" "True" "42") X)),
+            (vec![expr!("posesses" "Sam" "baloon"), expr!("likes" "Sam" ("blue" "stuff")), expr!("has-color" "baloon" "blue")],
+                expr!("," ("posesses" "Sam" object) ("likes" "Sam" (color "stuff")) ("has-color" object color))),
+            (vec![expr!(":" "Human" "Type"), expr!(":" "Socrates" "Human"), expr!("Cons" "Socrates" "Nil")],
+                expr!("," (":" h "Human") ("Cons" h t))),
+            (vec![expr!("man" "Socrates"), expr!("man" "Plato"), expr!("dead" "Socrates")],
+                expr!("," ("man" x) ("not" ("dead" x)))),
+        ];
+        for (atoms, query) in cases {
+            let expected = GroundingSpace::from_vec(atoms.clone()).query(&query);
+            assert_eq!(scan_space(atoms).query(&query), expected, "query: {}", query);
+        }
+    }
+
+    #[test]
+    fn alternative_storage_modification() {
+        let mut space = scan_space(vec![expr!("A" "B"), expr!("A" "C")]);
+        let events = space.common.register_observer(SpaceEventCollector::new());
+
+        assert!(space.remove(&expr!("A" "B")));
+        assert!(space.replace(&expr!("A" "C"), expr!("A" "D")));
+        space.add(expr!("E"));
+
+        assert_eq!(space.iter().cloned().collect::<Vec<_>>(), vec![expr!("A" "D"), expr!("E")]);
+        assert_eq!(space.query(&expr!("A" x)), bind_set![{x: sym!("D")}]);
+        assert_eq!(space.atom_count(), Some(2));
+        assert_eq!(events.borrow().events, vec![
+            SpaceEvent::Remove(expr!("A" "B")),
+            SpaceEvent::Replace(expr!("A" "C"), expr!("A" "D")),
+            SpaceEvent::Add(expr!("E")),
+        ]);
+    }
+
     #[test]
     fn index_atom_to_key() {
         assert_eq!(atom_to_trie_key(&Atom::sym("A")), TrieKey::from([TrieToken::Exact(SymbolAtom::new("A".into()))]));
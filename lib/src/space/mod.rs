@@ -5,11 +5,12 @@ pub mod grounding;
 
 use std::fmt::Display;
 use std::rc::{Rc, Weak};
+use std::collections::HashSet;
 use std::cell::{RefCell, Ref, RefMut};
 
 use crate::common::FlexRef;
 use crate::atom::*;
-use crate::atom::matcher::{BindingsSet, apply_bindings_to_atom_move};
+use crate::atom::matcher::{Bindings, BindingsSet, apply_bindings_to_atom_move, match_atoms};
 
 /// Contains information about space modification event.
 #[derive(Clone, Debug, PartialEq)]
@@ -20,6 +21,34 @@ pub enum SpaceEvent {
     Remove(Atom),
     /// First atom is replaced by the second one.
     Replace(Atom, Atom),
+    /// Several events delivered together, in the order they happened.
+    /// See [GroundingSpace::transaction](grounding::GroundingSpace::transaction).
+    Batch(Vec<SpaceEvent>),
+}
+
+impl SpaceEvent {
+    /// Returns the events to deliver instead of this one when
+    /// [SpaceEvent::Replace] is split: a replace is converted into
+    /// [SpaceEvent::Remove] of the old atom followed by [SpaceEvent::Add] of
+    /// the new one, replaces inside a [SpaceEvent::Batch] are converted in
+    /// place, other events are kept as is.
+    pub fn split_replace(&self) -> Vec<SpaceEvent> {
+        match self {
+            SpaceEvent::Replace(from, to) => vec![SpaceEvent::Remove(from.clone()), SpaceEvent::Add(to.clone())],
+            SpaceEvent::Batch(events) => vec![SpaceEvent::Batch(events.iter()
+                .flat_map(SpaceEvent::split_replace).collect())],
+            _ => vec![self.clone()],
+        }
+    }
+
+    /// Returns the individual events, with the contents of any [SpaceEvent::Batch]
+    /// expanded in place. Useful for observers which don't care about batching.
+    pub fn flatten(&self) -> Vec<&SpaceEvent> {
+        match self {
+            SpaceEvent::Batch(events) => events.iter().flat_map(|event| event.flatten()).collect(),
+            _ => vec![self],
+        }
+    }
 }
 
 /// Space modification event observer trait.
@@ -57,6 +86,67 @@ pub enum SpaceEvent {
 pub trait SpaceObserver {
     /// Notifies about space modification.
     fn notify(&mut self, event: &SpaceEvent);
+
+    /// Notifies about the `query` executed on the space, `result_count` is
+    /// the number of results returned. It can be used to profile the space
+    /// usage or to find queries which are worth caching. Default
+    /// implementation does nothing.
+    fn on_query(&mut self, _query: &Atom, _result_count: usize) {}
+}
+
+/// Change of the pattern matching results reported by [SpaceSubscription].
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueryDelta {
+    /// Atom matching the pattern is added into the space, bindings of the
+    /// pattern variables are returned.
+    Added(Bindings),
+    /// Atom matching the pattern is removed from the space, bindings of the
+    /// pattern variables are returned.
+    Removed(Bindings),
+}
+
+/// Observer which calls the callback each time an atom matching the pattern
+/// is added into or removed from the space. Replacing an atom is reported as
+/// removing the old atom followed by adding the new one. See
+/// [SpaceCommon::subscribe_query].
+pub struct SpaceSubscription<F: FnMut(QueryDelta)> {
+    pattern: Atom,
+    callback: F,
+}
+
+impl<F: FnMut(QueryDelta)> SpaceSubscription<F> {
+    fn match_atom(&mut self, atom: &Atom, delta: fn(Bindings) -> QueryDelta) {
+        let vars: HashSet<&VariableAtom> = self.pattern.iter().filter_type::<&VariableAtom>().collect();
+        let atom = make_variables_unique(atom.clone());
+        for bindings in match_atoms(&atom, &self.pattern) {
+            (self.callback)(delta(bindings.narrow_vars(&vars)));
+        }
+    }
+}
+
+impl<F: FnMut(QueryDelta)> SpaceObserver for SpaceSubscription<F> {
+    fn notify(&mut self, event: &SpaceEvent) {
+        for event in event.flatten() {
+            match event {
+                SpaceEvent::Add(atom) => self.match_atom(atom, QueryDelta::Added),
+                SpaceEvent::Remove(atom) => self.match_atom(atom, QueryDelta::Removed),
+                SpaceEvent::Replace(from, to) => {
+                    self.match_atom(from, QueryDelta::Removed);
+                    self.match_atom(to, QueryDelta::Added);
+                },
+                SpaceEvent::Batch(_) => unreachable!("Batch events are flattened"),
+            }
+        }
+    }
+}
+
+/// Options of the observer registration, see [SpaceCommon::register_observer_with_options].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObserverOptions {
+    /// When true the observer is notified about replacing an atom by
+    /// [SpaceEvent::Remove] of the old atom followed by [SpaceEvent::Add] of
+    /// the new atom, instead of [SpaceEvent::Replace]. False by default.
+    pub split_replace: bool,
 }
 
 /// A reference to a SpaceObserver that has been registered with a Space
@@ -109,31 +199,113 @@ impl<'a> Iterator for SpaceIter<'a> {
 /// A common object that needs to be maintained by all objects implementing the Space trait
 #[derive(Default)]
 pub struct SpaceCommon {
-    observers: RefCell<Vec<Weak<RefCell<dyn SpaceObserver>>>>,
+    observers: RefCell<Vec<(WeakObserverRef, ObserverOptions)>>,
 }
+
+type WeakObserverRef = Weak<RefCell<dyn SpaceObserver>>;
 impl SpaceCommon {
     /// Registers space modifications `observer`. Observer is automatically deregistered when
     /// the returned [SpaceObserverRef] and any clones are dropped.
     /// 
     /// See [SpaceObserver] for usage example.
     pub fn register_observer<T: SpaceObserver + 'static>(&self, observer: T) -> SpaceObserverRef<T> {
+        self.register_observer_with_options(observer, ObserverOptions::default())
+    }
+
+    /// Registers space modifications `observer` like [SpaceCommon::register_observer]
+    /// using the passed `options`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::sym;
+    /// use hyperon::space::*;
+    /// use hyperon::space::grounding::*;
+    ///
+    /// struct MyObserver {
+    ///     events: Vec<SpaceEvent>
+    /// }
+    ///
+    /// impl SpaceObserver for MyObserver {
+    ///     fn notify(&mut self, event: &SpaceEvent) {
+    ///         self.events.extend(event.flatten().into_iter().cloned());
+    ///     }
+    /// }
+    ///
+    /// let mut space = GroundingSpace::from_vec(vec![sym!("A")]);
+    /// let observer = space.common().register_observer_with_options(MyObserver{ events: Vec::new() },
+    ///     ObserverOptions{ split_replace: true });
+    ///
+    /// space.replace(&sym!("A"), sym!("B"));
+    ///
+    /// assert_eq!(observer.borrow().events, vec![SpaceEvent::Remove(sym!("A")), SpaceEvent::Add(sym!("B"))]);
+    /// ```
+    pub fn register_observer_with_options<T: SpaceObserver + 'static>(&self, observer: T, options: ObserverOptions) -> SpaceObserverRef<T> {
         let observer_ref = Rc::new(RefCell::new(observer));
-        self.observers.borrow_mut().push(Rc::downgrade(&observer_ref) as Weak<RefCell<dyn SpaceObserver>>);
+        self.observers.borrow_mut().push((Rc::downgrade(&observer_ref) as WeakObserverRef, options));
         SpaceObserverRef(observer_ref)
     }
 
+    /// Subscribes to the changes of the `pattern` matching results. The
+    /// `callback` is called with [QueryDelta::Added] when an atom matching
+    /// the `pattern` is added into the space and with [QueryDelta::Removed]
+    /// when it is removed. The subscription is cancelled when the returned
+    /// [SpaceObserverRef] and any clones are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyperon::{expr, bind, sym};
+    /// use hyperon::space::*;
+    /// use hyperon::space::grounding::GroundingSpace;
+    /// use std::rc::Rc;
+    /// use std::cell::RefCell;
+    ///
+    /// let mut space = GroundingSpace::new();
+    /// let deltas = Rc::new(RefCell::new(Vec::new()));
+    /// let deltas_ref = deltas.clone();
+    /// let _subscription = space.common().subscribe_query(expr!("tick" n),
+    ///     move |delta| deltas_ref.borrow_mut().push(delta));
+    ///
+    /// space.add(expr!("tick" "1"));
+    /// space.add(expr!("tock" "1"));
+    ///
+    /// assert_eq!(*deltas.borrow(), vec![QueryDelta::Added(bind!{n: sym!("1")})]);
+    /// ```
+    pub fn subscribe_query<F: FnMut(QueryDelta) + 'static>(&self, pattern: Atom, callback: F) -> SpaceObserverRef<SpaceSubscription<F>> {
+        self.register_observer(SpaceSubscription{ pattern, callback })
+    }
+
     /// Notifies all registered observers about space modification `event`.
     pub fn notify_all_observers(&self, event: &SpaceEvent) {
+        let mut split_event = None;
+        self.for_each_observer(|observer, options| {
+            if options.split_replace {
+                split_event.get_or_insert_with(|| event.split_replace())
+                    .iter().for_each(|event| observer.notify(event))
+            } else {
+                observer.notify(event)
+            }
+        });
+    }
+
+    /// Notifies all registered observers about `query` executed on the space
+    /// which returned `result_count` results.
+    pub fn notify_all_observers_on_query(&self, query: &Atom, result_count: usize) {
+        self.for_each_observer(|observer, _options| observer.on_query(query, result_count));
+    }
+
+    fn for_each_observer(&self, mut f: impl FnMut(&mut dyn SpaceObserver, &ObserverOptions)) {
         let mut cleanup = false;
         for observer in self.observers.borrow_mut().iter() {
-            if let Some(observer) = observer.upgrade() {
-                observer.borrow_mut().notify(event);
+            if let Some(observer_ref) = observer.0.upgrade() {
+                f(&mut *observer_ref.borrow_mut(), &observer.1);
             } else {
                 cleanup = true;
             }
         }
         if cleanup {
-            self.observers.borrow_mut().retain(|w| w.strong_count() > 0);
+            self.observers.borrow_mut().retain(|(w, _)| w.strong_count() > 0);
         }
     }
 }
@@ -232,6 +232,14 @@ class Environment:
         else:
             return None
 
+    def working_dir():
+        """Returns the working dir in the common environment"""
+        path = hp.environment_working_dir()
+        if (len(path) > 0):
+            return path
+        else:
+            return None
+
     def init_common_env(working_dir = None, config_dir = None, create_config = True, disable_config = False, is_test = False, include_paths = []):
         """Initialize the common environment with the supplied args"""
         builder = Environment.custom_env(working_dir, config_dir, create_config, disable_config, is_test, include_paths)
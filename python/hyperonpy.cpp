@@ -1046,6 +1046,9 @@ PYBIND11_MODULE(hyperonpy, m) {
     m.def("environment_config_dir", []() {
         return func_to_string_no_arg((write_to_buf_no_arg_func_t)&environment_config_dir);
     }, "Return the config_dir for the common environment");
+    m.def("environment_working_dir", []() {
+        return func_to_string_no_arg((write_to_buf_no_arg_func_t)&environment_working_dir);
+    }, "Return the working_dir for the common environment");
     m.def("env_builder_start", []() { return EnvBuilder(env_builder_start()); }, "Begin initialization of the environment");
     m.def("env_builder_use_default", []() { return EnvBuilder(env_builder_use_default()); }, "Use the common environment");
     m.def("env_builder_use_test_env", []() { return EnvBuilder(env_builder_use_test_env()); }, "Use an environment for unit testing");